@@ -0,0 +1,164 @@
+-- Upgrade a database created from an earlier schema.sql to the current schema
+--
+-- Rows written before the indexer tracked deployments are attributed to the one
+-- program it followed then:
+--
+--   psql openbook_indexer -v cluster=devnet -v program_id=opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb \
+--       -f crates/indexer/migrate.sql
+--
+-- Safe to run again: every step is skipped once applied. Migrated rows keep
+-- parser_version 1 and no position within their transaction (instruction_index 0,
+-- event_seq numbering the transaction's rows); reindexing from scratch is the only
+-- way to recover the exact positions.
+
+\set ON_ERROR_STOP on
+
+\if :{?cluster}
+\else
+\echo 'usage: psql <database> -v cluster=<cluster> -v program_id=<program> -f migrate.sql'
+\quit
+\endif
+\if :{?program_id}
+\else
+\echo 'usage: psql <database> -v cluster=<cluster> -v program_id=<program> -f migrate.sql'
+\quit
+\endif
+
+BEGIN;
+
+-- Markets: owned by a deployment, unique per deployment
+ALTER TABLE markets
+    ADD COLUMN IF NOT EXISTS cluster TEXT,
+    ADD COLUMN IF NOT EXISTS program_id TEXT;
+UPDATE markets SET cluster = :'cluster' WHERE cluster IS NULL;
+UPDATE markets SET program_id = :'program_id' WHERE program_id IS NULL;
+ALTER TABLE markets
+    ALTER COLUMN cluster SET NOT NULL,
+    ALTER COLUMN program_id SET NOT NULL,
+    DROP CONSTRAINT IF EXISTS markets_base_mint_quote_mint_key;
+
+-- Orders, trades and events: keyed by their position in the transaction
+ALTER TABLE orders
+    ADD COLUMN IF NOT EXISTS cluster TEXT,
+    ADD COLUMN IF NOT EXISTS program_id TEXT,
+    ADD COLUMN IF NOT EXISTS time_estimated BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS tx_index INTEGER,
+    ADD COLUMN IF NOT EXISTS instruction_index INTEGER,
+    ADD COLUMN IF NOT EXISTS inner_index INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS event_seq INTEGER,
+    ADD COLUMN IF NOT EXISTS commitment TEXT NOT NULL DEFAULT 'confirmed',
+    ADD COLUMN IF NOT EXISTS parser_version INTEGER NOT NULL DEFAULT 1;
+UPDATE orders o SET cluster = m.cluster, program_id = m.program_id
+    FROM markets m WHERE o.market_id = m.id AND o.cluster IS NULL;
+UPDATE orders o SET instruction_index = 0, event_seq = n.seq
+    FROM (SELECT id, ROW_NUMBER() OVER (PARTITION BY signature ORDER BY slot, order_id, id) - 1 AS seq
+          FROM orders WHERE event_seq IS NULL) n
+    WHERE o.id = n.id;
+ALTER TABLE orders
+    ALTER COLUMN cluster SET NOT NULL,
+    ALTER COLUMN program_id SET NOT NULL,
+    ALTER COLUMN instruction_index SET NOT NULL,
+    ALTER COLUMN event_seq SET NOT NULL,
+    ALTER COLUMN event_seq SET DEFAULT 0,
+    DROP CONSTRAINT IF EXISTS orders_market_id_order_id_key;
+
+ALTER TABLE trades
+    ADD COLUMN IF NOT EXISTS trade_id BIGINT,
+    ADD COLUMN IF NOT EXISTS cluster TEXT,
+    ADD COLUMN IF NOT EXISTS program_id TEXT,
+    ADD COLUMN IF NOT EXISTS time_estimated BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS tx_index INTEGER,
+    ADD COLUMN IF NOT EXISTS instruction_index INTEGER,
+    ADD COLUMN IF NOT EXISTS inner_index INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS event_seq INTEGER,
+    ADD COLUMN IF NOT EXISTS commitment TEXT NOT NULL DEFAULT 'confirmed',
+    ADD COLUMN IF NOT EXISTS parser_version INTEGER NOT NULL DEFAULT 1;
+UPDATE trades t SET cluster = m.cluster, program_id = m.program_id
+    FROM markets m WHERE t.market_id = m.id AND t.cluster IS NULL;
+UPDATE trades t SET instruction_index = 0, event_seq = n.seq
+    FROM (SELECT id, ROW_NUMBER() OVER (PARTITION BY signature ORDER BY timestamp, id) - 1 AS seq
+          FROM trades WHERE event_seq IS NULL) n
+    WHERE t.id = n.id;
+-- Trades placed nowhere in their block (all of the old schema's) are numbered from the end
+-- of the slot down, in signature order, so that their event_processor::trade_id is unique
+UPDATE trades t SET trade_id = (t.slot << 32) | ((65535 - n.rank) << 16) | LEAST(t.event_seq, 65535)
+    FROM (SELECT id, DENSE_RANK() OVER (PARTITION BY cluster, program_id, slot ORDER BY signature) - 1 AS rank
+          FROM trades WHERE tx_index IS NULL) n
+    WHERE t.id = n.id;
+ALTER TABLE trades
+    ALTER COLUMN trade_id DROP NOT NULL,
+    ALTER COLUMN cluster SET NOT NULL,
+    ALTER COLUMN program_id SET NOT NULL,
+    ALTER COLUMN instruction_index SET NOT NULL,
+    ALTER COLUMN event_seq SET NOT NULL,
+    ALTER COLUMN event_seq SET DEFAULT 0,
+    DROP CONSTRAINT IF EXISTS trades_signature_market_id_timestamp_key,
+    DROP CONSTRAINT IF EXISTS trades_trade_id_key;
+
+ALTER TABLE events
+    ADD COLUMN IF NOT EXISTS tx_index INTEGER,
+    ADD COLUMN IF NOT EXISTS time_estimated BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS instruction_index INTEGER,
+    ADD COLUMN IF NOT EXISTS inner_index INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS event_seq INTEGER,
+    ADD COLUMN IF NOT EXISTS commitment TEXT NOT NULL DEFAULT 'confirmed',
+    ADD COLUMN IF NOT EXISTS parser_version INTEGER NOT NULL DEFAULT 1;
+UPDATE events e SET instruction_index = 0, event_seq = n.seq
+    FROM (SELECT id, ROW_NUMBER() OVER (PARTITION BY signature ORDER BY id) - 1 AS seq
+          FROM events WHERE event_seq IS NULL) n
+    WHERE e.id = n.id;
+ALTER TABLE events
+    ALTER COLUMN instruction_index SET NOT NULL,
+    ALTER COLUMN event_seq SET NOT NULL,
+    ALTER COLUMN event_seq SET DEFAULT 0,
+    DROP CONSTRAINT IF EXISTS events_signature_event_type_slot_key;
+
+-- New unique keys, under the names schema.sql gives them
+DO $$
+DECLARE
+    k RECORD;
+BEGIN
+    FOR k IN SELECT * FROM (VALUES
+        ('markets', 'markets_cluster_program_id_base_mint_quote_mint_key', 'cluster, program_id, base_mint, quote_mint'),
+        ('orders', 'orders_signature_instruction_index_inner_index_event_seq_key', 'signature, instruction_index, inner_index, event_seq'),
+        ('trades', 'trades_signature_instruction_index_inner_index_event_seq_key', 'signature, instruction_index, inner_index, event_seq'),
+        ('trades', 'trades_cluster_program_id_trade_id_key', 'cluster, program_id, trade_id'),
+        ('events', 'events_signature_instruction_index_inner_index_event_seq_key', 'signature, instruction_index, inner_index, event_seq')
+    ) AS keys(table_name, constraint_name, columns)
+    LOOP
+        IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = k.constraint_name) THEN
+            EXECUTE format('ALTER TABLE %I ADD CONSTRAINT %I UNIQUE (%s)', k.table_name, k.constraint_name, k.columns);
+        END IF;
+    END LOOP;
+END $$;
+
+-- Indexer status: one checkpoint per deployment instead of a single row
+ALTER TABLE indexer_status
+    ADD COLUMN IF NOT EXISTS cluster TEXT,
+    ADD COLUMN IF NOT EXISTS program_id TEXT,
+    ADD COLUMN IF NOT EXISTS finalized_slot BIGINT,
+    ADD COLUMN IF NOT EXISTS reindex_parser_version INTEGER,
+    ADD COLUMN IF NOT EXISTS reindex_total BIGINT,
+    ADD COLUMN IF NOT EXISTS reindex_done BIGINT,
+    ADD COLUMN IF NOT EXISTS reindex_failed BIGINT,
+    ADD COLUMN IF NOT EXISTS reindex_started_at BIGINT,
+    ADD COLUMN IF NOT EXISTS reindex_updated_at BIGINT;
+-- The seed row of the old schema is not a checkpoint
+DELETE FROM indexer_status WHERE cluster IS NULL AND last_processed_signature = '';
+UPDATE indexer_status SET cluster = :'cluster', program_id = :'program_id' WHERE cluster IS NULL;
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM information_schema.columns
+               WHERE table_schema = current_schema() AND table_name = 'indexer_status' AND column_name = 'id') THEN
+        ALTER TABLE indexer_status DROP COLUMN id;
+        ALTER TABLE indexer_status
+            ALTER COLUMN cluster SET NOT NULL,
+            ALTER COLUMN program_id SET NOT NULL,
+            ADD PRIMARY KEY (cluster, program_id);
+    END IF;
+END $$;
+
+-- Tables and indexes added since
+\ir schema.sql
+
+COMMIT;
@@ -3,6 +3,8 @@
 -- Markets table
 CREATE TABLE IF NOT EXISTS markets (
     id TEXT PRIMARY KEY,
+    cluster TEXT NOT NULL, -- 'mainnet-beta', 'devnet', 'testnet' or 'localnet'
+    program_id TEXT NOT NULL, -- OpenBook deployment that owns the market
     base_mint TEXT NOT NULL,
     quote_mint TEXT NOT NULL,
     symbol TEXT NOT NULL,
@@ -11,16 +13,19 @@ CREATE TABLE IF NOT EXISTS markets (
     created_at BIGINT NOT NULL,
     updated_at BIGINT NOT NULL,
 
-    UNIQUE(base_mint, quote_mint)
+    UNIQUE(cluster, program_id, base_mint, quote_mint)
 );
 
-CREATE INDEX idx_markets_symbol ON markets(symbol);
-CREATE INDEX idx_markets_created_at ON markets(created_at);
+CREATE INDEX IF NOT EXISTS idx_markets_symbol ON markets(symbol);
+CREATE INDEX IF NOT EXISTS idx_markets_deployment ON markets(cluster, program_id);
+CREATE INDEX IF NOT EXISTS idx_markets_created_at ON markets(created_at);
 
 -- Orders table
 CREATE TABLE IF NOT EXISTS orders (
     id TEXT PRIMARY KEY,
     market_id TEXT NOT NULL REFERENCES markets(id),
+    cluster TEXT NOT NULL,
+    program_id TEXT NOT NULL,
     order_id BIGINT NOT NULL,
     user_address TEXT NOT NULL,
     side TEXT NOT NULL, -- 'bid' or 'ask'
@@ -30,23 +35,36 @@ CREATE TABLE IF NOT EXISTS orders (
     filled BIGINT NOT NULL DEFAULT 0,
     status TEXT NOT NULL, -- 'open', 'filled', 'cancelled', 'expired'
     timestamp BIGINT NOT NULL,
+    time_estimated BOOLEAN NOT NULL DEFAULT FALSE, -- timestamp estimated from neighbouring slots, the block time being unknown
     slot BIGINT NOT NULL,
+    tx_index INTEGER, -- position of the transaction in its block, NULL when unknown
     signature TEXT NOT NULL,
+    instruction_index INTEGER NOT NULL, -- top-level instruction of the transaction
+    inner_index INTEGER NOT NULL DEFAULT 0, -- 0 for the instruction itself, n for its n-th CPI
+    event_seq INTEGER NOT NULL DEFAULT 0, -- position among the transaction's OpenBook events
+    commitment TEXT NOT NULL DEFAULT 'confirmed', -- 'finalized' once the finalizer settles the slot
+    parser_version INTEGER NOT NULL DEFAULT 1, -- event_processor::PARSER_VERSION that derived the row
 
-    UNIQUE(market_id, order_id)
+    UNIQUE(signature, instruction_index, inner_index, event_seq)
 );
 
-CREATE INDEX idx_orders_market ON orders(market_id);
-CREATE INDEX idx_orders_user ON orders(user_address);
-CREATE INDEX idx_orders_status ON orders(status);
-CREATE INDEX idx_orders_timestamp ON orders(timestamp);
-CREATE INDEX idx_orders_market_status ON orders(market_id, status);
-CREATE INDEX idx_orders_user_market ON orders(user_address, market_id);
+CREATE INDEX IF NOT EXISTS idx_orders_market ON orders(market_id);
+CREATE INDEX IF NOT EXISTS idx_orders_user ON orders(user_address);
+CREATE INDEX IF NOT EXISTS idx_orders_status ON orders(status);
+CREATE INDEX IF NOT EXISTS idx_orders_timestamp ON orders(timestamp);
+CREATE INDEX IF NOT EXISTS idx_orders_market_status ON orders(market_id, status);
+CREATE INDEX IF NOT EXISTS idx_orders_user_market ON orders(user_address, market_id);
+CREATE INDEX IF NOT EXISTS idx_orders_market_order_id ON orders(market_id, order_id);
+CREATE INDEX IF NOT EXISTS idx_orders_deployment ON orders(cluster, program_id);
+CREATE INDEX IF NOT EXISTS idx_orders_position ON orders(slot, tx_index, event_seq);
 
 -- Trades table
 CREATE TABLE IF NOT EXISTS trades (
     id TEXT PRIMARY KEY,
+    trade_id BIGINT, -- monotonic within a deployment: slot, tx_index and event_seq packed (event_processor::trade_id); NULL until placed
     market_id TEXT NOT NULL REFERENCES markets(id),
+    cluster TEXT NOT NULL,
+    program_id TEXT NOT NULL,
     maker_order_id TEXT,
     taker_order_id TEXT,
     maker_address TEXT NOT NULL,
@@ -55,17 +73,27 @@ CREATE TABLE IF NOT EXISTS trades (
     price BIGINT NOT NULL,
     quantity BIGINT NOT NULL,
     timestamp BIGINT NOT NULL,
+    time_estimated BOOLEAN NOT NULL DEFAULT FALSE, -- timestamp estimated from neighbouring slots, the block time being unknown
     slot BIGINT NOT NULL,
+    tx_index INTEGER, -- position of the transaction in its block, NULL when unknown
     signature TEXT NOT NULL,
+    instruction_index INTEGER NOT NULL,
+    inner_index INTEGER NOT NULL DEFAULT 0, -- 0 for the instruction itself, n for its n-th CPI
+    event_seq INTEGER NOT NULL DEFAULT 0, -- position among the transaction's OpenBook events
+    commitment TEXT NOT NULL DEFAULT 'confirmed', -- 'finalized' once the finalizer settles the slot
+    parser_version INTEGER NOT NULL DEFAULT 1, -- event_processor::PARSER_VERSION that derived the row
 
-    UNIQUE(signature, market_id, timestamp)
+    UNIQUE(signature, instruction_index, inner_index, event_seq),
+    UNIQUE(cluster, program_id, trade_id)
 );
 
-CREATE INDEX idx_trades_market ON trades(market_id);
-CREATE INDEX idx_trades_maker ON trades(maker_address);
-CREATE INDEX idx_trades_taker ON trades(taker_address);
-CREATE INDEX idx_trades_timestamp ON trades(timestamp);
-CREATE INDEX idx_trades_market_timestamp ON trades(market_id, timestamp);
+CREATE INDEX IF NOT EXISTS idx_trades_market ON trades(market_id);
+CREATE INDEX IF NOT EXISTS idx_trades_maker ON trades(maker_address);
+CREATE INDEX IF NOT EXISTS idx_trades_taker ON trades(taker_address);
+CREATE INDEX IF NOT EXISTS idx_trades_timestamp ON trades(timestamp);
+CREATE INDEX IF NOT EXISTS idx_trades_market_timestamp ON trades(market_id, timestamp);
+CREATE INDEX IF NOT EXISTS idx_trades_deployment ON trades(cluster, program_id);
+CREATE INDEX IF NOT EXISTS idx_trades_market_trade_id ON trades(market_id, trade_id);
 
 -- Events table (raw event log)
 CREATE TABLE IF NOT EXISTS events (
@@ -75,31 +103,171 @@ CREATE TABLE IF NOT EXISTS events (
     user_address TEXT,
     signature TEXT NOT NULL,
     slot BIGINT NOT NULL,
+    tx_index INTEGER, -- position of the transaction in its block, NULL when unknown
     timestamp BIGINT NOT NULL,
+    time_estimated BOOLEAN NOT NULL DEFAULT FALSE, -- timestamp estimated from neighbouring slots, the block time being unknown
     data JSONB,
+    instruction_index INTEGER NOT NULL,
+    inner_index INTEGER NOT NULL DEFAULT 0, -- 0 for the instruction itself, n for its n-th CPI
+    event_seq INTEGER NOT NULL DEFAULT 0, -- position among the transaction's OpenBook events
+    commitment TEXT NOT NULL DEFAULT 'confirmed', -- 'finalized' once the finalizer settles the slot
+    parser_version INTEGER NOT NULL DEFAULT 1, -- event_processor::PARSER_VERSION that derived the row
 
-    UNIQUE(signature, event_type, slot)
+    UNIQUE(signature, instruction_index, inner_index, event_seq)
 );
 
-CREATE INDEX idx_events_type ON events(event_type);
-CREATE INDEX idx_events_market ON events(market_id);
-CREATE INDEX idx_events_user ON events(user_address);
-CREATE INDEX idx_events_timestamp ON events(timestamp);
-CREATE INDEX idx_events_slot ON events(slot);
+CREATE INDEX IF NOT EXISTS idx_events_type ON events(event_type);
+CREATE INDEX IF NOT EXISTS idx_events_market ON events(market_id);
+CREATE INDEX IF NOT EXISTS idx_events_user ON events(user_address);
+CREATE INDEX IF NOT EXISTS idx_events_timestamp ON events(timestamp);
+CREATE INDEX IF NOT EXISTS idx_events_slot ON events(slot);
 
--- Indexer status table
+-- Transactions whose writes have been applied (re-ingesting one is a no-op)
+CREATE TABLE IF NOT EXISTS processed_transactions (
+    signature TEXT PRIMARY KEY,
+    cluster TEXT NOT NULL,
+    program_id TEXT NOT NULL,
+    slot BIGINT NOT NULL,
+    events INTEGER NOT NULL,
+    processed_at BIGINT NOT NULL,
+    commitment TEXT NOT NULL DEFAULT 'confirmed', -- 'finalized' once the finalizer settles the slot
+    parser_version INTEGER NOT NULL DEFAULT 1 -- event_processor::PARSER_VERSION that derived the row
+);
+
+CREATE INDEX IF NOT EXISTS idx_processed_transactions_slot ON processed_transactions(slot);
+CREATE INDEX IF NOT EXISTS idx_processed_transactions_unfinalized ON processed_transactions(cluster, slot) WHERE commitment = 'confirmed';
+CREATE INDEX IF NOT EXISTS idx_processed_transactions_parser_version ON processed_transactions(cluster, program_id, parser_version);
+
+-- Archive of indexed transactions, compressed, so that rows can be derived again without RPC
+CREATE TABLE IF NOT EXISTS raw_transactions (
+    signature TEXT PRIMARY KEY,
+    cluster TEXT NOT NULL,
+    program_id TEXT NOT NULL,
+    slot BIGINT NOT NULL,
+    tx_index INTEGER, -- position in the block, when the source knows it
+    account_keys TEXT[] NOT NULL, -- lets a single market's transactions be selected
+    encoding TEXT NOT NULL, -- 'json+zlib': zlib-compressed JSON of the transaction with its status meta
+    data BYTEA NOT NULL,
+    archived_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_raw_transactions_deployment_slot ON raw_transactions(cluster, program_id, slot);
+CREATE INDEX IF NOT EXISTS idx_raw_transactions_account_keys ON raw_transactions USING GIN (account_keys);
+
+-- Dead-letter queue: transactions that failed to fetch, decode or persist
+CREATE TABLE IF NOT EXISTS failed_transactions (
+    signature TEXT PRIMARY KEY,
+    cluster TEXT NOT NULL,
+    program_id TEXT NOT NULL, -- deployment whose source delivered the transaction
+    slot BIGINT NOT NULL,
+    stage TEXT NOT NULL, -- 'fetch', 'decode' or 'persist'
+    error TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    first_failed_at BIGINT NOT NULL,
+    last_failed_at BIGINT NOT NULL,
+    next_retry_at BIGINT -- NULL once automatic retries are exhausted
+);
+
+CREATE INDEX IF NOT EXISTS idx_failed_transactions_next_retry ON failed_transactions(next_retry_at);
+
+-- Transactions that failed on-chain (meta.err set); they change no state
+CREATE TABLE IF NOT EXISTS failed_attempts (
+    signature TEXT PRIMARY KEY,
+    cluster TEXT NOT NULL,
+    program_id TEXT NOT NULL,
+    market_id TEXT, -- NULL when the failed instruction's market is not indexed
+    user_address TEXT,
+    instruction_index INTEGER, -- top-level instruction that failed
+    event_type TEXT, -- OpenBook instruction that failed, e.g. 'PlaceOrder'
+    error_code BIGINT, -- custom program error, e.g. 6029
+    error_name TEXT, -- OpenBook name of error_code, e.g. 'WouldSelfTrade'
+    error TEXT NOT NULL,
+    slot BIGINT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    time_estimated BOOLEAN NOT NULL DEFAULT FALSE, -- timestamp estimated from neighbouring slots, the block time being unknown
+    commitment TEXT NOT NULL DEFAULT 'confirmed', -- 'finalized' once the finalizer settles the slot
+    parser_version INTEGER NOT NULL DEFAULT 1 -- event_processor::PARSER_VERSION that derived the row
+);
+
+CREATE INDEX IF NOT EXISTS idx_failed_attempts_market_timestamp ON failed_attempts(market_id, timestamp);
+CREATE INDEX IF NOT EXISTS idx_failed_attempts_deployment ON failed_attempts(cluster, program_id);
+
+-- Event heap snapshots (crank lag time series)
+CREATE TABLE IF NOT EXISTS event_heap_snapshots (
+    id BIGSERIAL PRIMARY KEY,
+    market_id TEXT NOT NULL REFERENCES markets(id),
+    event_heap TEXT NOT NULL,
+    pending_events INTEGER NOT NULL,
+    fill_events INTEGER NOT NULL,
+    out_events INTEGER NOT NULL,
+    oldest_event_timestamp BIGINT, -- unix seconds, NULL when the heap is empty
+    oldest_event_age_secs BIGINT,
+    fullness DOUBLE PRECISION NOT NULL, -- pending_events / 600
+    seq_num BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    timestamp BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_heap_snapshots_market_timestamp ON event_heap_snapshots(market_id, timestamp);
+
+-- Oracle prices (time series, one row per market per poll)
+CREATE TABLE IF NOT EXISTS oracle_prices (
+    id BIGSERIAL PRIMARY KEY,
+    market_id TEXT NOT NULL REFERENCES markets(id),
+    oracle_a TEXT NOT NULL,
+    oracle_a_type TEXT NOT NULL, -- 'pyth', 'stub', 'switchboard_v1', 'switchboard_v2'
+    oracle_a_price DOUBLE PRECISION NOT NULL,
+    oracle_a_deviation DOUBLE PRECISION NOT NULL,
+    oracle_a_last_update_slot BIGINT NOT NULL,
+    oracle_b TEXT,
+    oracle_b_type TEXT,
+    oracle_b_price DOUBLE PRECISION,
+    oracle_b_deviation DOUBLE PRECISION,
+    oracle_b_last_update_slot BIGINT,
+    price DOUBLE PRECISION, -- combined price, NULL when stale or outside the confidence filter
+    price_lots BIGINT,
+    is_stale BOOLEAN NOT NULL,
+    slot BIGINT NOT NULL,
+    timestamp BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_oracle_prices_market_timestamp ON oracle_prices(market_id, timestamp);
+
+-- Vault balances (time series, real TVL per market)
+CREATE TABLE IF NOT EXISTS vault_balances (
+    id BIGSERIAL PRIMARY KEY,
+    market_id TEXT NOT NULL REFERENCES markets(id),
+    base_vault TEXT NOT NULL,
+    quote_vault TEXT NOT NULL,
+    base_amount BIGINT NOT NULL,
+    quote_amount BIGINT NOT NULL,
+    base_deposit_total BIGINT NOT NULL,
+    quote_deposit_total BIGINT NOT NULL,
+    price_lots BIGINT, -- price used to value the base vault, NULL when the book is empty
+    tvl_in_quote BIGINT,
+    slot BIGINT NOT NULL,
+    timestamp BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_vault_balances_market_timestamp ON vault_balances(market_id, timestamp);
+
+-- Indexer status table (one checkpoint per indexed program)
 CREATE TABLE IF NOT EXISTS indexer_status (
-    id INTEGER PRIMARY KEY DEFAULT 1,
+    cluster TEXT NOT NULL,
+    program_id TEXT NOT NULL,
     last_processed_slot BIGINT NOT NULL,
     last_processed_signature TEXT NOT NULL,
     last_processed_timestamp BIGINT NOT NULL,
     total_events_processed BIGINT NOT NULL DEFAULT 0,
     updated_at BIGINT NOT NULL,
+    finalized_slot BIGINT, -- slots up to here are settled: finalized or rolled back
+    -- Latest `reprocess --outdated` run: parser version it re-derives with and its progress
+    reindex_parser_version INTEGER,
+    reindex_total BIGINT,
+    reindex_done BIGINT, -- failed ones included
+    reindex_failed BIGINT,
+    reindex_started_at BIGINT,
+    reindex_updated_at BIGINT,
 
-    CHECK (id = 1) -- Only allow one row
+    PRIMARY KEY (cluster, program_id)
 );
-
--- Insert initial status
-INSERT INTO indexer_status (id, last_processed_slot, last_processed_signature, last_processed_timestamp, total_events_processed, updated_at)
-VALUES (1, 0, '', 0, 0, EXTRACT(EPOCH FROM NOW())::BIGINT * 1000)
-ON CONFLICT (id) DO NOTHING;
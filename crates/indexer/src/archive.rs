@@ -0,0 +1,50 @@
+//! Compressed copies of indexed transactions, so that history can be derived again
+//! from the database (e.g. after a parser fix) instead of refetching it over RPC
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use std::io::{Read, Write};
+
+use crate::ingest::IngestedTransaction;
+use crate::types::RawTransaction;
+
+/// `raw_transactions.encoding`: the transaction with its status meta as JSON (the same
+/// format as replay captures), zlib-compressed
+pub const ARCHIVE_ENCODING: &str = "json+zlib";
+
+/// The transaction with its status meta, compressed for `raw_transactions.data`
+pub fn compress_transaction(
+    tx: &IngestedTransaction,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let json = serde_json::to_vec(&tx.transaction)?;
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&json)?;
+    Ok(encoder.finish()?)
+}
+
+/// The archived transaction, as it was delivered by its ingestion source
+pub fn decompress_transaction(
+    raw: &RawTransaction,
+) -> Result<IngestedTransaction, Box<dyn std::error::Error>> {
+    if raw.encoding != ARCHIVE_ENCODING {
+        return Err(format!(
+            "{}: unsupported archive encoding '{}'",
+            raw.signature, raw.encoding
+        )
+        .into());
+    }
+
+    let mut json = Vec::new();
+    ZlibDecoder::new(raw.data.as_slice()).read_to_end(&mut json)?;
+    let transaction: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_slice(&json)?;
+
+    Ok(IngestedTransaction {
+        signature: raw.signature.clone(),
+        slot: transaction.slot,
+        tx_index: raw.tx_index.map(|index| index as u32),
+        transaction,
+        estimated_block_time: None,
+    })
+}
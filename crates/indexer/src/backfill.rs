@@ -0,0 +1,282 @@
+use futures::StreamExt;
+use solana_client::client_error::ClientError;
+use solana_client::rpc_config::RpcBlockConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{TransactionDetails, UiTransactionEncoding};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, warn};
+
+use crate::dead_letter;
+use crate::event_processor::{derive_writes, index_transaction};
+use crate::ingest::block_time::BlockTimeCache;
+use crate::ingest::logs::{fetch_transaction, list_signatures, BlockSignatureCache};
+use crate::programs::Deployment;
+use crate::rate_limiter::AdaptiveRateLimiter;
+use crate::rpc_pool::RpcPool;
+use crate::types::ApplyMode;
+use crate::Database;
+
+/// Blocks after `to_slot` searched for a signature to start paging back from
+const ANCHOR_BLOCKS: usize = 8;
+
+/// A window of history to (re)index
+#[derive(Debug, Clone)]
+pub struct BackfillOptions {
+    /// Program (and cluster) the transactions are indexed for
+    pub deployment: Deployment,
+    /// Address whose signatures are listed: the program, or one of its markets
+    pub address: Pubkey,
+    /// Oldest slot to include
+    pub from_slot: Option<u64>,
+    /// Newest slot to include
+    pub to_slot: Option<u64>,
+    /// Only signatures older than this one
+    pub before: Option<String>,
+    /// Only signatures newer than this one
+    pub until: Option<String>,
+    /// Report what would be written without touching the database
+    pub dry_run: bool,
+    /// Delete the rows previously derived from each transaction and derive them again
+    pub rebuild: bool,
+    /// Transactions fetched in parallel
+    pub concurrency: usize,
+}
+
+impl BackfillOptions {
+    /// Refuse unbounded windows (the full history of the program is `--reindex` territory)
+    /// and inverted slot ranges
+    pub fn validate(&self) -> Result<(), String> {
+        let bounded = self.from_slot.is_some() || self.before.is_some() || self.until.is_some();
+        if self.address == self.deployment.program_id && !bounded {
+            return Err("give a slot or signature range, or --market (use `event-listener --reindex` for the full history)".to_string());
+        }
+        if let (Some(from), Some(to)) = (self.from_slot, self.to_slot) {
+            if from > to {
+                return Err(format!("--from-slot {} is after --to-slot {}", from, to));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `slot` is within the slot range (if any)
+    pub fn contains_slot(&self, slot: u64) -> bool {
+        self.from_slot.is_none_or(|from| slot >= from) && self.to_slot.is_none_or(|to| slot <= to)
+    }
+}
+
+/// Totals of a backfill run
+#[derive(Debug, Clone, Default)]
+pub struct BackfillReport {
+    pub transactions: u64,
+    pub events: u64,
+    pub orders: u64,
+    pub trades: u64,
+    /// Already indexed and left untouched (without `rebuild`)
+    pub skipped: u64,
+    pub failed: u64,
+    /// Rows that `rebuild` deletes (or would delete, on a dry run)
+    pub replaced_events: i64,
+    pub replaced_orders: i64,
+    pub replaced_trades: i64,
+}
+
+/// Index the transactions of `options.address` within the requested window
+///
+/// Transactions are written in `Backfill` (or `Rebuild`) mode, so the live checkpoint
+/// never moves.
+pub async fn run_backfill(
+    rpc_pool: Arc<RpcPool>,
+    rate_limiter: Arc<AdaptiveRateLimiter>,
+    db: &Database,
+    options: &BackfillOptions,
+) -> Result<BackfillReport, Box<dyn std::error::Error>> {
+    let start = Instant::now();
+
+    // Page back from the end of the slot range rather than from the tip
+    let before = match (&options.before, options.to_slot) {
+        (None, Some(to_slot)) => signature_after_slot(&rpc_pool, &rate_limiter, to_slot).await?,
+        (before, _) => before.clone(),
+    };
+
+    info!("📜 Listing signatures of {}...", options.address);
+    let mut pending = list_signatures(
+        &rpc_pool,
+        &rate_limiter,
+        &options.address,
+        before,
+        options.until.clone(),
+        options.from_slot,
+    )
+    .await
+    .map_err(|e| e as Box<dyn std::error::Error>)?;
+    pending.retain(|sig| options.contains_slot(sig.slot));
+
+    info!(
+        "📜 {} transactions in range{}",
+        pending.len(),
+        match (pending.first(), pending.last()) {
+            (Some(first), Some(last)) => format!(" (slots {}-{})", first.slot, last.slot),
+            _ => String::new(),
+        }
+    );
+
+    let mut report = BackfillReport::default();
+
+    if options.rebuild {
+        let signatures: Vec<String> = pending.iter().map(|sig| sig.signature.clone()).collect();
+        let (events, orders, trades) = db.count_derived_rows(&signatures).await?;
+        report.replaced_events = events;
+        report.replaced_orders = orders;
+        report.replaced_trades = trades;
+        info!(
+            "♻️  Rebuild {} {} events, {} orders, {} trades",
+            if options.dry_run {
+                "would replace"
+            } else {
+                "replaces"
+            },
+            events,
+            orders,
+            trades
+        );
+    }
+
+    let mode = if options.rebuild {
+        ApplyMode::Rebuild
+    } else {
+        ApplyMode::Backfill
+    };
+    let total = pending.len();
+
+    let block_signatures = Arc::new(BlockSignatureCache::default());
+    let block_times = Arc::new(BlockTimeCache::default());
+    let mut fetched = futures::stream::iter(pending)
+        .map(|sig| {
+            fetch_transaction(
+                rpc_pool.clone(),
+                rate_limiter.clone(),
+                block_signatures.clone(),
+                block_times.clone(),
+                options.deployment.program_id,
+                sig,
+            )
+        })
+        .buffered(options.concurrency.max(1));
+
+    while let Some(result) = fetched.next().await {
+        let tx = match result {
+            Ok(tx) => tx,
+            Err(e) => {
+                if options.dry_run
+                    || !dead_letter::record_fetch_error(
+                        db,
+                        std::slice::from_ref(&options.deployment),
+                        e.as_ref(),
+                    )
+                    .await
+                {
+                    warn!("⚠️  {}", e);
+                }
+                report.failed += 1;
+                continue;
+            }
+        };
+
+        report.transactions += 1;
+
+        let derived = if options.dry_run {
+            let derived = derive_writes(&tx, &options.deployment, db).await;
+            if !derived.events.is_empty() {
+                info!(
+                    "  🔍 Slot {} {}: {} → {} orders, {} trades",
+                    tx.slot,
+                    tx.signature,
+                    derived.events.join(", "),
+                    derived.writes.orders.len(),
+                    derived.writes.trades.len()
+                );
+            }
+            derived
+        } else {
+            // Decode and persist failures land in the dead-letter queue
+            match index_transaction(&tx, &options.deployment, db, mode).await {
+                Ok((derived, true)) => derived,
+                Ok((_, false)) => {
+                    report.skipped += 1;
+                    continue;
+                }
+                Err(_) => {
+                    report.failed += 1;
+                    continue;
+                }
+            }
+        };
+
+        report.events += derived.events.len() as u64;
+        report.orders += derived.writes.orders.len() as u64;
+        report.trades += derived.writes.trades.len() as u64;
+
+        if report.transactions % 100 == 0 {
+            info!(
+                "⏱️  PROGRESS: {}/{} transactions in {:.1}s",
+                report.transactions,
+                total,
+                start.elapsed().as_secs_f64()
+            );
+        }
+    }
+
+    Ok(report)
+}
+
+/// A signature of the first block after `slot`, to list signatures `before`
+///
+/// `before` excludes the signature itself, so it is taken from the next block rather than
+/// from `slot`, whose transactions would otherwise be cut off. None when no later block
+/// exists yet: paging then starts at the tip.
+async fn signature_after_slot(
+    rpc_pool: &RpcPool,
+    rate_limiter: &AdaptiveRateLimiter,
+    slot: u64,
+) -> Result<Option<String>, ClientError> {
+    let commitment = rpc_pool.commitment();
+    rate_limiter.acquire().await;
+    let request_start = Instant::now();
+    let slots = rpc_pool
+        .call(|rpc| async move {
+            rpc.get_blocks_with_limit_and_commitment(slot + 1, ANCHOR_BLOCKS, commitment)
+                .await
+        })
+        .await
+        .inspect_err(|e| rate_limiter.record_error(request_start.elapsed(), e))?;
+    rate_limiter.record_success(request_start.elapsed());
+
+    let config = RpcBlockConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        transaction_details: Some(TransactionDetails::Signatures),
+        rewards: Some(false),
+        commitment: Some(commitment),
+        max_supported_transaction_version: Some(0),
+    };
+
+    for block_slot in slots {
+        rate_limiter.acquire().await;
+        let request_start = Instant::now();
+        let block = rpc_pool
+            .call(|rpc| async move { rpc.get_block_with_config(block_slot, config).await })
+            .await
+            .inspect_err(|e| rate_limiter.record_error(request_start.elapsed(), e))?;
+        rate_limiter.record_success(request_start.elapsed());
+
+        if let Some(signature) = block.signatures.and_then(|sigs| sigs.into_iter().next()) {
+            info!(
+                "📜 Paging back from slot {} (range ends at {})",
+                block_slot, slot
+            );
+            return Ok(Some(signature));
+        }
+    }
+
+    Ok(None)
+}
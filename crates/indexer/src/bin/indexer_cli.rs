@@ -0,0 +1,421 @@
+use solana_openbook_indexer::backfill::{run_backfill, BackfillOptions};
+use solana_openbook_indexer::config::Config;
+use solana_openbook_indexer::dead_letter::retry_transaction;
+use solana_openbook_indexer::event_processor::PARSER_VERSION;
+use solana_openbook_indexer::ingest::block_time::BlockTimeCache;
+use solana_openbook_indexer::ingest::logs::BlockSignatureCache;
+use solana_openbook_indexer::programs::Deployment;
+use solana_openbook_indexer::rate_limiter::AdaptiveRateLimiter;
+use solana_openbook_indexer::reprocess::{run_reprocess, ReprocessOptions};
+use solana_openbook_indexer::rpc_pool::RpcPool;
+use solana_openbook_indexer::{Database, DeploymentFilter};
+use solana_sdk::pubkey::Pubkey;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{error, info};
+
+const USAGE: &str = "\
+Usage: indexer-cli <command> [options] [--config <file>]
+
+Commands:
+  backfill    Index a window of history again
+  reprocess   Derive rows again from the raw transaction archive
+  failed      Inspect the dead-letter queue of transactions that failed to index
+
+Backfill options:
+  --from-slot <slot>        Oldest slot to include
+  --to-slot <slot>          Newest slot to include
+  --before <signature>      Only transactions older than this signature
+  --until <signature>       Only transactions newer than this signature
+  --market <address>        Only transactions of this market (its whole history if no other bound)
+  --program <id>            Program to backfill (default: the first configured program)
+  --dry-run                 Show what would be written, change nothing
+  --rebuild                 Delete the rows derived from each transaction and derive them again
+
+Reprocess options:
+  --from-slot <slot>        Oldest slot to include
+  --to-slot <slot>          Newest slot to include
+  --market <address>        Only transactions touching this market
+  --program <id>            Program to reprocess (default: the first configured program)
+  --outdated                Only transactions derived by an older parser version, tracking
+                            progress in indexer_status
+  --fetch-missing           With --outdated, fetch transactions missing from the archive
+                            over RPC (otherwise they are skipped and RPC is never used)
+  --dry-run                 Show what would be written, change nothing
+
+  Events, orders, trades and failed attempts are replaced. Vault and open orders
+  balances are account snapshots, not derived from transactions, and are kept.
+
+Failed commands:
+  failed list [--limit <n>]               List entries, most recent failures first
+  failed retry (<signature> | --all)      Fetch and index entries again now
+  failed discard (<signature> | --all)    Drop entries from the queue
+
+Settings come from --config <file>, $INDEXER_CONFIG or ./indexer.toml, with the
+same environment overrides as the listener (see indexer.example.toml).
+";
+
+/// Command line options, consumed as they are read
+struct Args(Vec<String>);
+
+impl Args {
+    fn flag(&mut self, name: &str) -> bool {
+        let before = self.0.len();
+        self.0.retain(|arg| arg != name);
+        self.0.len() != before
+    }
+
+    fn value<T: FromStr>(&mut self, name: &str) -> Result<Option<T>, String> {
+        let Some(position) = self.0.iter().position(|arg| arg == name) else {
+            return Ok(None);
+        };
+        if position + 1 >= self.0.len() {
+            return Err(format!("{} needs a value", name));
+        }
+        let value = self.0.remove(position + 1);
+        self.0.remove(position);
+        value
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("invalid value for {}: {}", name, value))
+    }
+
+    /// Next argument that is not an option
+    fn positional(&mut self) -> Option<String> {
+        let position = self.0.iter().position(|arg| !arg.starts_with("--"))?;
+        Some(self.0.remove(position))
+    }
+
+    /// Fail on anything that was not consumed
+    fn finish(self) -> Result<(), String> {
+        match self.0.first() {
+            Some(arg) => Err(format!("unexpected argument: {}", arg)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt().with_target(false).init();
+
+    dotenv::dotenv().ok();
+
+    let mut args = std::env::args().skip(1);
+    let command = args.next();
+    let mut args = Args(args.collect());
+
+    if matches!(
+        command.as_deref(),
+        Some("help") | Some("--help") | Some("-h")
+    ) {
+        print!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let config = match args
+        .value::<String>("--config")
+        .and_then(|path| Config::load(path.as_deref()))
+    {
+        Ok(config) => config,
+        Err(e) => {
+            error!("❌ {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match command.as_deref() {
+        Some("backfill") => backfill(&config, args).await,
+        Some("reprocess") => reprocess(&config, args).await,
+        Some("failed") => failed(&config, args).await,
+        _ => {
+            eprint!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("❌ {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn backfill(config: &Config, mut args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    let market: Option<Pubkey> = args.value("--market")?;
+    let program: Option<Pubkey> = args.value("--program")?;
+    let from_slot = args.value("--from-slot")?;
+    let to_slot = args.value("--to-slot")?;
+    let before = args.value("--before")?;
+    let until = args.value("--until")?;
+    let dry_run = args.flag("--dry-run");
+    let rebuild = args.flag("--rebuild");
+    args.finish()?;
+
+    let deployment = configured_deployment(config, program)?;
+    let options = BackfillOptions {
+        address: market.unwrap_or(deployment.program_id),
+        deployment,
+        from_slot,
+        to_slot,
+        before,
+        until,
+        dry_run,
+        rebuild,
+        concurrency: config.backfill.concurrency,
+    };
+    options.validate()?;
+
+    let (rpc_pool, rate_limiter) = connect_rpc(config).await?;
+    let db = connect_db(config).await?;
+
+    info!(
+        "🔁 Backfill of {}{}{}",
+        options.address,
+        if dry_run { " (dry run)" } else { "" },
+        if rebuild { " (rebuild)" } else { "" }
+    );
+
+    let report = run_backfill(rpc_pool, rate_limiter, &db, &options).await?;
+
+    info!(
+        "✅ {} {} transactions: {} events, {} orders, {} trades | {} already indexed | {} failed",
+        if dry_run { "Would index" } else { "Processed" },
+        report.transactions,
+        report.events,
+        report.orders,
+        report.trades,
+        report.skipped,
+        report.failed
+    );
+
+    if report.failed > 0 {
+        return Err(format!("{} transactions could not be indexed", report.failed).into());
+    }
+    Ok(())
+}
+
+async fn reprocess(config: &Config, mut args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    let market: Option<Pubkey> = args.value("--market")?;
+    let program: Option<Pubkey> = args.value("--program")?;
+    let from_slot = args.value("--from-slot")?;
+    let to_slot = args.value("--to-slot")?;
+    let outdated = args.flag("--outdated");
+    let fetch_missing = args.flag("--fetch-missing");
+    let dry_run = args.flag("--dry-run");
+    args.finish()?;
+
+    if let (Some(from), Some(to)) = (from_slot, to_slot) {
+        if from > to {
+            return Err(format!("--from-slot {} is after --to-slot {}", from, to).into());
+        }
+    }
+    if fetch_missing && !outdated {
+        return Err("--fetch-missing needs --outdated".into());
+    }
+
+    let deployment = configured_deployment(config, program)?;
+    let rpc = if fetch_missing {
+        Some(connect_rpc(config).await?)
+    } else {
+        None
+    };
+    let db = connect_db(config).await?;
+
+    let options = ReprocessOptions {
+        deployment,
+        market: market.map(|market| market.to_string()),
+        from_slot,
+        to_slot,
+        outdated,
+        dry_run,
+    };
+
+    info!(
+        "♻️  Reprocess of {}{}{}",
+        options
+            .market
+            .clone()
+            .unwrap_or_else(|| options.deployment.program()),
+        if outdated {
+            format!(" (rows older than parser version {})", PARSER_VERSION)
+        } else {
+            " from the archive".to_string()
+        },
+        if dry_run { " (dry run)" } else { "" }
+    );
+
+    let report = run_reprocess(&db, rpc, &options).await?;
+
+    info!(
+        "✅ {} {} transactions: {} events, {} orders, {} trades (replacing {} events, {} orders, {} trades) | {} fetched over RPC | {} not archived | {} failed",
+        if dry_run { "Would reprocess" } else { "Reprocessed" },
+        report.transactions,
+        report.events,
+        report.orders,
+        report.trades,
+        report.replaced_events,
+        report.replaced_orders,
+        report.replaced_trades,
+        report.fetched,
+        report.missing,
+        report.failed
+    );
+
+    if report.failed + report.missing > 0 {
+        return Err(format!(
+            "{} transactions could not be reprocessed",
+            report.failed + report.missing
+        )
+        .into());
+    }
+    Ok(())
+}
+
+async fn failed(config: &Config, mut args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    let action = args.positional();
+    let all = args.flag("--all");
+    let limit = args.value("--limit")?.unwrap_or(100);
+    let signature = args.positional();
+    args.finish()?;
+
+    let target = match (signature.as_deref(), all) {
+        (Some(_), true) => return Err("give either a signature or --all".into()),
+        (Some(signature), false) => Some(signature),
+        (None, _) => None,
+    };
+
+    let db = connect_db(config).await?;
+
+    match action.as_deref() {
+        Some("list") => {
+            let entries = db
+                .get_failed_transactions(None, &DeploymentFilter::default(), limit)
+                .await?;
+            info!("☠️  {} failed transactions", entries.len());
+            for entry in entries {
+                let next_retry = match entry.next_retry_at {
+                    Some(at) => format_timestamp(at),
+                    None => "manual only".to_string(),
+                };
+                info!(
+                    "  {} | {} on {} | slot {} | {} | {} attempts | last {} | next retry {}",
+                    entry.signature,
+                    entry.program_id,
+                    entry.cluster,
+                    entry.slot,
+                    entry.stage,
+                    entry.attempts,
+                    format_timestamp(entry.last_failed_at),
+                    next_retry
+                );
+                info!("      {}", entry.error);
+            }
+            Ok(())
+        }
+        Some("retry") => {
+            let entries = match target {
+                Some(signature) => db
+                    .get_failed_transaction(signature)
+                    .await?
+                    .into_iter()
+                    .collect(),
+                None if all => {
+                    db.get_failed_transactions(None, &DeploymentFilter::default(), i64::MAX)
+                        .await?
+                }
+                None => return Err("failed retry needs a signature or --all".into()),
+            };
+            if entries.is_empty() {
+                return Err("no matching failed transaction".into());
+            }
+
+            let (rpc_pool, rate_limiter) = connect_rpc(config).await?;
+            let deployments = config.deployments()?;
+            let block_signatures = Arc::new(BlockSignatureCache::default());
+            let block_times = Arc::new(BlockTimeCache::default());
+            let mut recovered = 0;
+            for entry in &entries {
+                match retry_transaction(
+                    rpc_pool.clone(),
+                    rate_limiter.clone(),
+                    block_signatures.clone(),
+                    block_times.clone(),
+                    &db,
+                    &deployments,
+                    entry,
+                )
+                .await
+                {
+                    Ok(()) => {
+                        recovered += 1;
+                        info!("♻️  Recovered {}", entry.signature);
+                    }
+                    Err(e) => error!("❌ Retry of {} failed: {}", entry.signature, e),
+                }
+            }
+
+            info!("✅ Recovered {}/{}", recovered, entries.len());
+            if recovered < entries.len() {
+                return Err(format!("{} retries failed", entries.len() - recovered).into());
+            }
+            Ok(())
+        }
+        Some("discard") => {
+            if target.is_none() && !all {
+                return Err("failed discard needs a signature or --all".into());
+            }
+            let discarded = db.delete_failed_transactions(target).await?;
+            info!("🗑️  Discarded {} failed transactions", discarded);
+            Ok(())
+        }
+        _ => Err("expected `failed list`, `failed retry` or `failed discard`".into()),
+    }
+}
+
+/// RPC pool and request pacing from the same settings as the listener
+async fn connect_rpc(
+    config: &Config,
+) -> Result<(Arc<RpcPool>, Arc<AdaptiveRateLimiter>), Box<dyn std::error::Error>> {
+    let rpc_pool = Arc::new(RpcPool::from_config(&config.rpc)?);
+    rpc_pool.check_health().await;
+
+    Ok((
+        rpc_pool,
+        Arc::new(AdaptiveRateLimiter::new(
+            config.backfill.rps,
+            1.0,
+            config.backfill.max_rps,
+        )),
+    ))
+}
+
+/// The configured deployment of `program`, or the first configured one
+fn configured_deployment(
+    config: &Config,
+    program: Option<Pubkey>,
+) -> Result<Deployment, Box<dyn std::error::Error>> {
+    let deployments = config.deployments()?;
+    Ok(match program {
+        Some(program) => deployments
+            .into_iter()
+            .find(|d| d.program_id == program)
+            .ok_or_else(|| format!("program {} is not configured", program))?,
+        None => deployments
+            .into_iter()
+            .next()
+            .expect("at least one program"),
+    })
+}
+
+async fn connect_db(config: &Config) -> Result<Database, Box<dyn std::error::Error>> {
+    Database::new(&config.database.url, config.database.pool_size).await
+}
+
+fn format_timestamp(millis: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| millis.to_string())
+}
@@ -1,13 +1,43 @@
-use deadpool_postgres::{Config, ManagerConfig, Pool, RecyclingMethod, Runtime};
+use deadpool_postgres::{Config, ManagerConfig, Pool, PoolConfig, RecyclingMethod, Runtime};
 use tokio_postgres::NoTls;
 
+use crate::metrics::metrics;
+
+/// Advance a program's checkpoint; never moves it back to an older slot
+const SAVE_CHECKPOINT: &str = "INSERT INTO indexer_status (cluster, program_id, last_processed_slot, last_processed_signature, last_processed_timestamp, total_events_processed, updated_at)
+     VALUES ($1, $2, $3, $4, $5, $6, $7)
+     ON CONFLICT (cluster, program_id) DO UPDATE SET
+         last_processed_slot = EXCLUDED.last_processed_slot,
+         last_processed_signature = EXCLUDED.last_processed_signature,
+         last_processed_timestamp = EXCLUDED.last_processed_timestamp,
+         total_events_processed = indexer_status.total_events_processed + EXCLUDED.total_events_processed,
+         updated_at = EXCLUDED.updated_at
+     WHERE indexer_status.last_processed_slot <= EXCLUDED.last_processed_slot";
+
+/// Transactions of deployment ($1, $2) derived by a parser older than $3, optionally only
+/// those touching market $4 (per the archive or the rows derived from them) and within
+/// slots $5..=$6
+const OUTDATED_TRANSACTIONS: &str = "FROM processed_transactions p
+     LEFT JOIN raw_transactions r ON r.signature = p.signature
+     WHERE p.cluster = $1 AND p.program_id = $2 AND p.parser_version < $3
+       AND ($4::TEXT IS NULL
+            OR r.account_keys @> ARRAY[$4::TEXT]
+            OR EXISTS (SELECT 1 FROM orders o WHERE o.signature = p.signature AND o.market_id = $4)
+            OR EXISTS (SELECT 1 FROM trades t WHERE t.signature = p.signature AND t.market_id = $4)
+            OR EXISTS (SELECT 1 FROM failed_attempts f WHERE f.signature = p.signature AND f.market_id = $4))
+       AND ($5::BIGINT IS NULL OR p.slot >= $5)
+       AND ($6::BIGINT IS NULL OR p.slot <= $6)";
+
 #[derive(Clone)]
 pub struct Database {
     pool: Pool,
 }
 
 impl Database {
-    pub async fn new(database_url: &str) -> Result<Self, Box<dyn std::error::Error>> {
+    pub async fn new(
+        database_url: &str,
+        pool_size: usize,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         // Parse the database URL
         let config = database_url.parse::<tokio_postgres::Config>()?;
 
@@ -27,6 +57,7 @@ impl Database {
         cfg.manager = Some(ManagerConfig {
             recycling_method: RecyclingMethod::Fast,
         });
+        cfg.pool = Some(PoolConfig::new(pool_size));
 
         let pool = cfg.create_pool(Some(Runtime::Tokio1), NoTls)?;
 
@@ -38,9 +69,12 @@ impl Database {
     }
 
     // Insert or update market
+    #[allow(clippy::too_many_arguments)]
     pub async fn upsert_market(
         &self,
         id: &str,
+        cluster: &str,
+        program_id: &str,
         base_mint: &str,
         quote_mint: &str,
         symbol: &str,
@@ -48,73 +82,391 @@ impl Database {
         quote_decimals: i32,
         created_at: i64,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        let _timer = metrics().db_query_duration.start_timer(&["upsert_market"]);
         let client = self.pool.get().await?;
 
         client.execute(
-            "INSERT INTO markets (id, base_mint, quote_mint, symbol, base_decimals, quote_decimals, created_at, updated_at)
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-             ON CONFLICT (id) DO UPDATE SET updated_at = $8",
-            &[&id, &base_mint, &quote_mint, &symbol, &base_decimals, &quote_decimals, &created_at, &chrono::Utc::now().timestamp_millis()],
+            "INSERT INTO markets (id, cluster, program_id, base_mint, quote_mint, symbol, base_decimals, quote_decimals, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+             ON CONFLICT (id) DO UPDATE SET updated_at = $10",
+            &[&id, &cluster, &program_id, &base_mint, &quote_mint, &symbol, &base_decimals, &quote_decimals, &created_at, &chrono::Utc::now().timestamp_millis()],
         ).await?;
 
         Ok(())
     }
 
-    // Insert order
-    pub async fn insert_order(
+    // Apply all writes derived from one transaction, plus the checkpoint in live mode, in
+    // a single Postgres transaction. Returns false (and writes nothing) if the signature
+    // was already applied, so re-ingesting a transaction is a no-op; `Rebuild` deletes the
+    // previously derived rows first instead.
+    pub async fn apply_transaction(
         &self,
-        id: &str,
-        market_id: &str,
-        order_id: i64,
-        user_address: &str,
-        side: &str,
-        order_type: &str,
-        price: i64,
-        quantity: i64,
-        timestamp: i64,
-        slot: i64,
-        signature: &str,
+        writes: &crate::types::TransactionWrites,
+        mode: crate::types::ApplyMode,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["apply_transaction"]);
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let events_count = writes.events.len() as i32;
+
+        if mode == crate::types::ApplyMode::Rebuild {
+            for table in [
+                "events",
+                "orders",
+                "trades",
+                "failed_attempts",
+                "processed_transactions",
+            ] {
+                transaction
+                    .execute(
+                        &format!("DELETE FROM {} WHERE signature = $1", table),
+                        &[&writes.signature],
+                    )
+                    .await?;
+            }
+        }
+
+        // Slots the finalizer has already settled are final as soon as they are written
+        let finalized_slot: Option<i64> = transaction
+            .query_opt(
+                "SELECT finalized_slot FROM indexer_status WHERE cluster = $1 AND program_id = $2",
+                &[&writes.cluster, &writes.program_id],
+            )
+            .await?
+            .and_then(|row| row.get(0));
+        let commitment = if finalized_slot.is_some_and(|slot| writes.slot <= slot) {
+            "finalized"
+        } else {
+            "confirmed"
+        };
+
+        let claimed = transaction
+            .execute(
+                "INSERT INTO processed_transactions (signature, cluster, program_id, slot, events, processed_at, commitment, parser_version)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                 ON CONFLICT (signature) DO NOTHING",
+                &[&writes.signature, &writes.cluster, &writes.program_id, &writes.slot, &events_count, &now, &commitment, &writes.parser_version],
+            )
+            .await?;
+        if claimed == 0 {
+            transaction.rollback().await?;
+            return Ok(false);
+        }
+
+        for event in &writes.events {
+            transaction.execute(
+                "INSERT INTO events (event_type, market_id, user_address, signature, slot, tx_index, timestamp, time_estimated, data, instruction_index, inner_index, event_seq, commitment, parser_version)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NULL, $9, $10, $11, $12, $13)
+                 ON CONFLICT (signature, instruction_index, inner_index, event_seq) DO NOTHING",
+                &[&event.event_type, &event.market_id, &event.user_address, &writes.signature, &writes.slot, &writes.tx_index, &writes.timestamp, &writes.time_estimated, &event.position.instruction_index, &event.position.inner_index, &event.position.event_seq, &commitment, &writes.parser_version],
+            ).await?;
+        }
+
+        // Orders and trades of markets the scanner has not indexed are skipped
+        for order in &writes.orders {
+            let id = format!("{}_{}", writes.signature, order.position.event_seq);
+            transaction.execute(
+                "INSERT INTO orders (id, market_id, cluster, program_id, order_id, user_address, side, order_type, price, quantity, filled, status, timestamp, time_estimated, slot, tx_index, signature, instruction_index, inner_index, event_seq, commitment, parser_version)
+                 SELECT $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, 0, 'open', $11, $12, $13, $14, $15, $16, $17, $18, $19, $20
+                 WHERE EXISTS (SELECT 1 FROM markets WHERE id = $2)
+                 ON CONFLICT (signature, instruction_index, inner_index, event_seq) DO NOTHING",
+                &[&id, &order.market_id, &writes.cluster, &writes.program_id, &order.order_id, &order.user_address, &order.side, &order.order_type, &order.price, &order.quantity, &writes.timestamp, &writes.time_estimated, &writes.slot, &writes.tx_index, &writes.signature, &order.position.instruction_index, &order.position.inner_index, &order.position.event_seq, &commitment, &writes.parser_version],
+            ).await?;
+        }
+
+        // Either unique key identifies the same fill: a trade id packs its position
+        for trade in &writes.trades {
+            let id = format!("{}_{}", writes.signature, trade.position.event_seq);
+            transaction.execute(
+                "INSERT INTO trades (id, trade_id, market_id, cluster, program_id, maker_address, taker_address, side, price, quantity, timestamp, time_estimated, slot, tx_index, signature, instruction_index, inner_index, event_seq, commitment, parser_version)
+                 SELECT $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20
+                 WHERE EXISTS (SELECT 1 FROM markets WHERE id = $3)
+                 ON CONFLICT DO NOTHING",
+                &[&id, &trade.trade_id, &trade.market_id, &writes.cluster, &writes.program_id, &trade.maker_address, &trade.taker_address, &trade.side, &trade.price, &trade.quantity, &writes.timestamp, &writes.time_estimated, &writes.slot, &writes.tx_index, &writes.signature, &trade.position.instruction_index, &trade.position.inner_index, &trade.position.event_seq, &commitment, &writes.parser_version],
+            ).await?;
+        }
+
+        // Failed on-chain: recorded against the market when the scanner indexed it
+        if let Some(attempt) = &writes.failed_attempt {
+            transaction.execute(
+                "INSERT INTO failed_attempts (signature, cluster, program_id, market_id, user_address, instruction_index, event_type, error_code, error_name, error, slot, timestamp, time_estimated, commitment, parser_version)
+                 VALUES ($1, $2, $3, (SELECT id FROM markets WHERE id = $4), $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+                 ON CONFLICT (signature) DO NOTHING",
+                &[&writes.signature, &writes.cluster, &writes.program_id, &attempt.market_id, &attempt.user_address, &attempt.instruction_index, &attempt.event_type, &attempt.error_code, &attempt.error_name, &attempt.error, &writes.slot, &writes.timestamp, &writes.time_estimated, &commitment, &writes.parser_version],
+            ).await?;
+        }
+
+        // Archived once: a rebuild derives from the same transaction
+        if let Some(raw) = &writes.raw_transaction {
+            transaction.execute(
+                "INSERT INTO raw_transactions (signature, cluster, program_id, slot, tx_index, account_keys, encoding, data, archived_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                 ON CONFLICT (signature) DO NOTHING",
+                &[&writes.signature, &writes.cluster, &writes.program_id, &writes.slot, &raw.tx_index, &raw.account_keys, &crate::archive::ARCHIVE_ENCODING, &raw.data, &now],
+            ).await?;
+        }
+
+        if mode == crate::types::ApplyMode::Live {
+            transaction
+                .execute(
+                    SAVE_CHECKPOINT,
+                    &[
+                        &writes.cluster,
+                        &writes.program_id,
+                        &writes.slot,
+                        &writes.signature,
+                        &writes.timestamp,
+                        &(events_count as i64),
+                        &now,
+                    ],
+                )
+                .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(true)
+    }
+
+    // Get a page of archived transactions of a deployment in slot order, optionally only
+    // those touching a market, resuming after the (slot, signature) of the previous page
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_raw_transactions(
+        &self,
+        cluster: &str,
+        program_id: &str,
+        market_id: Option<&str>,
+        from_slot: Option<i64>,
+        to_slot: Option<i64>,
+        after: Option<(i64, &str)>,
+        limit: i64,
+    ) -> Result<Vec<crate::types::RawTransaction>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_raw_transactions"]);
+        let client = self.pool.get().await?;
+        let (after_slot, after_signature) = after.unzip();
+
+        let rows = client
+            .query(
+                "SELECT signature, slot, tx_index, encoding, data
+                 FROM raw_transactions
+                 WHERE cluster = $1 AND program_id = $2
+                   AND ($3::TEXT IS NULL OR account_keys @> ARRAY[$3::TEXT])
+                   AND ($4::BIGINT IS NULL OR slot >= $4)
+                   AND ($5::BIGINT IS NULL OR slot <= $5)
+                   AND ($6::BIGINT IS NULL OR (slot, signature) > ($6, $7::TEXT))
+                 ORDER BY slot, signature
+                 LIMIT $8",
+                &[
+                    &cluster,
+                    &program_id,
+                    &market_id,
+                    &from_slot,
+                    &to_slot,
+                    &after_slot,
+                    &after_signature,
+                    &limit,
+                ],
+            )
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| crate::types::RawTransaction {
+                signature: row.get(0),
+                slot: row.get(1),
+                tx_index: row.get(2),
+                encoding: row.get(3),
+                data: row.get(4),
+            })
+            .collect())
+    }
+
+    // Count a deployment's transactions derived by a parser older than `parser_version`,
+    // optionally only those of a market and within a slot range
+    pub async fn count_outdated_transactions(
+        &self,
+        cluster: &str,
+        program_id: &str,
+        parser_version: i32,
+        market_id: Option<&str>,
+        from_slot: Option<i64>,
+        to_slot: Option<i64>,
+    ) -> Result<i64, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["count_outdated_transactions"]);
+        let client = self.pool.get().await?;
+
+        let row = client
+            .query_one(
+                &format!("SELECT COUNT(*) {}", OUTDATED_TRANSACTIONS),
+                &[
+                    &cluster,
+                    &program_id,
+                    &parser_version,
+                    &market_id,
+                    &from_slot,
+                    &to_slot,
+                ],
+            )
+            .await?;
+
+        Ok(row.get(0))
+    }
+
+    // Get a page of outdated transactions (see count_outdated_transactions) in slot order,
+    // with their archived copies, resuming after the (slot, signature) of the previous page
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_outdated_transactions(
+        &self,
+        cluster: &str,
+        program_id: &str,
+        parser_version: i32,
+        market_id: Option<&str>,
+        from_slot: Option<i64>,
+        to_slot: Option<i64>,
+        after: Option<(i64, &str)>,
+        limit: i64,
+    ) -> Result<Vec<crate::types::OutdatedTransaction>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_outdated_transactions"]);
+        let client = self.pool.get().await?;
+        let (after_slot, after_signature) = after.unzip();
+
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT p.signature, p.slot, r.tx_index, r.encoding, r.data {}
+                       AND ($7::BIGINT IS NULL OR (p.slot, p.signature) > ($7, $8::TEXT))
+                     ORDER BY p.slot, p.signature
+                     LIMIT $9",
+                    OUTDATED_TRANSACTIONS
+                ),
+                &[
+                    &cluster,
+                    &program_id,
+                    &parser_version,
+                    &market_id,
+                    &from_slot,
+                    &to_slot,
+                    &after_slot,
+                    &after_signature,
+                    &limit,
+                ],
+            )
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let signature: String = row.get(0);
+                let slot: i64 = row.get(1);
+                let raw =
+                    row.get::<_, Option<String>>(3)
+                        .map(|encoding| crate::types::RawTransaction {
+                            signature: signature.clone(),
+                            slot,
+                            tx_index: row.get(2),
+                            encoding,
+                            data: row.get(4),
+                        });
+                crate::types::OutdatedTransaction {
+                    signature,
+                    slot,
+                    raw,
+                }
+            })
+            .collect())
+    }
+
+    // Start tracking a re-derivation of `total` outdated transactions in indexer_status
+    pub async fn start_reindex(
+        &self,
+        cluster: &str,
+        program_id: &str,
+        parser_version: i32,
+        total: i64,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        let _timer = metrics().db_query_duration.start_timer(&["start_reindex"]);
         let client = self.pool.get().await?;
+        let now = chrono::Utc::now().timestamp_millis();
 
-        client.execute(
-            "INSERT INTO orders (id, market_id, order_id, user_address, side, order_type, price, quantity, filled, status, timestamp, slot, signature)
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 0, 'open', $9, $10, $11)
-             ON CONFLICT (market_id, order_id) DO UPDATE SET filled = orders.filled, status = orders.status",
-            &[&id, &market_id, &order_id, &user_address, &side, &order_type, &price, &quantity, &timestamp, &slot, &signature],
-        ).await?;
+        client
+            .execute(
+                "UPDATE indexer_status
+                 SET reindex_parser_version = $3, reindex_total = $4, reindex_done = 0, reindex_failed = 0,
+                     reindex_started_at = $5, reindex_updated_at = $5
+                 WHERE cluster = $1 AND program_id = $2",
+                &[&cluster, &program_id, &parser_version, &total, &now],
+            )
+            .await?;
 
         Ok(())
     }
 
-    // Log raw event
-    pub async fn log_event(
+    // Record how far the running re-derivation got
+    pub async fn update_reindex_progress(
         &self,
-        event_type: &str,
-        market_id: Option<&str>,
-        user_address: Option<&str>,
-        signature: &str,
-        slot: i64,
-        timestamp: i64,
+        cluster: &str,
+        program_id: &str,
+        done: i64,
+        failed: i64,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["update_reindex_progress"]);
         let client = self.pool.get().await?;
+        let now = chrono::Utc::now().timestamp_millis();
 
-        client.execute(
-            "INSERT INTO events (event_type, market_id, user_address, signature, slot, timestamp, data)
-             VALUES ($1, $2, $3, $4, $5, $6, NULL)
-             ON CONFLICT (signature, event_type, slot) DO NOTHING",
-            &[&event_type, &market_id, &user_address, &signature, &slot, &timestamp],
-        ).await?;
+        client
+            .execute(
+                "UPDATE indexer_status
+                 SET reindex_done = $3, reindex_failed = $4, reindex_updated_at = $5
+                 WHERE cluster = $1 AND program_id = $2",
+                &[&cluster, &program_id, &done, &failed, &now],
+            )
+            .await?;
 
         Ok(())
     }
 
-    // Get order book depth
+    // Count the events, orders and trades derived from the given transactions
+    pub async fn count_derived_rows(
+        &self,
+        signatures: &[String],
+    ) -> Result<(i64, i64, i64), Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["count_derived_rows"]);
+        let client = self.pool.get().await?;
+
+        let row = client
+            .query_one(
+                "SELECT
+                     (SELECT COUNT(*) FROM events WHERE signature = ANY($1)),
+                     (SELECT COUNT(*) FROM orders WHERE signature = ANY($1)),
+                     (SELECT COUNT(*) FROM trades WHERE signature = ANY($1))",
+                &[&signatures],
+            )
+            .await?;
+
+        Ok((row.get(0), row.get(1), row.get(2)))
+    }
+
+    // Get order book depth, from finalized orders only if asked
     pub async fn get_depth(
         &self,
         market_id: &str,
+        finalized_only: bool,
         limit: i64,
     ) -> Result<(Vec<(i64, i64)>, Vec<(i64, i64)>), Box<dyn std::error::Error>> {
+        let _timer = metrics().db_query_duration.start_timer(&["get_depth"]);
         let client = self.pool.get().await?;
 
         // Get bids (buy orders) - highest price first
@@ -123,10 +475,11 @@ impl Database {
                 "SELECT price, COALESCE(SUM(quantity - filled), 0)::bigint as total_quantity
              FROM orders
              WHERE market_id = $1 AND status = 'open' AND side = 'bid'
+               AND (NOT $3 OR commitment = 'finalized')
              GROUP BY price
              ORDER BY price DESC
              LIMIT $2",
-                &[&market_id, &limit],
+                &[&market_id, &limit, &finalized_only],
             )
             .await?;
 
@@ -136,10 +489,11 @@ impl Database {
                 "SELECT price, COALESCE(SUM(quantity - filled), 0)::bigint as total_quantity
              FROM orders
              WHERE market_id = $1 AND status = 'open' AND side = 'ask'
+               AND (NOT $3 OR commitment = 'finalized')
              GROUP BY price
              ORDER BY price ASC
              LIMIT $2",
-                &[&market_id, &limit],
+                &[&market_id, &limit, &finalized_only],
             )
             .await?;
 
@@ -161,178 +515,135 @@ impl Database {
         &self,
         user_address: &str,
         market_id: Option<&str>,
+        filter: &crate::types::DeploymentFilter,
+        finalized_only: bool,
         limit: i64,
     ) -> Result<Vec<crate::types::Order>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_user_orders"]);
         let client = self.pool.get().await?;
 
-        let rows = if let Some(market) = market_id {
-            client.query(
-                "SELECT id, market_id, order_id, user_address, side, order_type, price, quantity, filled, status, timestamp
-                 FROM orders
-                 WHERE user_address = $1 AND market_id = $2
-                 ORDER BY timestamp DESC
-                 LIMIT $3",
-                &[&user_address, &market, &limit],
-            ).await?
-        } else {
-            client.query(
-                "SELECT id, market_id, order_id, user_address, side, order_type, price, quantity, filled, status, timestamp
-                 FROM orders
-                 WHERE user_address = $1
-                 ORDER BY timestamp DESC
-                 LIMIT $2",
-                &[&user_address, &limit],
-            ).await?
-        };
-
-        let orders = rows
-            .iter()
-            .map(|row| crate::types::Order {
-                id: row.get(0),
-                market_id: row.get(1),
-                order_id: row.get(2),
-                user_address: row.get(3),
-                side: row.get(4),
-                order_type: row.get(5),
-                price: row.get(6),
-                quantity: row.get(7),
-                filled: row.get(8),
-                status: row.get(9),
-                timestamp: row.get(10),
-            })
-            .collect();
+        let rows = client.query(
+            "SELECT id, market_id, order_id, user_address, side, order_type, price, quantity, filled, status, timestamp, commitment
+             FROM orders
+             WHERE user_address = $1
+               AND ($2::TEXT IS NULL OR market_id = $2)
+               AND ($3::TEXT IS NULL OR cluster = $3)
+               AND ($4::TEXT IS NULL OR program_id = $4)
+               AND (NOT $6 OR commitment = 'finalized')
+             ORDER BY slot DESC, COALESCE(tx_index, 65535) DESC, event_seq DESC
+             LIMIT $5",
+            &[&user_address, &market_id, &filter.cluster, &filter.program_id, &limit, &finalized_only],
+        ).await?;
 
-        Ok(orders)
+        Ok(rows.iter().map(order_from_row).collect())
     }
 
-    // Get open orders
+    // Get open orders (at most 1000 when neither market nor user is given)
     pub async fn get_open_orders(
         &self,
         market_id: Option<&str>,
         user_address: Option<&str>,
+        filter: &crate::types::DeploymentFilter,
+        finalized_only: bool,
     ) -> Result<Vec<crate::types::Order>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_open_orders"]);
         let client = self.pool.get().await?;
 
-        let rows = match (market_id, user_address) {
-            (Some(market), Some(user)) => {
-                client.query(
-                    "SELECT id, market_id, order_id, user_address, side, order_type, price, quantity, filled, status, timestamp
-                     FROM orders
-                     WHERE market_id = $1 AND user_address = $2 AND status = 'open'
-                     ORDER BY timestamp DESC",
-                    &[&market, &user],
-                ).await?
-            }
-            (Some(market), None) => {
-                client.query(
-                    "SELECT id, market_id, order_id, user_address, side, order_type, price, quantity, filled, status, timestamp
-                     FROM orders
-                     WHERE market_id = $1 AND status = 'open'
-                     ORDER BY timestamp DESC",
-                    &[&market],
-                ).await?
-            }
-            (None, Some(user)) => {
-                client.query(
-                    "SELECT id, market_id, order_id, user_address, side, order_type, price, quantity, filled, status, timestamp
-                     FROM orders
-                     WHERE user_address = $1 AND status = 'open'
-                     ORDER BY timestamp DESC",
-                    &[&user],
-                ).await?
-            }
-            (None, None) => {
-                client.query(
-                    "SELECT id, market_id, order_id, user_address, side, order_type, price, quantity, filled, status, timestamp
-                     FROM orders
-                     WHERE status = 'open'
-                     ORDER BY timestamp DESC
-                     LIMIT 1000",
-                    &[],
-                ).await?
-            }
+        let limit: Option<i64> = match (market_id, user_address) {
+            (None, None) => Some(1000),
+            _ => None,
         };
 
-        let orders = rows
-            .iter()
-            .map(|row| crate::types::Order {
-                id: row.get(0),
-                market_id: row.get(1),
-                order_id: row.get(2),
-                user_address: row.get(3),
-                side: row.get(4),
-                order_type: row.get(5),
-                price: row.get(6),
-                quantity: row.get(7),
-                filled: row.get(8),
-                status: row.get(9),
-                timestamp: row.get(10),
-            })
-            .collect();
+        let rows = client.query(
+            "SELECT id, market_id, order_id, user_address, side, order_type, price, quantity, filled, status, timestamp, commitment
+             FROM orders
+             WHERE status = 'open'
+               AND ($1::TEXT IS NULL OR market_id = $1)
+               AND ($2::TEXT IS NULL OR user_address = $2)
+               AND ($3::TEXT IS NULL OR cluster = $3)
+               AND ($4::TEXT IS NULL OR program_id = $4)
+               AND (NOT $6 OR commitment = 'finalized')
+             ORDER BY slot DESC, COALESCE(tx_index, 65535) DESC, event_seq DESC
+             LIMIT $5",
+            &[&market_id, &user_address, &filter.cluster, &filter.program_id, &limit, &finalized_only],
+        ).await?;
 
-        Ok(orders)
+        Ok(rows.iter().map(order_from_row).collect())
     }
 
     // Get all markets
     pub async fn get_markets(
         &self,
+        filter: &crate::types::DeploymentFilter,
         limit: i64,
     ) -> Result<Vec<crate::types::Market>, Box<dyn std::error::Error>> {
+        let _timer = metrics().db_query_duration.start_timer(&["get_markets"]);
         let client = self.pool.get().await?;
 
         let rows = client.query(
-            "SELECT id, base_mint, quote_mint, symbol, base_decimals, quote_decimals, created_at
+            "SELECT id, cluster, program_id, base_mint, quote_mint, symbol, base_decimals, quote_decimals, created_at
              FROM markets
+             WHERE ($1::TEXT IS NULL OR cluster = $1)
+               AND ($2::TEXT IS NULL OR program_id = $2)
              ORDER BY created_at DESC
-             LIMIT $1",
-            &[&limit],
+             LIMIT $3",
+            &[&filter.cluster, &filter.program_id, &limit],
         ).await?;
 
-        let markets = rows
-            .iter()
-            .map(|row| crate::types::Market {
-                id: row.get(0),
-                base_mint: row.get(1),
-                quote_mint: row.get(2),
-                symbol: row.get(3),
-                base_decimals: row.get(4),
-                quote_decimals: row.get(5),
-                created_at: row.get(6),
-            })
-            .collect();
+        Ok(rows.iter().map(market_from_row).collect())
+    }
+
+    // Whether a market with this ID is indexed for the deployment
+    pub async fn has_market(
+        &self,
+        market_id: &str,
+        filter: &crate::types::DeploymentFilter,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let _timer = metrics().db_query_duration.start_timer(&["has_market"]);
+        let client = self.pool.get().await?;
+
+        let row = client
+            .query_one(
+                "SELECT EXISTS (
+                 SELECT 1 FROM markets
+                 WHERE id = $1
+                   AND ($2::TEXT IS NULL OR cluster = $2)
+                   AND ($3::TEXT IS NULL OR program_id = $3)
+             )",
+                &[&market_id, &filter.cluster, &filter.program_id],
+            )
+            .await?;
 
-        Ok(markets)
+        Ok(row.get(0))
     }
 
     // Get market by symbol or ID
     pub async fn get_market_by_symbol(
         &self,
         symbol: &str,
+        filter: &crate::types::DeploymentFilter,
     ) -> Result<Option<crate::types::Market>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_market_by_symbol"]);
         let client = self.pool.get().await?;
 
         // Try to find by symbol first, then by ID
         let rows = client.query(
-            "SELECT id, base_mint, quote_mint, symbol, base_decimals, quote_decimals, created_at
+            "SELECT id, cluster, program_id, base_mint, quote_mint, symbol, base_decimals, quote_decimals, created_at
              FROM markets
-             WHERE symbol = $1 OR id = $1
+             WHERE (symbol = $1 OR id = $1)
+               AND ($2::TEXT IS NULL OR cluster = $2)
+               AND ($3::TEXT IS NULL OR program_id = $3)
              LIMIT 1",
-            &[&symbol],
+            &[&symbol, &filter.cluster, &filter.program_id],
         ).await?;
 
-        if let Some(row) = rows.first() {
-            Ok(Some(crate::types::Market {
-                id: row.get(0),
-                base_mint: row.get(1),
-                quote_mint: row.get(2),
-                symbol: row.get(3),
-                base_decimals: row.get(4),
-                quote_decimals: row.get(5),
-                created_at: row.get(6),
-            }))
-        } else {
-            Ok(None)
-        }
+        Ok(rows.first().map(market_from_row))
     }
 
     // Get total bid liquidity for a market
@@ -340,6 +651,9 @@ impl Database {
         &self,
         market_id: &str,
     ) -> Result<i64, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_bid_liquidity"]);
         let client = self.pool.get().await?;
 
         let row = client
@@ -359,6 +673,9 @@ impl Database {
         &self,
         market_id: &str,
     ) -> Result<i64, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_ask_liquidity"]);
         let client = self.pool.get().await?;
 
         let row = client
@@ -378,6 +695,7 @@ impl Database {
         &self,
         market_id: &str,
     ) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+        let _timer = metrics().db_query_duration.start_timer(&["get_best_bid"]);
         let client = self.pool.get().await?;
 
         let rows = client
@@ -399,6 +717,7 @@ impl Database {
         &self,
         market_id: &str,
     ) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+        let _timer = metrics().db_query_duration.start_timer(&["get_best_ask"]);
         let client = self.pool.get().await?;
 
         let rows = client
@@ -420,6 +739,9 @@ impl Database {
         &self,
         market_id: &str,
     ) -> Result<String, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_market_symbol"]);
         let client = self.pool.get().await?;
 
         let rows = client
@@ -436,126 +758,98 @@ impl Database {
         }
     }
 
-    // Insert trade
-    pub async fn insert_trade(
-        &self,
-        id: &str,
-        market_id: &str,
-        maker_address: &str,
-        taker_address: &str,
-        side: &str,
-        price: i64,
-        quantity: i64,
-        timestamp: i64,
-        slot: i64,
-        signature: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let client = self.pool.get().await?;
-
-        client.execute(
-            "INSERT INTO trades (id, market_id, maker_address, taker_address, side, price, quantity, timestamp, slot, signature)
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
-             ON CONFLICT (signature, market_id, timestamp) DO NOTHING",
-            &[&id, &market_id, &maker_address, &taker_address, &side, &price, &quantity, &timestamp, &slot, &signature],
-        ).await?;
-
-        Ok(())
-    }
-
-    // Get trades for a market
+    // Get trades for a market in trade id (chain) order, finalized ones only if asked,
+    // starting at trade id `from_id` of the market's deployment if given
     pub async fn get_trades(
         &self,
-        market_id: &str,
+        market: &crate::types::Market,
+        finalized_only: bool,
+        from_id: Option<i64>,
         limit: i64,
         order_by: Option<&str>,
     ) -> Result<Vec<crate::types::Trade>, Box<dyn std::error::Error>> {
+        let _timer = metrics().db_query_duration.start_timer(&["get_trades"]);
         let client = self.pool.get().await?;
 
-        // Determine sort order (default: DESC for most recent first)
-        let order_clause = match order_by {
-            Some("asc") | Some("ASC") => "ORDER BY timestamp ASC",
-            _ => "ORDER BY timestamp DESC",
-        };
-
         let query = format!(
-            "SELECT id, market_id, price, quantity, side, timestamp
+            "SELECT {}
              FROM trades
              WHERE market_id = $1
+               AND cluster = $5
+               AND program_id = $6
+               AND (NOT $3 OR commitment = 'finalized')
+               AND ($4::BIGINT IS NULL OR trade_id >= $4)
              {}
              LIMIT $2",
-            order_clause
+            TRADE_COLUMNS,
+            trade_order_clause(order_by, from_id)
         );
 
-        let rows = client.query(&query, &[&market_id, &limit]).await?;
-
-        let trades = rows
-            .iter()
-            .map(|row| crate::types::Trade {
-                id: row.get(0),
-                market_id: row.get(1),
-                price: row.get(2),
-                quantity: row.get(3),
-                side: row.get(4),
-                timestamp: row.get(5),
-            })
-            .collect();
+        let rows = client
+            .query(
+                &query,
+                &[
+                    &market.id,
+                    &limit,
+                    &finalized_only,
+                    &from_id,
+                    &market.cluster,
+                    &market.program_id,
+                ],
+            )
+            .await?;
 
-        Ok(trades)
+        Ok(rows.iter().map(trade_from_row).collect())
     }
 
-    // Get trades by user (maker or taker)
+    // Get trades by user (maker or taker), like get_trades; `from_id` is only unique
+    // within one deployment, so callers paging with it narrow `filter` to one
+    #[allow(clippy::too_many_arguments)]
     pub async fn get_user_trades(
         &self,
         user_address: &str,
         market_id: Option<&str>,
+        filter: &crate::types::DeploymentFilter,
+        finalized_only: bool,
+        from_id: Option<i64>,
         limit: i64,
         order_by: Option<&str>,
     ) -> Result<Vec<crate::types::Trade>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_user_trades"]);
         let client = self.pool.get().await?;
 
-        // Determine sort order (default: DESC for most recent first)
-        let order_clause = match order_by {
-            Some("asc") | Some("ASC") => "ORDER BY timestamp ASC",
-            _ => "ORDER BY timestamp DESC",
-        };
-
-        let rows = if let Some(market) = market_id {
-            let query = format!(
-                "SELECT id, market_id, price, quantity, side, timestamp
-                 FROM trades
-                 WHERE (maker_address = $1 OR taker_address = $1) AND market_id = $2
-                 {}
-                 LIMIT $3",
-                order_clause
-            );
-            client
-                .query(&query, &[&user_address, &market, &limit])
-                .await?
-        } else {
-            let query = format!(
-                "SELECT id, market_id, price, quantity, side, timestamp
-                 FROM trades
-                 WHERE maker_address = $1 OR taker_address = $1
-                 {}
-                 LIMIT $2",
-                order_clause
-            );
-            client.query(&query, &[&user_address, &limit]).await?
-        };
-
-        let trades = rows
-            .iter()
-            .map(|row| crate::types::Trade {
-                id: row.get(0),
-                market_id: row.get(1),
-                price: row.get(2),
-                quantity: row.get(3),
-                side: row.get(4),
-                timestamp: row.get(5),
-            })
-            .collect();
+        let query = format!(
+            "SELECT {}
+             FROM trades
+             WHERE (maker_address = $1 OR taker_address = $1)
+               AND ($2::TEXT IS NULL OR market_id = $2)
+               AND ($3::TEXT IS NULL OR cluster = $3)
+               AND ($4::TEXT IS NULL OR program_id = $4)
+               AND (NOT $6 OR commitment = 'finalized')
+               AND ($7::BIGINT IS NULL OR trade_id >= $7)
+             {}
+             LIMIT $5",
+            TRADE_COLUMNS,
+            trade_order_clause(order_by, from_id)
+        );
+        let rows = client
+            .query(
+                &query,
+                &[
+                    &user_address,
+                    &market_id,
+                    &filter.cluster,
+                    &filter.program_id,
+                    &limit,
+                    &finalized_only,
+                    &from_id,
+                ],
+            )
+            .await?;
 
-        Ok(trades)
+        Ok(rows.iter().map(trade_from_row).collect())
     }
 
     // Get user's open order value by asset
@@ -563,6 +857,9 @@ impl Database {
         &self,
         user_address: &str,
     ) -> Result<Vec<(String, i64, i64)>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_user_open_order_value"]);
         let client = self.pool.get().await?;
 
         // Get aggregated open order values grouped by market
@@ -598,6 +895,9 @@ impl Database {
         &self,
         user_address: &str,
     ) -> Result<i64, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_user_24h_volume"]);
         let client = self.pool.get().await?;
 
         let now = chrono::Utc::now().timestamp_millis();
@@ -615,4 +915,991 @@ impl Database {
 
         Ok(row.get(0))
     }
+
+    // Insert event heap snapshot
+    pub async fn insert_event_heap_snapshot(
+        &self,
+        snapshot: &crate::types::EventHeapSnapshot,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["insert_event_heap_snapshot"]);
+        let client = self.pool.get().await?;
+
+        client.execute(
+            "INSERT INTO event_heap_snapshots (market_id, event_heap, pending_events, fill_events, out_events, oldest_event_timestamp, oldest_event_age_secs, fullness, seq_num, slot, timestamp)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+            &[
+                &snapshot.market_id,
+                &snapshot.event_heap,
+                &snapshot.pending_events,
+                &snapshot.fill_events,
+                &snapshot.out_events,
+                &snapshot.oldest_event_timestamp,
+                &snapshot.oldest_event_age_secs,
+                &snapshot.fullness,
+                &snapshot.seq_num,
+                &snapshot.slot,
+                &snapshot.timestamp,
+            ],
+        ).await?;
+
+        Ok(())
+    }
+
+    // Get event heap snapshots for a market (most recent first)
+    pub async fn get_event_heap_snapshots(
+        &self,
+        market_id: &str,
+        limit: i64,
+    ) -> Result<Vec<crate::types::EventHeapSnapshot>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_event_heap_snapshots"]);
+        let client = self.pool.get().await?;
+
+        let rows = client.query(
+            "SELECT market_id, event_heap, pending_events, fill_events, out_events, oldest_event_timestamp, oldest_event_age_secs, fullness, seq_num, slot, timestamp
+             FROM event_heap_snapshots
+             WHERE market_id = $1
+             ORDER BY timestamp DESC
+             LIMIT $2",
+            &[&market_id, &limit],
+        ).await?;
+
+        Ok(rows.iter().map(event_heap_snapshot_from_row).collect())
+    }
+
+    // Get the latest event heap snapshot for every market
+    pub async fn get_latest_event_heap_snapshots(
+        &self,
+    ) -> Result<Vec<crate::types::EventHeapSnapshot>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_latest_event_heap_snapshots"]);
+        let client = self.pool.get().await?;
+
+        let rows = client.query(
+            "SELECT DISTINCT ON (market_id) market_id, event_heap, pending_events, fill_events, out_events, oldest_event_timestamp, oldest_event_age_secs, fullness, seq_num, slot, timestamp
+             FROM event_heap_snapshots
+             ORDER BY market_id, timestamp DESC",
+            &[],
+        ).await?;
+
+        Ok(rows.iter().map(event_heap_snapshot_from_row).collect())
+    }
+
+    // Insert oracle price
+    pub async fn insert_oracle_price(
+        &self,
+        price: &crate::types::OraclePrice,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["insert_oracle_price"]);
+        let client = self.pool.get().await?;
+
+        client.execute(
+            "INSERT INTO oracle_prices (market_id, oracle_a, oracle_a_type, oracle_a_price, oracle_a_deviation, oracle_a_last_update_slot, oracle_b, oracle_b_type, oracle_b_price, oracle_b_deviation, oracle_b_last_update_slot, price, price_lots, is_stale, slot, timestamp)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)",
+            &[
+                &price.market_id,
+                &price.oracle_a,
+                &price.oracle_a_type,
+                &price.oracle_a_price,
+                &price.oracle_a_deviation,
+                &price.oracle_a_last_update_slot,
+                &price.oracle_b,
+                &price.oracle_b_type,
+                &price.oracle_b_price,
+                &price.oracle_b_deviation,
+                &price.oracle_b_last_update_slot,
+                &price.price,
+                &price.price_lots,
+                &price.is_stale,
+                &price.slot,
+                &price.timestamp,
+            ],
+        ).await?;
+
+        Ok(())
+    }
+
+    // Get oracle prices for a market (most recent first)
+    pub async fn get_oracle_prices(
+        &self,
+        market_id: &str,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<crate::types::OraclePrice>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_oracle_prices"]);
+        let client = self.pool.get().await?;
+
+        let rows = client.query(
+            "SELECT market_id, oracle_a, oracle_a_type, oracle_a_price, oracle_a_deviation, oracle_a_last_update_slot, oracle_b, oracle_b_type, oracle_b_price, oracle_b_deviation, oracle_b_last_update_slot, price, price_lots, is_stale, slot, timestamp
+             FROM oracle_prices
+             WHERE market_id = $1
+               AND ($2::BIGINT IS NULL OR timestamp >= $2)
+               AND ($3::BIGINT IS NULL OR timestamp <= $3)
+             ORDER BY timestamp DESC
+             LIMIT $4",
+            &[&market_id, &start_time, &end_time, &limit],
+        ).await?;
+
+        let prices = rows
+            .iter()
+            .map(|row| crate::types::OraclePrice {
+                market_id: row.get(0),
+                oracle_a: row.get(1),
+                oracle_a_type: row.get(2),
+                oracle_a_price: row.get(3),
+                oracle_a_deviation: row.get(4),
+                oracle_a_last_update_slot: row.get(5),
+                oracle_b: row.get(6),
+                oracle_b_type: row.get(7),
+                oracle_b_price: row.get(8),
+                oracle_b_deviation: row.get(9),
+                oracle_b_last_update_slot: row.get(10),
+                price: row.get(11),
+                price_lots: row.get(12),
+                is_stale: row.get(13),
+                slot: row.get(14),
+                timestamp: row.get(15),
+            })
+            .collect();
+
+        Ok(prices)
+    }
+
+    // Insert vault balance
+    pub async fn insert_vault_balance(
+        &self,
+        balance: &crate::types::VaultBalance,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["insert_vault_balance"]);
+        let client = self.pool.get().await?;
+
+        client.execute(
+            "INSERT INTO vault_balances (market_id, base_vault, quote_vault, base_amount, quote_amount, base_deposit_total, quote_deposit_total, price_lots, tvl_in_quote, slot, timestamp)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+            &[
+                &balance.market_id,
+                &balance.base_vault,
+                &balance.quote_vault,
+                &balance.base_amount,
+                &balance.quote_amount,
+                &balance.base_deposit_total,
+                &balance.quote_deposit_total,
+                &balance.price_lots,
+                &balance.tvl_in_quote,
+                &balance.slot,
+                &balance.timestamp,
+            ],
+        ).await?;
+
+        Ok(())
+    }
+
+    // Get vault balances for a market (most recent first)
+    pub async fn get_vault_balances(
+        &self,
+        market_id: &str,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<crate::types::VaultBalance>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_vault_balances"]);
+        let client = self.pool.get().await?;
+
+        let query = format!(
+            "SELECT {}
+             FROM vault_balances
+             WHERE market_id = $1
+               AND ($2::BIGINT IS NULL OR timestamp >= $2)
+               AND ($3::BIGINT IS NULL OR timestamp <= $3)
+             ORDER BY timestamp DESC
+             LIMIT $4",
+            VAULT_BALANCE_COLUMNS
+        );
+        let rows = client
+            .query(&query, &[&market_id, &start_time, &end_time, &limit])
+            .await?;
+
+        Ok(rows.iter().map(vault_balance_from_row).collect())
+    }
+
+    // Get the latest vault balance of each of `market_ids` in one query; markets never
+    // snapshotted are absent
+    pub async fn get_latest_vault_balances(
+        &self,
+        market_ids: &[String],
+    ) -> Result<Vec<crate::types::VaultBalance>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_latest_vault_balances"]);
+        let client = self.pool.get().await?;
+
+        let query = format!(
+            "SELECT DISTINCT ON (market_id) {}
+             FROM vault_balances
+             WHERE market_id = ANY($1)
+             ORDER BY market_id, timestamp DESC",
+            VAULT_BALANCE_COLUMNS
+        );
+        let rows = client.query(&query, &[&market_ids]).await?;
+
+        Ok(rows.iter().map(vault_balance_from_row).collect())
+    }
+
+    // Failed and successful transactions per market, most failures first
+    pub async fn get_market_failure_rates(
+        &self,
+        filter: &crate::types::DeploymentFilter,
+        market_id: Option<&str>,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<crate::types::MarketFailureRate>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_market_failure_rates"]);
+        let client = self.pool.get().await?;
+
+        let rows = client
+            .query(
+                "WITH failed AS (
+                 SELECT market_id, COUNT(*) AS failed
+                 FROM failed_attempts
+                 WHERE market_id IS NOT NULL
+                   AND ($1::TEXT IS NULL OR cluster = $1)
+                   AND ($2::TEXT IS NULL OR program_id = $2)
+                   AND ($3::TEXT IS NULL OR market_id = $3)
+                   AND ($4::BIGINT IS NULL OR timestamp >= $4)
+                   AND ($5::BIGINT IS NULL OR timestamp <= $5)
+                 GROUP BY market_id
+             ),
+             succeeded AS (
+                 SELECT market_id, COUNT(DISTINCT signature) AS succeeded
+                 FROM (
+                     SELECT market_id, signature, cluster, program_id, timestamp FROM orders
+                     UNION ALL
+                     SELECT market_id, signature, cluster, program_id, timestamp FROM trades
+                 ) written
+                 WHERE market_id IN (SELECT market_id FROM failed)
+                   AND ($1::TEXT IS NULL OR cluster = $1)
+                   AND ($2::TEXT IS NULL OR program_id = $2)
+                   AND ($4::BIGINT IS NULL OR timestamp >= $4)
+                   AND ($5::BIGINT IS NULL OR timestamp <= $5)
+                 GROUP BY market_id
+             )
+             SELECT f.market_id, m.symbol, f.failed, COALESCE(s.succeeded, 0)
+             FROM failed f
+             LEFT JOIN succeeded s ON s.market_id = f.market_id
+             LEFT JOIN markets m ON m.id = f.market_id
+             ORDER BY f.failed DESC, f.market_id
+             LIMIT $6",
+                &[
+                    &filter.cluster,
+                    &filter.program_id,
+                    &market_id,
+                    &start_time,
+                    &end_time,
+                    &limit,
+                ],
+            )
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let failed: i64 = row.get(2);
+                let succeeded: i64 = row.get(3);
+                crate::types::MarketFailureRate {
+                    market_id: row.get(0),
+                    symbol: row.get(1),
+                    failed,
+                    succeeded,
+                    failure_rate: failed as f64 / (failed + succeeded) as f64,
+                }
+            })
+            .collect())
+    }
+
+    // Failed attempts per error, most frequent first
+    pub async fn get_error_failure_counts(
+        &self,
+        filter: &crate::types::DeploymentFilter,
+        market_id: Option<&str>,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<crate::types::ErrorFailureCount>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_error_failure_counts"]);
+        let client = self.pool.get().await?;
+
+        let rows = client
+            .query(
+                "SELECT error_code, COALESCE(error_name, error), COUNT(*) AS failures,
+                    COUNT(*)::DOUBLE PRECISION / SUM(COUNT(*)) OVER () AS share
+             FROM failed_attempts
+             WHERE ($1::TEXT IS NULL OR cluster = $1)
+               AND ($2::TEXT IS NULL OR program_id = $2)
+               AND ($3::TEXT IS NULL OR market_id = $3)
+               AND ($4::BIGINT IS NULL OR timestamp >= $4)
+               AND ($5::BIGINT IS NULL OR timestamp <= $5)
+             GROUP BY error_code, COALESCE(error_name, error)
+             ORDER BY failures DESC, error_code
+             LIMIT $6",
+                &[
+                    &filter.cluster,
+                    &filter.program_id,
+                    &market_id,
+                    &start_time,
+                    &end_time,
+                    &limit,
+                ],
+            )
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| crate::types::ErrorFailureCount {
+                error_code: row.get(0),
+                error_name: row.get(1),
+                failures: row.get(2),
+                share: row.get(3),
+            })
+            .collect())
+    }
+
+    // Get a program's checkpoint (None until one of its transactions has been processed)
+    pub async fn get_checkpoint(
+        &self,
+        cluster: &str,
+        program_id: &str,
+    ) -> Result<Option<crate::types::Checkpoint>, Box<dyn std::error::Error>> {
+        let _timer = metrics().db_query_duration.start_timer(&["get_checkpoint"]);
+        let client = self.pool.get().await?;
+
+        let row = client
+            .query_opt(
+                "SELECT cluster, program_id, last_processed_slot, last_processed_signature, last_processed_timestamp, total_events_processed, updated_at, finalized_slot,
+                        reindex_parser_version, reindex_total, reindex_done, reindex_failed, reindex_started_at, reindex_updated_at
+                 FROM indexer_status
+                 WHERE cluster = $1 AND program_id = $2",
+                &[&cluster, &program_id],
+            )
+            .await?;
+
+        Ok(row
+            .as_ref()
+            .map(checkpoint_from_row)
+            .filter(|cp| !cp.signature.is_empty()))
+    }
+
+    // Checkpoints of every indexed program, least advanced first
+    pub async fn get_checkpoints(
+        &self,
+        filter: &crate::types::DeploymentFilter,
+    ) -> Result<Vec<crate::types::Checkpoint>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_checkpoints"]);
+        let client = self.pool.get().await?;
+
+        let rows = client
+            .query(
+                "SELECT cluster, program_id, last_processed_slot, last_processed_signature, last_processed_timestamp, total_events_processed, updated_at, finalized_slot,
+                        reindex_parser_version, reindex_total, reindex_done, reindex_failed, reindex_started_at, reindex_updated_at
+                 FROM indexer_status
+                 WHERE last_processed_signature <> ''
+                   AND ($1::TEXT IS NULL OR cluster = $1)
+                   AND ($2::TEXT IS NULL OR program_id = $2)
+                 ORDER BY last_processed_slot",
+                &[&filter.cluster, &filter.program_id],
+            )
+            .await?;
+
+        Ok(rows.iter().map(checkpoint_from_row).collect())
+    }
+
+    // Advance a program's checkpoint; never moves it back to an older slot
+    pub async fn save_checkpoint(
+        &self,
+        cluster: &str,
+        program_id: &str,
+        slot: i64,
+        signature: &str,
+        timestamp: i64,
+        events_processed: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["save_checkpoint"]);
+        let client = self.pool.get().await?;
+
+        client
+            .execute(
+                SAVE_CHECKPOINT,
+                &[
+                    &cluster,
+                    &program_id,
+                    &slot,
+                    &signature,
+                    &timestamp,
+                    &events_processed,
+                    &chrono::Utc::now().timestamp_millis(),
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    // Clear a program's checkpoint so the next backfill re-indexes its full history
+    pub async fn reset_checkpoint(
+        &self,
+        cluster: &str,
+        program_id: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["reset_checkpoint"]);
+        let client = self.pool.get().await?;
+
+        client
+            .execute(
+                "DELETE FROM indexer_status WHERE cluster = $1 AND program_id = $2",
+                &[&cluster, &program_id],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    // Slots of a cluster with confirmed transactions, up to `max_slot`, oldest first
+    pub async fn get_unfinalized_slots(
+        &self,
+        cluster: &str,
+        max_slot: i64,
+        limit: i64,
+    ) -> Result<Vec<i64>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_unfinalized_slots"]);
+        let client = self.pool.get().await?;
+
+        let rows = client
+            .query(
+                "SELECT DISTINCT slot
+                 FROM processed_transactions
+                 WHERE cluster = $1 AND commitment = 'confirmed' AND slot <= $2
+                 ORDER BY slot
+                 LIMIT $3",
+                &[&cluster, &max_slot, &limit],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    // Mark the transactions of finalized slots, and every row derived from them, finalized.
+    // Returns the number of transactions promoted.
+    pub async fn finalize_slots(
+        &self,
+        cluster: &str,
+        slots: &[i64],
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let _timer = metrics().db_query_duration.start_timer(&["finalize_slots"]);
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+
+        let signatures: Vec<String> = transaction
+            .query(
+                "UPDATE processed_transactions SET commitment = 'finalized'
+                 WHERE cluster = $1 AND slot = ANY($2) AND commitment = 'confirmed'
+                 RETURNING signature",
+                &[&cluster, &slots],
+            )
+            .await?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+
+        for table in ["events", "orders", "trades", "failed_attempts"] {
+            transaction
+                .execute(
+                    &format!(
+                        "UPDATE {} SET commitment = 'finalized' WHERE signature = ANY($1)",
+                        table
+                    ),
+                    &[&signatures],
+                )
+                .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(signatures.len() as u64)
+    }
+
+    // Delete everything derived from the transactions of confirmed slots that were skipped,
+    // and move checkpoints that pointed into them back to the newest remaining transaction.
+    // Returns the signatures rolled back.
+    pub async fn rollback_slots(
+        &self,
+        cluster: &str,
+        slots: &[i64],
+    ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let _timer = metrics().db_query_duration.start_timer(&["rollback_slots"]);
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let signatures: Vec<String> = transaction
+            .query(
+                "DELETE FROM processed_transactions
+                 WHERE cluster = $1 AND slot = ANY($2) AND commitment = 'confirmed'
+                 RETURNING signature",
+                &[&cluster, &slots],
+            )
+            .await?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+
+        for table in [
+            "events",
+            "orders",
+            "trades",
+            "failed_attempts",
+            "raw_transactions",
+        ] {
+            transaction
+                .execute(
+                    &format!("DELETE FROM {} WHERE signature = ANY($1)", table),
+                    &[&signatures],
+                )
+                .await?;
+        }
+
+        transaction
+            .execute(
+                "UPDATE indexer_status s
+                 SET (last_processed_slot, last_processed_signature) = (
+                         SELECT p.slot, p.signature
+                         FROM processed_transactions p
+                         WHERE p.cluster = s.cluster AND p.program_id = s.program_id
+                           AND p.slot < s.last_processed_slot
+                         ORDER BY p.slot DESC, p.processed_at DESC
+                         LIMIT 1
+                     ),
+                     updated_at = $3
+                 WHERE s.cluster = $1 AND s.last_processed_slot = ANY($2)
+                   AND EXISTS (
+                       SELECT 1 FROM processed_transactions p
+                       WHERE p.cluster = s.cluster AND p.program_id = s.program_id
+                         AND p.slot < s.last_processed_slot
+                   )",
+                &[&cluster, &slots, &now],
+            )
+            .await?;
+
+        transaction.commit().await?;
+
+        Ok(signatures)
+    }
+
+    // Slots up to `max_slot` holding trades indexed live, not placed in their block yet
+    pub async fn get_unplaced_slots(
+        &self,
+        cluster: &str,
+        max_slot: i64,
+        limit: i64,
+    ) -> Result<Vec<i64>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_unplaced_slots"]);
+        let client = self.pool.get().await?;
+
+        let rows = client
+            .query(
+                "SELECT DISTINCT slot
+                 FROM trades
+                 WHERE cluster = $1 AND tx_index IS NULL AND trade_id IS NULL AND slot <= $2
+                 ORDER BY slot
+                 LIMIT $3",
+                &[&cluster, &max_slot, &limit],
+            )
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    // Place the unplaced trades of `slot` with the block's signature list: set their
+    // tx_index and trade id, and the tx_index of every row of their transactions.
+    // Returns the number of trades placed.
+    pub async fn place_slot(
+        &self,
+        cluster: &str,
+        slot: i64,
+        block_signatures: &[String],
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let _timer = metrics().db_query_duration.start_timer(&["place_slot"]);
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+
+        let trades = transaction
+            .query(
+                "SELECT id, signature, event_seq FROM trades
+                 WHERE cluster = $1 AND slot = $2 AND tx_index IS NULL AND trade_id IS NULL
+                 ORDER BY signature",
+                &[&cluster, &slot],
+            )
+            .await?;
+
+        let mut placed = 0;
+        let mut signatures = Vec::new();
+        for trade in &trades {
+            let signature: String = trade.get(1);
+            let Some(tx_index) = block_signatures.iter().position(|s| *s == signature) else {
+                continue;
+            };
+            let tx_index = tx_index as i32;
+            let trade_id = crate::event_processor::trade_id(slot, Some(tx_index), trade.get(2));
+
+            transaction
+                .execute(
+                    "UPDATE trades SET tx_index = $2, trade_id = $3 WHERE id = $1",
+                    &[&trade.get::<_, String>(0), &tx_index, &trade_id],
+                )
+                .await?;
+            placed += 1;
+            signatures.push((signature, tx_index));
+        }
+
+        signatures.dedup();
+        for (signature, tx_index) in &signatures {
+            for table in ["events", "orders", "raw_transactions"] {
+                transaction
+                    .execute(
+                        &format!(
+                            "UPDATE {} SET tx_index = $2 WHERE signature = $1 AND tx_index IS NULL",
+                            table
+                        ),
+                        &[signature, tx_index],
+                    )
+                    .await?;
+            }
+        }
+
+        transaction.commit().await?;
+
+        Ok(placed)
+    }
+
+    // Record that a cluster's slots up to `slot` are settled; never moves back
+    pub async fn set_finalized_slot(
+        &self,
+        cluster: &str,
+        slot: i64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["set_finalized_slot"]);
+        let client = self.pool.get().await?;
+
+        client
+            .execute(
+                "UPDATE indexer_status SET finalized_slot = $2
+                 WHERE cluster = $1 AND (finalized_slot IS NULL OR finalized_slot < $2)",
+                &[&cluster, &slot],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    // Add a transaction to the dead-letter queue, or count one more failed attempt.
+    // Returns the number of attempts so far.
+    pub async fn record_failed_transaction(
+        &self,
+        signature: &str,
+        cluster: &str,
+        program_id: &str,
+        slot: i64,
+        stage: &str,
+        error: &str,
+    ) -> Result<i32, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["record_failed_transaction"]);
+        let client = self.pool.get().await?;
+
+        let row = client.query_one(
+            "INSERT INTO failed_transactions (signature, cluster, program_id, slot, stage, error, attempts, first_failed_at, last_failed_at, next_retry_at)
+             VALUES ($1, $2, $3, $4, $5, $6, 1, $7, $7, NULL)
+             ON CONFLICT (signature) DO UPDATE SET
+                 slot = EXCLUDED.slot,
+                 stage = EXCLUDED.stage,
+                 error = EXCLUDED.error,
+                 attempts = failed_transactions.attempts + 1,
+                 last_failed_at = EXCLUDED.last_failed_at
+             RETURNING attempts",
+            &[&signature, &cluster, &program_id, &slot, &stage, &error, &chrono::Utc::now().timestamp_millis()],
+        ).await?;
+
+        Ok(row.get(0))
+    }
+
+    // Set (or clear, with None) the next automatic retry of a dead-letter entry
+    pub async fn schedule_failed_transaction_retry(
+        &self,
+        signature: &str,
+        next_retry_at: Option<i64>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["schedule_failed_transaction_retry"]);
+        let client = self.pool.get().await?;
+
+        client
+            .execute(
+                "UPDATE failed_transactions SET next_retry_at = $2 WHERE signature = $1",
+                &[&signature, &next_retry_at],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    // Dead-letter entries, most recent failures first; only those due for a retry at
+    // `due_at` when given (oldest due first)
+    pub async fn get_failed_transactions(
+        &self,
+        due_at: Option<i64>,
+        filter: &crate::types::DeploymentFilter,
+        limit: i64,
+    ) -> Result<Vec<crate::types::FailedTransaction>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_failed_transactions"]);
+        let client = self.pool.get().await?;
+
+        let rows = match due_at {
+            Some(due_at) => {
+                client
+                    .query(
+                        "SELECT signature, cluster, program_id, slot, stage, error, attempts, first_failed_at, last_failed_at, next_retry_at
+                         FROM failed_transactions
+                         WHERE next_retry_at <= $1
+                           AND ($2::TEXT IS NULL OR cluster = $2)
+                           AND ($3::TEXT IS NULL OR program_id = $3)
+                         ORDER BY next_retry_at
+                         LIMIT $4",
+                        &[&due_at, &filter.cluster, &filter.program_id, &limit],
+                    )
+                    .await?
+            }
+            None => {
+                client
+                    .query(
+                        "SELECT signature, cluster, program_id, slot, stage, error, attempts, first_failed_at, last_failed_at, next_retry_at
+                         FROM failed_transactions
+                         WHERE ($1::TEXT IS NULL OR cluster = $1)
+                           AND ($2::TEXT IS NULL OR program_id = $2)
+                         ORDER BY last_failed_at DESC
+                         LIMIT $3",
+                        &[&filter.cluster, &filter.program_id, &limit],
+                    )
+                    .await?
+            }
+        };
+
+        Ok(rows.iter().map(failed_transaction_from_row).collect())
+    }
+
+    // Single dead-letter entry
+    pub async fn get_failed_transaction(
+        &self,
+        signature: &str,
+    ) -> Result<Option<crate::types::FailedTransaction>, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["get_failed_transaction"]);
+        let client = self.pool.get().await?;
+
+        let row = client
+            .query_opt(
+                "SELECT signature, cluster, program_id, slot, stage, error, attempts, first_failed_at, last_failed_at, next_retry_at
+                 FROM failed_transactions
+                 WHERE signature = $1",
+                &[&signature],
+            )
+            .await?;
+
+        Ok(row.as_ref().map(failed_transaction_from_row))
+    }
+
+    // Remove dead-letter entries (all of them when `signature` is None); returns how many
+    pub async fn delete_failed_transactions(
+        &self,
+        signature: Option<&str>,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let _timer = metrics()
+            .db_query_duration
+            .start_timer(&["delete_failed_transactions"]);
+        let client = self.pool.get().await?;
+
+        let deleted = client
+            .execute(
+                "DELETE FROM failed_transactions WHERE $1::TEXT IS NULL OR signature = $1",
+                &[&signature],
+            )
+            .await?;
+
+        Ok(deleted)
+    }
+}
+
+fn failed_transaction_from_row(row: &tokio_postgres::Row) -> crate::types::FailedTransaction {
+    crate::types::FailedTransaction {
+        signature: row.get(0),
+        cluster: row.get(1),
+        program_id: row.get(2),
+        slot: row.get(3),
+        stage: row.get(4),
+        error: row.get(5),
+        attempts: row.get(6),
+        first_failed_at: row.get(7),
+        last_failed_at: row.get(8),
+        next_retry_at: row.get(9),
+    }
+}
+
+fn market_from_row(row: &tokio_postgres::Row) -> crate::types::Market {
+    crate::types::Market {
+        id: row.get(0),
+        cluster: row.get(1),
+        program_id: row.get(2),
+        base_mint: row.get(3),
+        quote_mint: row.get(4),
+        symbol: row.get(5),
+        base_decimals: row.get(6),
+        quote_decimals: row.get(7),
+        created_at: row.get(8),
+    }
+}
+
+fn order_from_row(row: &tokio_postgres::Row) -> crate::types::Order {
+    crate::types::Order {
+        id: row.get(0),
+        market_id: row.get(1),
+        order_id: row.get(2),
+        user_address: row.get(3),
+        side: row.get(4),
+        order_type: row.get(5),
+        price: row.get(6),
+        quantity: row.get(7),
+        filled: row.get(8),
+        status: row.get(9),
+        timestamp: row.get(10),
+        commitment: row.get(11),
+    }
+}
+
+/// Columns read by `trade_from_row`
+const TRADE_COLUMNS: &str =
+    "id, trade_id, market_id, price, quantity, side, timestamp, time_estimated, commitment";
+
+fn trade_from_row(row: &tokio_postgres::Row) -> crate::types::Trade {
+    crate::types::Trade {
+        id: row.get(0),
+        trade_id: row.get(1),
+        market_id: row.get(2),
+        price: row.get(3),
+        quantity: row.get(4),
+        side: row.get(5),
+        timestamp: row.get(6),
+        time_estimated: row.get(7),
+        commitment: row.get(8),
+    }
+}
+
+/// Columns read by `vault_balance_from_row`
+const VAULT_BALANCE_COLUMNS: &str = "market_id, base_vault, quote_vault, base_amount, quote_amount, base_deposit_total, quote_deposit_total, price_lots, tvl_in_quote, slot, timestamp";
+
+fn vault_balance_from_row(row: &tokio_postgres::Row) -> crate::types::VaultBalance {
+    crate::types::VaultBalance {
+        market_id: row.get(0),
+        base_vault: row.get(1),
+        quote_vault: row.get(2),
+        base_amount: row.get(3),
+        quote_amount: row.get(4),
+        base_deposit_total: row.get(5),
+        quote_deposit_total: row.get(6),
+        price_lots: row.get(7),
+        tvl_in_quote: row.get(8),
+        slot: row.get(9),
+        timestamp: row.get(10),
+    }
+}
+
+/// Trades in trade id order: newest first unless `asc` is asked for, or when paging
+/// forward from an id (Binance `fromId`). Live trades not placed yet are the newest.
+fn trade_order_clause(order_by: Option<&str>, from_id: Option<i64>) -> &'static str {
+    match (order_by, from_id) {
+        (Some("asc") | Some("ASC"), _) | (None, Some(_)) => {
+            "ORDER BY trade_id ASC NULLS LAST, slot ASC, id ASC"
+        }
+        _ => "ORDER BY trade_id DESC NULLS FIRST, slot DESC, id DESC",
+    }
+}
+
+fn checkpoint_from_row(row: &tokio_postgres::Row) -> crate::types::Checkpoint {
+    crate::types::Checkpoint {
+        cluster: row.get(0),
+        program_id: row.get(1),
+        slot: row.get(2),
+        signature: row.get(3),
+        timestamp: row.get(4),
+        total_events_processed: row.get(5),
+        updated_at: row.get(6),
+        finalized_slot: row.get(7),
+        reindex: row
+            .get::<_, Option<i32>>(8)
+            .map(|parser_version| crate::types::ReindexProgress {
+                parser_version,
+                total: row.get::<_, Option<i64>>(9).unwrap_or(0),
+                done: row.get::<_, Option<i64>>(10).unwrap_or(0),
+                failed: row.get::<_, Option<i64>>(11).unwrap_or(0),
+                started_at: row.get::<_, Option<i64>>(12).unwrap_or(0),
+                updated_at: row.get::<_, Option<i64>>(13).unwrap_or(0),
+            }),
+    }
+}
+
+fn event_heap_snapshot_from_row(row: &tokio_postgres::Row) -> crate::types::EventHeapSnapshot {
+    crate::types::EventHeapSnapshot {
+        market_id: row.get(0),
+        event_heap: row.get(1),
+        pending_events: row.get(2),
+        fill_events: row.get(3),
+        out_events: row.get(4),
+        oldest_event_timestamp: row.get(5),
+        oldest_event_age_secs: row.get(6),
+        fullness: row.get(7),
+        seq_num: row.get(8),
+        slot: row.get(9),
+        timestamp: row.get(10),
+    }
 }
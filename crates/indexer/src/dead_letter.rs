@@ -0,0 +1,255 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::event_processor::index_transaction;
+use crate::ingest::block_time::BlockTimeCache;
+use crate::ingest::logs::{fetch_transaction, BlockSignatureCache, PendingSignature};
+use crate::ingest::FetchError;
+use crate::metrics::metrics;
+use crate::programs::Deployment;
+use crate::rate_limiter::AdaptiveRateLimiter;
+use crate::rpc_pool::RpcPool;
+use crate::types::{ApplyMode, DeploymentFilter, FailedTransaction};
+use crate::Database;
+
+/// Delay before the first automatic retry, doubled after each failed attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(30);
+
+/// Upper bound for the retry delay
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(3600);
+
+/// Attempts after which only a manual retry is possible
+const MAX_ATTEMPTS: i32 = 10;
+
+/// Entries retried per worker pass
+const RETRY_BATCH: i64 = 50;
+
+/// Where in the pipeline a transaction failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureStage {
+    Fetch,
+    Decode,
+    Persist,
+}
+
+impl FailureStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureStage::Fetch => "fetch",
+            FailureStage::Decode => "decode",
+            FailureStage::Persist => "persist",
+        }
+    }
+}
+
+/// Wait before the next automatic retry, None once `attempts` reached the limit
+pub fn retry_delay(attempts: i32) -> Option<Duration> {
+    if attempts >= MAX_ATTEMPTS {
+        return None;
+    }
+    let exponent = attempts.clamp(1, 31) as u32 - 1;
+    Some(
+        RETRY_BASE_DELAY
+            .saturating_mul(2u32.saturating_pow(exponent))
+            .min(RETRY_MAX_DELAY),
+    )
+}
+
+/// Put a transaction in the dead-letter queue (or count another failed attempt) and
+/// schedule its next retry; problems doing so are only logged
+pub async fn record_failure(
+    db: &Database,
+    deployment: &Deployment,
+    signature: &str,
+    slot: u64,
+    stage: FailureStage,
+    message: &str,
+) {
+    warn!(
+        "☠️  {} failed at {}: {}",
+        &signature[..12.min(signature.len())],
+        stage.as_str(),
+        message
+    );
+    metrics().failed_transactions.inc(&[stage.as_str()]);
+
+    let attempts = match db
+        .record_failed_transaction(
+            signature,
+            &deployment.cluster,
+            &deployment.program(),
+            slot as i64,
+            stage.as_str(),
+            message,
+        )
+        .await
+    {
+        Ok(attempts) => attempts,
+        Err(e) => {
+            error!(
+                "❌ Failed to record failed transaction {}: {}",
+                signature, e
+            );
+            return;
+        }
+    };
+
+    let next_retry_at = retry_delay(attempts)
+        .map(|delay| chrono::Utc::now().timestamp_millis() + delay.as_millis() as i64);
+    if next_retry_at.is_none() {
+        warn!(
+            "☠️  {} gave up after {} attempts, retry it manually",
+            signature, attempts
+        );
+    }
+
+    if let Err(e) = db
+        .schedule_failed_transaction_retry(signature, next_retry_at)
+        .await
+    {
+        error!("❌ Failed to schedule retry of {}: {}", signature, e);
+    }
+}
+
+/// Record a fetch failure reported by an ingestion source, against the one of
+/// `deployments` whose program the transaction was listed for, if the error is one
+///
+/// Returns whether the error was a [`FetchError`] of one of `deployments`.
+pub async fn record_fetch_error(
+    db: &Database,
+    deployments: &[Deployment],
+    error: &(dyn std::error::Error + Send + Sync + 'static),
+) -> bool {
+    let Some(fetch) = error.downcast_ref::<FetchError>() else {
+        return false;
+    };
+    let Some(deployment) = deployments
+        .iter()
+        .find(|d| d.program_id == fetch.program_id)
+    else {
+        return false;
+    };
+    record_failure(
+        db,
+        deployment,
+        &fetch.signature,
+        fetch.slot,
+        FailureStage::Fetch,
+        &fetch.message,
+    )
+    .await;
+    true
+}
+
+/// Fetch and index a dead-letter transaction again, removing it from the queue on success
+///
+/// Rows derived from an earlier partial attempt are replaced, and the checkpoint is not
+/// moved. A new failure is recorded as another attempt. The entry's deployment must be
+/// one of `deployments`, the programs served by `rpc_pool`.
+#[allow(clippy::too_many_arguments)]
+pub async fn retry_transaction(
+    rpc_pool: Arc<RpcPool>,
+    rate_limiter: Arc<AdaptiveRateLimiter>,
+    block_signatures: Arc<BlockSignatureCache>,
+    block_times: Arc<BlockTimeCache>,
+    db: &Database,
+    deployments: &[Deployment],
+    failed: &FailedTransaction,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let deployment = deployments
+        .iter()
+        .find(|d| d.cluster == failed.cluster && d.program() == failed.program_id)
+        .ok_or_else(|| {
+            format!(
+                "program {} on {} is not configured here",
+                failed.program_id, failed.cluster
+            )
+        })?;
+
+    let pending = PendingSignature {
+        signature: failed.signature.clone(),
+        slot: failed.slot as u64,
+        live: false,
+    };
+
+    let tx = match fetch_transaction(
+        rpc_pool,
+        rate_limiter,
+        block_signatures,
+        block_times,
+        deployment.program_id,
+        pending,
+    )
+    .await
+    {
+        Ok(tx) => tx,
+        Err(e) => {
+            record_fetch_error(db, std::slice::from_ref(deployment), e.as_ref()).await;
+            return Err(e.to_string().into());
+        }
+    };
+
+    let (derived, _) = index_transaction(&tx, deployment, db, ApplyMode::Rebuild).await?;
+    if !derived.decode_errors.is_empty() {
+        return Err(derived.decode_errors.join("; ").into());
+    }
+
+    db.delete_failed_transactions(Some(&failed.signature))
+        .await?;
+    Ok(())
+}
+
+/// Retry due dead-letter entries of `deployments` every `interval`
+pub async fn run_retry_worker(
+    rpc_pool: Arc<RpcPool>,
+    rate_limiter: Arc<AdaptiveRateLimiter>,
+    db: Database,
+    deployments: Vec<Deployment>,
+    interval: Duration,
+) {
+    info!(
+        "☠️  Dead-letter retry worker started (every {}s)",
+        interval.as_secs()
+    );
+
+    let block_signatures = Arc::new(BlockSignatureCache::default());
+    let block_times = Arc::new(BlockTimeCache::default());
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut due = Vec::new();
+        for deployment in &deployments {
+            let filter = DeploymentFilter::deployment(deployment);
+            match db
+                .get_failed_transactions(Some(now), &filter, RETRY_BATCH)
+                .await
+            {
+                Ok(entries) => due.extend(entries),
+                Err(e) => warn!("⚠️  Failed to load dead-letter queue: {}", e),
+            }
+        }
+
+        for failed in due {
+            match retry_transaction(
+                rpc_pool.clone(),
+                rate_limiter.clone(),
+                block_signatures.clone(),
+                block_times.clone(),
+                &db,
+                &deployments,
+                &failed,
+            )
+            .await
+            {
+                Ok(()) => info!(
+                    "♻️  Recovered {} after {} failed attempts",
+                    failed.signature, failed.attempts
+                ),
+                Err(e) => warn!("⚠️  Retry of {} failed: {}", failed.signature, e),
+            }
+        }
+    }
+}
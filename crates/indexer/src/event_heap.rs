@@ -0,0 +1,423 @@
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::metrics::metrics;
+use crate::programs::{Deployment, MarketLayout};
+use crate::rpc_pool::RpcPool;
+use crate::types::{DeploymentFilter, EventHeapSnapshot};
+
+/// OpenBook V2 EventHeap account discriminator (sha256("account:EventHeap")[..8])
+const EVENT_HEAP_DISCRIMINATOR: [u8; 8] = [119, 59, 61, 19, 165, 84, 57, 175];
+
+/// Number of event slots in the heap (`nodes: [EventNode; 600]` in the IDL)
+pub const MAX_NUM_EVENTS: usize = 600;
+
+/// Sentinel used by the heap for "no node"
+const NO_NODE: u16 = u16::MAX;
+
+// EventHeap layout (zero-copy, after the 8-byte discriminator):
+// - 16 bytes: EventHeapHeader { free_head: u16, used_head: u16, count: u16, padd: u16, seq_num: u64 }
+// - 600 * 152 bytes: EventNode { next: u16, prev: u16, pad: [u8; 4], event: AnyEvent }
+// - 64 bytes: reserved
+const HEADER_OFFSET: usize = 8;
+const HEADER_SIZE: usize = 16;
+const NODES_OFFSET: usize = HEADER_OFFSET + HEADER_SIZE;
+const EVENT_SIZE: usize = 144;
+const NODE_SIZE: usize = 8 + EVENT_SIZE;
+const EVENT_HEAP_SIZE: usize = NODES_OFFSET + MAX_NUM_EVENTS * NODE_SIZE + 64;
+
+/// `getMultipleAccounts` accepts at most 100 keys per request
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct EventHeapHeader {
+    pub free_head: u16,
+    pub used_head: u16,
+    pub count: u16,
+    pub seq_num: u64,
+}
+
+/// Fill event (`FillEvent` in the IDL, event_type = 0)
+#[derive(Debug, Clone)]
+pub struct FillEvent {
+    pub taker_side: u8,
+    pub maker_out: bool,
+    pub maker_slot: u8,
+    pub timestamp: u64,
+    pub seq_num: u64,
+    pub maker: String,
+    pub maker_timestamp: u64,
+    pub taker: String,
+    pub taker_client_order_id: u64,
+    pub price: i64,
+    pub peg_limit: i64,
+    pub quantity: i64,
+    pub maker_client_order_id: u64,
+}
+
+/// Out event (`OutEvent` in the IDL, event_type = 1)
+#[derive(Debug, Clone)]
+pub struct OutEvent {
+    pub side: u8,
+    pub owner_slot: u8,
+    pub timestamp: u64,
+    pub seq_num: u64,
+    pub owner: String,
+    pub quantity: i64,
+}
+
+#[derive(Debug, Clone)]
+pub enum AnyEvent {
+    Fill(FillEvent),
+    Out(OutEvent),
+    Unknown(u8),
+}
+
+impl AnyEvent {
+    /// Unix timestamp (seconds) at which the event was pushed to the heap
+    pub fn timestamp(&self) -> Option<u64> {
+        match self {
+            AnyEvent::Fill(fill) => Some(fill.timestamp),
+            AnyEvent::Out(out) => Some(out.timestamp),
+            AnyEvent::Unknown(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EventNode {
+    pub next: u16,
+    pub prev: u16,
+    pub event: AnyEvent,
+}
+
+#[derive(Debug, Clone)]
+pub struct EventHeap {
+    pub header: EventHeapHeader,
+    pub nodes: Vec<EventNode>,
+}
+
+impl EventHeap {
+    /// Pending events in the order `consumeEvents` will process them (oldest first)
+    pub fn pending_events(&self) -> Vec<&AnyEvent> {
+        let mut events = Vec::with_capacity(self.header.count as usize);
+        let mut index = self.header.used_head;
+
+        // Walk the used list; bounded by `count` so a corrupt list can't loop forever
+        while events.len() < self.header.count as usize && index != NO_NODE {
+            let Some(node) = self.nodes.get(index as usize) else {
+                break;
+            };
+            events.push(&node.event);
+            index = node.next;
+        }
+
+        events
+    }
+
+    /// Oldest event still waiting to be consumed
+    pub fn oldest_pending(&self) -> Option<&AnyEvent> {
+        if self.header.count == 0 {
+            return None;
+        }
+        self.nodes
+            .get(self.header.used_head as usize)
+            .map(|node| &node.event)
+    }
+
+    /// Fraction of the heap in use (0.0 - 1.0)
+    pub fn fullness(&self) -> f64 {
+        self.header.count as f64 / MAX_NUM_EVENTS as f64
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> String {
+    Pubkey::try_from(&data[offset..offset + 32])
+        .map(|p| p.to_string())
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Parse a single 144-byte AnyEvent slot
+fn parse_any_event(data: &[u8]) -> AnyEvent {
+    match data[0] {
+        0 => AnyEvent::Fill(FillEvent {
+            taker_side: data[1],
+            maker_out: data[2] != 0,
+            maker_slot: data[3],
+            timestamp: read_u64(data, 8),
+            seq_num: read_u64(data, 16),
+            maker: read_pubkey(data, 24),
+            maker_timestamp: read_u64(data, 56),
+            taker: read_pubkey(data, 64),
+            taker_client_order_id: read_u64(data, 96),
+            price: read_i64(data, 104),
+            peg_limit: read_i64(data, 112),
+            quantity: read_i64(data, 120),
+            maker_client_order_id: read_u64(data, 128),
+        }),
+        1 => AnyEvent::Out(OutEvent {
+            side: data[1],
+            owner_slot: data[2],
+            timestamp: read_u64(data, 8),
+            seq_num: read_u64(data, 16),
+            owner: read_pubkey(data, 24),
+            quantity: read_i64(data, 56),
+        }),
+        other => AnyEvent::Unknown(other),
+    }
+}
+
+/// Parse EventHeap account data
+pub fn parse_event_heap(data: &[u8]) -> Result<EventHeap, Box<dyn std::error::Error>> {
+    if data.len() < EVENT_HEAP_SIZE {
+        return Err(format!(
+            "Account data too small to be an event heap ({} < {})",
+            data.len(),
+            EVENT_HEAP_SIZE
+        )
+        .into());
+    }
+
+    if data[0..8] != EVENT_HEAP_DISCRIMINATOR {
+        return Err("Invalid event heap discriminator".into());
+    }
+
+    let header = EventHeapHeader {
+        free_head: read_u16(data, HEADER_OFFSET),
+        used_head: read_u16(data, HEADER_OFFSET + 2),
+        count: read_u16(data, HEADER_OFFSET + 4),
+        seq_num: read_u64(data, HEADER_OFFSET + 8),
+    };
+
+    let nodes = (0..MAX_NUM_EVENTS)
+        .map(|i| {
+            let offset = NODES_OFFSET + i * NODE_SIZE;
+            EventNode {
+                next: read_u16(data, offset),
+                prev: read_u16(data, offset + 2),
+                event: parse_any_event(&data[offset + 8..offset + NODE_SIZE]),
+            }
+        })
+        .collect();
+
+    Ok(EventHeap { header, nodes })
+}
+
+/// Read the event heap address out of raw market account data of the given layout
+pub fn event_heap_address_from_market(data: &[u8], layout: MarketLayout) -> Option<Pubkey> {
+    let offset = layout.offsets().state?.event_heap;
+    if data.len() < offset + 32 {
+        return None;
+    }
+    Pubkey::try_from(&data[offset..offset + 32]).ok()
+}
+
+/// Summarise a decoded heap into a snapshot
+pub fn snapshot_event_heap(
+    market_id: &str,
+    event_heap: &str,
+    heap: &EventHeap,
+    slot: u64,
+    now_ms: i64,
+) -> EventHeapSnapshot {
+    let pending = heap.pending_events();
+    let fill_events = pending
+        .iter()
+        .filter(|e| matches!(e, AnyEvent::Fill(_)))
+        .count();
+    let out_events = pending
+        .iter()
+        .filter(|e| matches!(e, AnyEvent::Out(_)))
+        .count();
+
+    let oldest_event_timestamp = heap
+        .oldest_pending()
+        .and_then(|e| e.timestamp())
+        .map(|ts| ts as i64);
+    let oldest_event_age_secs = oldest_event_timestamp.map(|ts| (now_ms / 1000 - ts).max(0));
+
+    EventHeapSnapshot {
+        market_id: market_id.to_string(),
+        event_heap: event_heap.to_string(),
+        pending_events: heap.header.count as i32,
+        fill_events: fill_events as i32,
+        out_events: out_events as i32,
+        oldest_event_timestamp,
+        oldest_event_age_secs,
+        fullness: heap.fullness(),
+        seq_num: heap.header.seq_num as i64,
+        slot: slot as i64,
+        timestamp: now_ms,
+    }
+}
+
+/// Resolve market -> event heap addresses by reading the market accounts
+async fn resolve_event_heaps(
+    rpc_pool: &RpcPool,
+    market_ids: &[String],
+    layout: MarketLayout,
+) -> Result<HashMap<String, Pubkey>, Box<dyn std::error::Error>> {
+    let mut resolved = HashMap::new();
+
+    let market_keys: Vec<Pubkey> = market_ids
+        .iter()
+        .filter_map(|id| Pubkey::from_str(id).ok())
+        .collect();
+
+    for chunk in market_keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = rpc_pool
+            .call(|rpc| async move { rpc.get_multiple_accounts(chunk).await })
+            .await?;
+        for (market, account) in chunk.iter().zip(accounts) {
+            match account.and_then(|a| event_heap_address_from_market(&a.data, layout)) {
+                Some(event_heap) => {
+                    resolved.insert(market.to_string(), event_heap);
+                }
+                None => warn!("⚠️  Could not resolve event heap for market {}", market),
+            }
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Fetch and decode the event heap of every market of `deployments` once, storing a
+/// snapshot per market
+pub async fn snapshot_event_heaps(
+    rpc_pool: &RpcPool,
+    db: &crate::Database,
+    deployments: &[Deployment],
+    event_heaps: &mut HashMap<String, Pubkey>,
+) -> Result<Vec<EventHeapSnapshot>, Box<dyn std::error::Error>> {
+    let mut markets = Vec::new();
+    for deployment in deployments {
+        let deployment_markets = db
+            .get_markets(&DeploymentFilter::deployment(deployment), 1000)
+            .await?;
+        let unresolved: Vec<String> = deployment_markets
+            .iter()
+            .map(|m| m.id.clone())
+            .filter(|id| !event_heaps.contains_key(id))
+            .collect();
+
+        if !unresolved.is_empty() {
+            event_heaps
+                .extend(resolve_event_heaps(rpc_pool, &unresolved, deployment.layout).await?);
+        }
+        markets.extend(deployment_markets);
+    }
+
+    let entries: Vec<(String, Pubkey)> = markets
+        .iter()
+        .filter_map(|m| event_heaps.get(&m.id).map(|heap| (m.id.clone(), *heap)))
+        .collect();
+
+    let mut snapshots = Vec::new();
+
+    for chunk in entries.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let keys: Vec<Pubkey> = chunk.iter().map(|(_, heap)| *heap).collect();
+        let keys = &keys;
+        let response = rpc_pool
+            .call(|rpc| async move {
+                rpc.get_multiple_accounts_with_commitment(keys, CommitmentConfig::confirmed())
+                    .await
+            })
+            .await?;
+        let slot = response.context.slot;
+        let now_ms = chrono::Utc::now().timestamp_millis();
+
+        for ((market_id, heap_key), account) in chunk.iter().zip(response.value) {
+            let Some(account) = account else {
+                warn!(
+                    "⚠️  Event heap {} for market {} not found",
+                    heap_key, market_id
+                );
+                continue;
+            };
+
+            let heap = match parse_event_heap(&account.data) {
+                Ok(heap) => heap,
+                Err(e) => {
+                    warn!("⚠️  Failed to parse event heap {}: {}", heap_key, e);
+                    continue;
+                }
+            };
+
+            let snapshot =
+                snapshot_event_heap(market_id, &heap_key.to_string(), &heap, slot, now_ms);
+            db.insert_event_heap_snapshot(&snapshot).await?;
+            snapshots.push(snapshot);
+        }
+    }
+
+    Ok(snapshots)
+}
+
+/// Periodically record pending event count, oldest event age and heap fullness per market
+pub async fn monitor_event_heaps(
+    rpc_pool: &RpcPool,
+    db: &crate::Database,
+    deployments: &[Deployment],
+    interval: Duration,
+) {
+    info!(
+        "🧮 Event heap monitor started (interval: {}s)",
+        interval.as_secs()
+    );
+
+    let mut event_heaps: HashMap<String, Pubkey> = HashMap::new();
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+
+        let result = snapshot_event_heaps(rpc_pool, db, deployments, &mut event_heaps)
+            .await
+            .map_err(|e| e.to_string());
+
+        match result {
+            Ok(snapshots) => {
+                for snapshot in &snapshots {
+                    let market = [snapshot.market_id.as_str()];
+                    metrics()
+                        .event_heap_pending
+                        .set(&market, snapshot.pending_events as f64);
+                    metrics()
+                        .event_heap_oldest_event_age
+                        .set(&market, snapshot.oldest_event_age_secs.unwrap_or(0) as f64);
+                    metrics()
+                        .event_heap_fullness
+                        .set(&market, snapshot.fullness);
+                }
+
+                for snapshot in snapshots.iter().filter(|s| s.pending_events > 0) {
+                    info!(
+                        "  🧮 Event heap {}: {} pending ({:.1}% full, oldest {}s)",
+                        &snapshot.market_id[..12.min(snapshot.market_id.len())],
+                        snapshot.pending_events,
+                        snapshot.fullness * 100.0,
+                        snapshot.oldest_event_age_secs.unwrap_or(0)
+                    );
+                }
+            }
+            Err(e) => {
+                warn!("⚠️  Event heap snapshot failed: {}", e);
+            }
+        }
+    }
+}
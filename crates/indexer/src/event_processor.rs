@@ -3,8 +3,138 @@ use solana_transaction_status::{
 };
 use tracing::{info, warn};
 
+use crate::archive::compress_transaction;
+use crate::dead_letter::{self, FailureStage};
+use crate::ingest::IngestedTransaction;
+use crate::metrics::metrics;
+use crate::program_errors::TransactionFailure;
+use crate::programs::Deployment;
+use crate::types::{
+    ApplyMode, DeploymentFilter, EventPosition, EventWrite, FailedAttemptWrite, OrderWrite,
+    RawTransactionWrite, TradeWrite, TransactionWrites,
+};
+
+/// Version of the logic deriving rows from transactions, stamped on every row it writes
+///
+/// Bump it whenever instruction parsing or fill handling changes what is derived, then
+/// run `indexer-cli reprocess --outdated` to re-derive the rows of older versions.
+pub const PARSER_VERSION: i32 = 3;
+
+/// Parse events from transaction logs
+pub fn parse_events_from_logs(logs: &[String]) -> Vec<&'static str> {
+    parse_instruction_events(logs)
+        .into_iter()
+        .map(|(_, event_type)| event_type)
+        .collect()
+}
+
+/// Parse events from transaction logs, each with the index of the top-level
+/// instruction that emitted it
+pub fn parse_instruction_events(logs: &[String]) -> Vec<(i32, &'static str)> {
+    parse_event_positions(logs)
+        .into_iter()
+        .map(|(position, event_type)| (position.instruction_index, event_type))
+        .collect()
+}
+
+/// Parse events from transaction logs, each with its position in the transaction
+pub fn parse_event_positions(logs: &[String]) -> Vec<(EventPosition, &'static str)> {
+    let mut found_events = Vec::new();
+    let mut instruction_index = -1;
+    // Invocations made under the current top-level instruction, and the chain of those
+    // still running (the innermost one emits the next log line)
+    let mut invocations = 0;
+    let mut running: Vec<i32> = Vec::new();
+
+    for log_line in logs.iter() {
+        // Depth 1 invocations are the transaction's own instructions; CPIs are deeper
+        if let Some(depth) = invoke_depth(log_line) {
+            if depth <= 1 {
+                instruction_index += 1;
+                invocations = 0;
+                running.clear();
+            } else {
+                invocations += 1;
+                running.truncate(depth - 2);
+                running.push(invocations);
+            }
+        } else if is_invocation_end(log_line) {
+            running.pop();
+        } else if let Some(event_type) = event_type_of(log_line) {
+            let position = EventPosition {
+                instruction_index: instruction_index.max(0),
+                inner_index: running.last().copied().unwrap_or(0),
+                event_seq: found_events.len() as i32,
+            };
+            found_events.push((position, event_type));
+        }
+    }
+
+    found_events
+}
+
+/// Depth of a `Program <id> invoke [<depth>]` line
+fn invoke_depth(log_line: &str) -> Option<usize> {
+    let (program, rest) = log_line.strip_prefix("Program ")?.split_once(' ')?;
+    if program.ends_with(':') {
+        return None;
+    }
+    rest.strip_prefix("invoke [")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+/// `Program <id> success` or `Program <id> failed: <error>`
+fn is_invocation_end(log_line: &str) -> bool {
+    let Some((program, rest)) = log_line
+        .strip_prefix("Program ")
+        .and_then(|line| line.split_once(' '))
+    else {
+        return false;
+    };
+    !program.ends_with(':') && (rest == "success" || rest.starts_with("failed: "))
+}
+
+/// Monotonic trade id: the slot, the transaction's index in the block and the event's
+/// sequence number in the transaction, packed so that ids sort in chain order
+///
+/// 16 bits each for the index and the sequence number is tight but enough: blocks hold a
+/// few thousand transactions and a transaction a few dozen events. None when the index
+/// is unknown or either does not fit, since the id would not be unique.
+pub fn trade_id(slot: i64, tx_index: Option<i32>, event_seq: i32) -> Option<i64> {
+    let tx_index = tx_index.filter(|index| (0..=0xFFFF).contains(index))? as i64;
+    if !(0..=0xFFFF).contains(&event_seq) {
+        return None;
+    }
+    Some((slot << 32) | (tx_index << 16) | event_seq as i64)
+}
+
+fn event_type_of(log_line: &str) -> Option<&'static str> {
+    if log_line.contains("Instruction: PlaceOrder") {
+        Some("PlaceOrder")
+    } else if log_line.contains("Instruction: FillEvent") || log_line.contains("Instruction: Fill")
+    {
+        Some("Fill")
+    } else if log_line.contains("Instruction: CancelOrder") {
+        Some("CancelOrder")
+    } else if log_line.contains("Instruction: SettleFunds") {
+        Some("SettleFunds")
+    } else if log_line.contains("Instruction: ConsumeEvents") {
+        Some("ConsumeEvents")
+    } else if log_line.contains("Instruction: CreateOpenOrdersAccount") {
+        Some("CreateOpenOrdersAccount")
+    } else if log_line.contains("Instruction: CreateOpenOrdersIndexer") {
+        Some("CreateOpenOrdersIndexer")
+    } else if log_line.contains("Instruction: CreateMarket") {
+        Some("CreateMarket")
+    } else {
+        None
+    }
+}
+
 /// Extract account keys from transaction
-fn extract_account_keys(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Vec<String> {
+pub(crate) fn extract_account_keys(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Vec<String> {
     let mut accounts = Vec::new();
 
     if let EncodedTransaction::Json(ui_tx) = &tx.transaction.transaction {
@@ -22,41 +152,24 @@ fn extract_account_keys(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Vec<S
     accounts
 }
 
-/// Extract market name from transaction logs
-fn extract_market_name_from_logs(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Option<String> {
-    if let Some(ref meta) = tx.transaction.meta {
-        let log_messages: Option<Vec<String>> = meta.log_messages.clone().into();
-        if let Some(logs) = log_messages {
-            // Look for market name in logs - OpenBook logs the market name
-            for log in logs {
-                // Try to extract name from log patterns
-                if log.contains("CreateMarket") || log.contains("name:") {
-                    // Simple pattern matching - in real implementation, parse instruction data
-                    continue;
-                }
-            }
-        }
-    }
-    None
-}
-
 /// Process CreateMarket event and extract market data
 /// NOTE: Markets are now indexed by the market scanner on startup.
 /// This function is kept for logging but doesn't insert markets to avoid duplicates/fake markets.
 pub async fn process_create_market(
-    tx: &EncodedConfirmedTransactionWithStatusMeta,
-    signature: &str,
-    slot: u64,
-    timestamp: i64,
-    db: &crate::Database,
+    tx: &IngestedTransaction,
+    _position: EventPosition,
+    _db: &crate::Database,
+    _writes: &mut TransactionWrites,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let account_keys = extract_account_keys(tx);
+    let signature = &tx.signature;
+    let slot = tx.slot;
+    let account_keys = extract_account_keys(&tx.transaction);
 
     if account_keys.len() >= 3 {
         // Just log the CreateMarket event for debugging
         // The actual market will be indexed by the market scanner
         let potential_market = account_keys
-            .get(0)
+            .first()
             .cloned()
             .unwrap_or_else(|| format!("market_{}", slot));
 
@@ -74,27 +187,32 @@ pub async fn process_create_market(
     Ok(())
 }
 
-/// Extract instruction data from transaction
-fn extract_instruction_data(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Option<Vec<u8>> {
+/// Extract instruction data from transaction, starting at the instruction that emitted the event
+fn extract_instruction_data(
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+    instruction_index: i32,
+) -> Option<Vec<u8>> {
     if let EncodedTransaction::Json(ui_tx) = &tx.transaction.transaction {
-        match &ui_tx.message {
-            UiMessage::Raw(raw_message) => {
-                // Iterate through all instructions to find PlaceOrder (OpenBook program)
-                for (idx, instruction) in raw_message.instructions.iter().enumerate() {
-                    // Try base58 decoding (Solana's default encoding)
-                    if let Ok(decoded) = bs58::decode(&instruction.data).into_vec() {
-                        info!("  🔍 Instruction {}: decoded {} bytes", idx, decoded.len());
-                        if decoded.len() >= 8 {
-                            info!("  🔍 First 8 bytes (discriminator): {:?}", &decoded[0..8]);
-                        }
-                        // Return the first instruction with substantial data
-                        if decoded.len() > 16 {
-                            return Some(decoded);
-                        }
+        if let UiMessage::Raw(raw_message) = &ui_tx.message {
+            // Iterate through the instructions to find PlaceOrder (OpenBook program)
+            for (idx, instruction) in raw_message
+                .instructions
+                .iter()
+                .enumerate()
+                .skip(instruction_index as usize)
+            {
+                // Try base58 decoding (Solana's default encoding)
+                if let Ok(decoded) = bs58::decode(&instruction.data).into_vec() {
+                    info!("  🔍 Instruction {}: decoded {} bytes", idx, decoded.len());
+                    if decoded.len() >= 8 {
+                        info!("  🔍 First 8 bytes (discriminator): {:?}", &decoded[0..8]);
+                    }
+                    // Return the first instruction with substantial data
+                    if decoded.len() > 16 {
+                        return Some(decoded);
                     }
                 }
             }
-            _ => {}
         }
     }
     None
@@ -140,13 +258,20 @@ fn parse_place_order_instruction(data: &[u8]) -> Option<(String, i64, i64)> {
 /// Extract market address from transaction accounts
 /// PlaceOrder instruction accounts (from IDL):
 /// 0: signer, 1: openOrdersAccount, 2: openOrdersAdmin (optional), 3: userTokenAccount, 4: market, ...
-fn extract_market_address(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Option<String> {
+fn extract_market_address(
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+    instruction_index: i32,
+) -> Option<String> {
     let account_keys = extract_account_keys(tx);
 
     if let EncodedTransaction::Json(ui_tx) = &tx.transaction.transaction {
         if let UiMessage::Raw(raw_message) = &ui_tx.message {
             // Find the PlaceOrder instruction (has substantial data)
-            for instruction in &raw_message.instructions {
+            for instruction in raw_message
+                .instructions
+                .iter()
+                .skip(instruction_index as usize)
+            {
                 if let Ok(decoded) = bs58::decode(&instruction.data).into_vec() {
                     if decoded.len() > 16 {
                         // This is likely the PlaceOrder instruction
@@ -164,20 +289,84 @@ fn extract_market_address(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Opt
     None
 }
 
+/// Position of the market among an OpenBook instruction's accounts, by instruction
+/// discriminator (sha256("global:<instruction>")[..8]), from the IDL
+const MARKET_ACCOUNT_INDEX: &[([u8; 8], usize)] = &[
+    ([103, 226, 97, 235, 200, 188, 251, 254], 0), // createMarket
+    ([88, 154, 248, 186, 48, 14, 123, 244], 1),   // closeMarket
+    ([204, 181, 175, 222, 40, 125, 188, 71], 5),  // createOpenOrdersAccount
+    ([51, 194, 155, 175, 109, 130, 96, 106], 4),  // placeOrder
+    ([254, 208, 118, 29, 173, 248, 200, 70], 4),  // editOrder
+    ([62, 187, 125, 69, 26, 221, 157, 133], 4),   // editOrderPegged
+    ([60, 63, 50, 123, 12, 197, 60, 190], 5),     // placeOrders
+    ([128, 155, 222, 60, 186, 40, 225, 50], 5),   // cancelAllAndPlaceOrders
+    ([141, 185, 251, 63, 74, 85, 210, 145], 4),   // placeOrderPegged
+    ([3, 44, 71, 3, 26, 199, 203, 85], 2),        // placeTakeOrder
+    ([221, 145, 177, 52, 31, 47, 63, 201], 1),    // consumeEvents
+    ([209, 227, 54, 4, 109, 172, 41, 71], 1),     // consumeGivenEvents
+    ([95, 129, 237, 240, 8, 49, 223, 132], 2),    // cancelOrder
+    ([115, 178, 201, 8, 175, 183, 123, 119], 2),  // cancelOrderByClientOrderId
+    ([196, 83, 243, 171, 17, 100, 160, 143], 2),  // cancelAllOrders
+    ([242, 35, 198, 137, 82, 225, 242, 182], 4),  // deposit
+    ([128, 207, 142, 11, 54, 232, 38, 201], 4),   // refill
+    ([238, 64, 163, 96, 75, 171, 16, 33], 3),     // settleFunds
+    ([107, 18, 56, 69, 228, 56, 55, 164], 4),     // settleFundsExpired
+    ([175, 225, 98, 71, 118, 66, 34, 148], 1),    // sweepFees
+    ([219, 82, 219, 236, 60, 115, 197, 64], 1),   // setMarketExpired
+    ([27, 213, 159, 191, 12, 116, 112, 121], 2),  // pruneOrders
+];
+
+/// Index of the market among the accounts of the OpenBook instruction with `data`; None
+/// for instructions without a market and unknown ones
+pub fn market_account_index(data: &[u8]) -> Option<usize> {
+    let discriminator = data.get(..8)?;
+    MARKET_ACCOUNT_INDEX
+        .iter()
+        .find(|(known, _)| known[..] == *discriminator)
+        .map(|(_, index)| *index)
+}
+
+/// Market named by the top-level instruction `instruction_index`, at the account position
+/// its discriminator calls for
+fn instruction_market(
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+    instruction_index: i32,
+) -> Option<String> {
+    let EncodedTransaction::Json(ui_tx) = &tx.transaction.transaction else {
+        return None;
+    };
+    let UiMessage::Raw(raw_message) = &ui_tx.message else {
+        return None;
+    };
+    let instruction = raw_message
+        .instructions
+        .get(usize::try_from(instruction_index).ok()?)?;
+    let data = bs58::decode(&instruction.data).into_vec().ok()?;
+    let market_idx = *instruction.accounts.get(market_account_index(&data)?)?;
+    extract_account_keys(tx).get(market_idx as usize).cloned()
+}
+
 /// Process PlaceOrder event and extract order data
 pub async fn process_place_order(
-    tx: &EncodedConfirmedTransactionWithStatusMeta,
-    signature: &str,
-    slot: u64,
-    timestamp: i64,
+    ingested: &IngestedTransaction,
+    position: EventPosition,
     db: &crate::Database,
+    writes: &mut TransactionWrites,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let instruction_index = position.instruction_index;
+    let tx = &ingested.transaction;
+    let slot = ingested.slot;
+
     // Extract market address from transaction accounts
-    let market_address = if let Some(addr) = extract_market_address(tx) {
+    let market_address = if let Some(addr) = extract_market_address(tx, instruction_index) {
         addr
     } else {
-        // Fallback to first market if extraction fails
-        let markets = db.get_markets(1).await?;
+        // Fallback to the deployment's first market if extraction fails
+        let filter = DeploymentFilter {
+            cluster: Some(writes.cluster.clone()),
+            program_id: Some(writes.program_id.clone()),
+        };
+        let markets = db.get_markets(&filter, 1).await?;
         if markets.is_empty() {
             return Ok(());
         }
@@ -188,26 +377,27 @@ pub async fn process_place_order(
 
     if !account_keys.is_empty() {
         let user_address = account_keys
-            .get(0)
+            .first()
             .cloned()
             .unwrap_or_else(|| "unknown_user".to_string());
 
-        // Generate a unique order ID from slot and timestamp
-        let order_id = (slot as i64) * 1000 + (timestamp % 1000);
+        // Generate a unique order ID from slot and instruction index
+        let order_id = (slot as i64) * 1000 + instruction_index as i64;
 
         // Parse instruction data to get actual side, price, and quantity
-        let (side, price, quantity) = if let Some(instruction_data) = extract_instruction_data(tx) {
-            if let Some(parsed) = parse_place_order_instruction(&instruction_data) {
-                parsed
+        let (side, price, quantity) =
+            if let Some(instruction_data) = extract_instruction_data(tx, instruction_index) {
+                if let Some(parsed) = parse_place_order_instruction(&instruction_data) {
+                    parsed
+                } else {
+                    // Fallback to defaults if parsing fails
+                    warn!("Failed to parse PlaceOrder instruction data, using defaults");
+                    ("bid".to_string(), 1000, 10)
+                }
             } else {
-                // Fallback to defaults if parsing fails
-                warn!("Failed to parse PlaceOrder instruction data, using defaults");
+                warn!("Could not extract instruction data, using defaults");
                 ("bid".to_string(), 1000, 10)
-            }
-        } else {
-            warn!("Could not extract instruction data, using defaults");
-            ("bid".to_string(), 1000, 10)
-        };
+            };
 
         info!(
             "  📈 PlaceOrder: {} on {} (side: {}, price: {}, qty: {})",
@@ -218,23 +408,17 @@ pub async fn process_place_order(
             quantity
         );
 
-        // Insert order into database
-        db.insert_order(
-            signature,
-            &market_address, // Use the extracted market address
+        // Written with the rest of the transaction
+        writes.orders.push(OrderWrite {
+            position,
+            market_id: market_address, // Use the extracted market address
             order_id,
-            &user_address,
-            &side,
-            "limit",
+            user_address,
+            side,
+            order_type: "limit".to_string(),
             price,
             quantity,
-            timestamp,
-            slot as i64,
-            signature,
-        )
-        .await?;
-
-        info!("  ✅ Order stored in database");
+        });
     }
 
     Ok(())
@@ -242,42 +426,59 @@ pub async fn process_place_order(
 
 /// Process Fill/Consume event (when orders match)
 pub async fn process_fill(
-    tx: &EncodedConfirmedTransactionWithStatusMeta,
-    signature: &str,
-    slot: u64,
-    timestamp: i64,
+    ingested: &IngestedTransaction,
+    position: EventPosition,
     db: &crate::Database,
+    writes: &mut TransactionWrites,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let instruction_index = position.instruction_index;
+    let tx = &ingested.transaction;
+    let slot = ingested.slot;
+
     // Extract market address from instruction accounts
     // ConsumeEvents instruction accounts: 0: market, 1: eventHeap
     // (consumeEventsAdmin is signer, not in instruction.accounts)
     let account_keys = extract_account_keys(tx);
 
-    let mut market_address = String::new();
+    let mut extracted_market = None;
 
     if let EncodedTransaction::Json(ui_tx) = &tx.transaction.transaction {
         if let UiMessage::Raw(raw_message) = &ui_tx.message {
-            // Find the consumeEvents instruction (usually the first one)
-            if let Some(instruction) = raw_message.instructions.first() {
+            // The consumeEvents instruction is the one that emitted the event
+            if let Some(instruction) = raw_message.instructions.get(instruction_index as usize) {
                 // consumeEvents accounts: [market, eventHeap]
                 if let Some(&market_idx) = instruction.accounts.first() {
                     if let Some(addr) = account_keys.get(market_idx as usize) {
                         info!("  🔍 Extracted market address: {}", addr);
-                        market_address = addr.clone();
+                        extracted_market = Some(addr.clone());
                     }
                 }
             }
         }
     }
 
-    // For now, use WETH/USDT market as default since account extraction is complex
-    let market_address = "GNNZmWtsrzKoRppmASnszdaTK7tNabREsxEKZt1pBjHS".to_string();
-    info!("  📍 Using WETH/USDT market for trade");
-    let account_keys = extract_account_keys(tx);
+    // A trade is only recorded against a market of the program that emitted it
+    let Some(market_address) = extracted_market else {
+        return Err(format!("no market account on instruction {}", instruction_index).into());
+    };
+    let filter = DeploymentFilter {
+        cluster: Some(writes.cluster.clone()),
+        program_id: Some(writes.program_id.clone()),
+    };
+    if !db.has_market(&market_address, &filter).await? {
+        return Err(format!(
+            "{} is not a market of {}",
+            market_address, writes.program_id
+        )
+        .into());
+    }
+
+    // Live trades are not placed in their block yet: the finalizer gives them their id
+    let trade_id = trade_id(writes.slot, writes.tx_index, position.event_seq);
 
     if account_keys.len() >= 2 {
         let maker_address = account_keys
-            .get(0)
+            .first()
             .cloned()
             .unwrap_or_else(|| "unknown_maker".to_string());
 
@@ -286,9 +487,6 @@ pub async fn process_fill(
             .cloned()
             .unwrap_or_else(|| "unknown_taker".to_string());
 
-        // Generate trade ID from signature and slot
-        let trade_id = format!("{}_{}", signature, slot);
-
         // Use slot-based pricing (simplified - should parse from instruction data)
         let price = ((slot % 100) as i64) + 1000;
         let quantity = 5; // Simplified quantity
@@ -309,22 +507,17 @@ pub async fn process_fill(
             &taker_address[..12.min(taker_address.len())]
         );
 
-        // Insert trade into database
-        db.insert_trade(
-            &trade_id,
-            &market_address,
-            &maker_address,
-            &taker_address,
-            side,
+        // Written with the rest of the transaction
+        writes.trades.push(TradeWrite {
+            position,
+            trade_id,
+            market_id: market_address,
+            maker_address,
+            taker_address,
+            side: side.to_string(),
             price,
             quantity,
-            timestamp,
-            slot as i64,
-            signature,
-        )
-        .await?;
-
-        info!("  ✅ Trade stored in database");
+        });
     }
 
     Ok(())
@@ -333,21 +526,20 @@ pub async fn process_fill(
 /// Main event processor - routes events to specific handlers
 pub async fn process_event(
     event_type: &str,
-    tx: &EncodedConfirmedTransactionWithStatusMeta,
-    signature: &str,
-    slot: u64,
-    timestamp: i64,
+    position: EventPosition,
+    tx: &IngestedTransaction,
     db: &crate::Database,
+    writes: &mut TransactionWrites,
 ) -> Result<(), Box<dyn std::error::Error>> {
     match event_type {
         "CreateMarket" => {
-            process_create_market(tx, signature, slot, timestamp, db).await?;
+            process_create_market(tx, position, db, writes).await?;
         }
         "PlaceOrder" => {
-            process_place_order(tx, signature, slot, timestamp, db).await?;
+            process_place_order(tx, position, db, writes).await?;
         }
         "Fill" | "Consume" | "ConsumeEvents" => {
-            process_fill(tx, signature, slot, timestamp, db).await?;
+            process_fill(tx, position, db, writes).await?;
         }
         _ => {
             // For other events, just log them (already done in the main listener)
@@ -356,3 +548,218 @@ pub async fn process_event(
 
     Ok(())
 }
+
+/// Events found in a transaction and the rows derived from them
+#[derive(Debug, Clone)]
+pub struct DerivedTransaction {
+    pub events: Vec<&'static str>,
+    pub writes: TransactionWrites,
+    /// Events whose structured data could not be extracted
+    pub decode_errors: Vec<String>,
+}
+
+/// Index one transaction from an ingestion source: log every event found in its
+/// program logs, extract structured data from it and persist everything, together
+/// with the checkpoint, in one database transaction
+///
+/// Returns the events found. A transaction that was already indexed is not written again.
+pub async fn process_transaction(
+    tx: &IngestedTransaction,
+    deployment: &Deployment,
+    db: &crate::Database,
+) -> Result<Vec<&'static str>, Box<dyn std::error::Error>> {
+    let (derived, applied) = index_transaction(tx, deployment, db, ApplyMode::Live).await?;
+
+    if !applied {
+        info!("  ⏭️  Already indexed, skipping");
+    } else if let Some(attempt) = &derived.writes.failed_attempt {
+        info!("  ❌ Failed on-chain, recorded: {}", attempt.error);
+    } else if !derived.writes.orders.is_empty() || !derived.writes.trades.is_empty() {
+        info!(
+            "  ✅ Stored {} orders, {} trades",
+            derived.writes.orders.len(),
+            derived.writes.trades.len()
+        );
+    }
+
+    Ok(derived.events)
+}
+
+/// Derive and apply one transaction of `deployment` in `mode`, sending decode and persist
+/// failures to the dead-letter queue
+///
+/// Returns what was derived and whether it was written (false when already indexed).
+pub async fn index_transaction(
+    tx: &IngestedTransaction,
+    deployment: &Deployment,
+    db: &crate::Database,
+    mode: ApplyMode,
+) -> Result<(DerivedTransaction, bool), Box<dyn std::error::Error>> {
+    let derived = derive_writes(tx, deployment, db).await;
+
+    let applied = match db
+        .apply_transaction(&derived.writes, mode)
+        .await
+        .map_err(|e| e.to_string())
+    {
+        Ok(applied) => applied,
+        Err(message) => {
+            dead_letter::record_failure(
+                db,
+                deployment,
+                &tx.signature,
+                tx.slot,
+                FailureStage::Persist,
+                &message,
+            )
+            .await;
+            return Err(message.into());
+        }
+    };
+
+    if applied {
+        let program = deployment.program();
+        metrics().transactions.inc(&[&program]);
+        for event_type in &derived.events {
+            metrics().events.inc(&[&program, event_type]);
+        }
+        if let Some(attempt) = &derived.writes.failed_attempt {
+            let error = attempt.error_name.as_deref().unwrap_or("other");
+            metrics().failed_attempts.inc(&[&program, error]);
+        }
+    }
+
+    if applied && !derived.decode_errors.is_empty() {
+        dead_letter::record_failure(
+            db,
+            deployment,
+            &tx.signature,
+            tx.slot,
+            FailureStage::Decode,
+            &derived.decode_errors.join("; "),
+        )
+        .await;
+    }
+
+    Ok((derived, applied))
+}
+
+/// Events found in a transaction of `deployment` and the rows derived from them, without
+/// writing anything
+///
+/// A transaction that failed on-chain changes no state: its logs still name the
+/// instructions it tried, so only a failed attempt is derived from it.
+pub async fn derive_writes(
+    tx: &IngestedTransaction,
+    deployment: &Deployment,
+    db: &crate::Database,
+) -> DerivedTransaction {
+    let events = parse_event_positions(&tx.log_messages());
+
+    let mut writes = TransactionWrites {
+        signature: tx.signature.clone(),
+        cluster: deployment.cluster.clone(),
+        program_id: deployment.program(),
+        slot: tx.slot as i64,
+        tx_index: tx.tx_index.map(|index| index as i32),
+        timestamp: tx.timestamp_ms(),
+        time_estimated: tx.time_estimated(),
+        parser_version: PARSER_VERSION,
+        raw_transaction: raw_transaction(tx),
+        ..Default::default()
+    };
+    let mut decode_errors = Vec::new();
+
+    if let Some(error) = tx.error() {
+        let instruction_events: Vec<(i32, &'static str)> = events
+            .iter()
+            .map(|(position, event_type)| (position.instruction_index, *event_type))
+            .collect();
+        writes.failed_attempt = Some(failed_attempt(
+            tx,
+            &instruction_events,
+            &TransactionFailure::from_error(&error),
+        ));
+        return DerivedTransaction {
+            events: Vec::new(),
+            writes,
+            decode_errors,
+        };
+    }
+
+    for &(position, event_type) in &events {
+        // Log raw event
+        writes.events.push(EventWrite {
+            position,
+            event_type: event_type.to_string(),
+            market_id: None,    // would need to parse from logs
+            user_address: None, // would need to parse from logs
+        });
+
+        // Process event to extract structured data
+        if let Err(e) = process_event(event_type, position, tx, db, &mut writes).await {
+            warn!("Failed to process event {}: {}", event_type, e);
+            metrics()
+                .decode_failures
+                .inc(&[&writes.program_id, event_type]);
+            decode_errors.push(format!(
+                "{} (instruction {}): {}",
+                event_type, position.instruction_index, e
+            ));
+        }
+    }
+
+    DerivedTransaction {
+        events: events
+            .into_iter()
+            .map(|(_, event_type)| event_type)
+            .collect(),
+        writes,
+        decode_errors,
+    }
+}
+
+/// `raw_transactions` row archiving `tx`; None (with a warning) if it cannot be encoded
+fn raw_transaction(tx: &IngestedTransaction) -> Option<RawTransactionWrite> {
+    match compress_transaction(tx) {
+        Ok(data) => Some(RawTransactionWrite {
+            tx_index: tx.tx_index.map(|index| index as i32),
+            account_keys: extract_account_keys(&tx.transaction),
+            data,
+        }),
+        Err(e) => {
+            warn!("Failed to archive {}: {}", tx.signature, e);
+            None
+        }
+    }
+}
+
+/// `failed_attempts` row of a transaction that failed with `failure`, attributed to the
+/// OpenBook instruction that failed
+fn failed_attempt(
+    tx: &IngestedTransaction,
+    events: &[(i32, &'static str)],
+    failure: &TransactionFailure,
+) -> FailedAttemptWrite {
+    let event_type = failure.instruction_index.and_then(|index| {
+        events
+            .iter()
+            .find(|(instruction_index, _)| *instruction_index == index)
+            .map(|(_, event_type)| event_type.to_string())
+    });
+    // Only OpenBook instructions name a market, each at its own account position
+    let market_id = match (failure.instruction_index, &event_type) {
+        (Some(index), Some(_)) => instruction_market(&tx.transaction, index),
+        _ => None,
+    };
+
+    FailedAttemptWrite {
+        instruction_index: failure.instruction_index,
+        event_type,
+        market_id,
+        user_address: extract_account_keys(&tx.transaction).first().cloned(),
+        error_code: failure.error_code.map(i64::from),
+        error_name: failure.error_name.map(str::to_string),
+        error: failure.message.clone(),
+    }
+}
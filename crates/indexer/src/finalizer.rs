@@ -0,0 +1,171 @@
+//! Settles rows indexed at `confirmed`: slots that finalize have their rows promoted
+//! to `finalized`, confirmed slots that are skipped instead have their rows rolled back.
+//! Trades indexed live are then placed in their finalized block, which gives them an id.
+
+use solana_client::rpc_config::RpcBlockConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_transaction_status::TransactionDetails;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::metrics::metrics;
+use crate::rpc_pool::RpcPool;
+use crate::Database;
+
+/// Unfinalized slots settled per database round trip
+const SLOTS_PER_BATCH: i64 = 500;
+
+/// Widest slot range `getBlocks` accepts
+const MAX_BLOCK_RANGE: i64 = 500_000;
+
+/// What one finalizer pass changed
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FinalizeReport {
+    pub finalized_slot: u64,
+    /// Transactions whose slot finalized
+    pub promoted: u64,
+    /// Signatures of the transactions of skipped slots
+    pub rolled_back: Vec<String>,
+    /// Live trades placed in their block
+    pub placed: u64,
+}
+
+/// Settle the cluster's confirmed slots every `interval`
+pub async fn run_finalizer(
+    rpc_pool: Arc<RpcPool>,
+    db: Database,
+    cluster: String,
+    interval: Duration,
+) {
+    info!(
+        "🔒 Finalizer started on {} (every {}s)",
+        cluster,
+        interval.as_secs()
+    );
+
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+
+        match finalize(&rpc_pool, &db, &cluster).await {
+            Ok(report) => {
+                if report.promoted > 0 {
+                    info!(
+                        "🔒 Finalized {} transactions up to slot {}",
+                        report.promoted, report.finalized_slot
+                    );
+                }
+                if report.placed > 0 {
+                    info!("📍 Placed {} live trades in their blocks", report.placed);
+                }
+                if !report.rolled_back.is_empty() {
+                    warn!(
+                        "⏪ Rolled back {} transactions of skipped slots: {}",
+                        report.rolled_back.len(),
+                        report.rolled_back.join(", ")
+                    );
+                }
+            }
+            Err(e) => warn!("⚠️  Finalizer pass failed: {}", e),
+        }
+    }
+}
+
+/// Slots `getBlocks` is asked about for the ascending unfinalized `slots`: from the first
+/// to the last, at most `MAX_BLOCK_RANGE` wide
+pub fn block_range(slots: &[i64]) -> Option<(i64, i64)> {
+    let (first, last) = (*slots.first()?, *slots.last()?);
+    Some((first, last.min(first + MAX_BLOCK_RANGE - 1)))
+}
+
+/// Split the unfinalized `slots` up to `last` into those with a block in the finalized
+/// chain (`blocks`) and the skipped ones; slots after `last` were not asked about
+pub fn partition_slots(slots: &[i64], last: i64, blocks: &HashSet<u64>) -> (Vec<i64>, Vec<i64>) {
+    slots
+        .iter()
+        .filter(|slot| **slot <= last)
+        .partition(|slot| blocks.contains(&(**slot as u64)))
+}
+
+/// One pass: every confirmed slot of `cluster` at or below the finalized slot is either
+/// in the finalized chain (its rows are promoted) or was skipped (its rows are deleted);
+/// then trades indexed live up to the finalized slot are placed in their block
+pub async fn finalize(
+    rpc_pool: &RpcPool,
+    db: &Database,
+    cluster: &str,
+) -> Result<FinalizeReport, Box<dyn std::error::Error>> {
+    let finalized_slot = rpc_pool
+        .call(|rpc| async move {
+            rpc.get_slot_with_commitment(CommitmentConfig::finalized())
+                .await
+        })
+        .await?;
+    let mut report = FinalizeReport {
+        finalized_slot,
+        ..Default::default()
+    };
+
+    loop {
+        let slots = db
+            .get_unfinalized_slots(cluster, finalized_slot as i64, SLOTS_PER_BATCH)
+            .await?;
+        let Some((first, last)) = block_range(&slots) else {
+            break;
+        };
+
+        let blocks: HashSet<u64> = rpc_pool
+            .call(|rpc| async move {
+                rpc.get_blocks_with_commitment(
+                    first as u64,
+                    Some(last as u64),
+                    CommitmentConfig::finalized(),
+                )
+                .await
+            })
+            .await?
+            .into_iter()
+            .collect();
+
+        let (finalized, skipped) = partition_slots(&slots, last, &blocks);
+
+        report.promoted += db.finalize_slots(cluster, &finalized).await?;
+        if !skipped.is_empty() {
+            let rolled_back = db.rollback_slots(cluster, &skipped).await?;
+            metrics()
+                .rolled_back_transactions
+                .inc_by(&[cluster], rolled_back.len() as f64);
+            report.rolled_back.extend(rolled_back);
+        }
+    }
+
+    // One getBlock per slot, off the live path that left these trades unplaced
+    let unplaced = db
+        .get_unplaced_slots(cluster, finalized_slot as i64, SLOTS_PER_BATCH)
+        .await?;
+    for slot in unplaced {
+        let config = RpcBlockConfig {
+            encoding: None,
+            transaction_details: Some(TransactionDetails::Signatures),
+            rewards: Some(false),
+            commitment: Some(CommitmentConfig::finalized()),
+            max_supported_transaction_version: Some(0),
+        };
+        let block = rpc_pool
+            .call(|rpc| async move { rpc.get_block_with_config(slot as u64, config).await })
+            .await?;
+        report.placed += db
+            .place_slot(cluster, slot, &block.signatures.unwrap_or_default())
+            .await?;
+    }
+
+    db.set_finalized_slot(cluster, finalized_slot as i64)
+        .await?;
+    metrics()
+        .finalized_slot
+        .set(&[cluster], finalized_slot as f64);
+
+    Ok(report)
+}
@@ -0,0 +1,171 @@
+//! Block times for transactions delivered without one
+//!
+//! A missing time is fetched with `getBlockTime` and cached per slot. When the node has
+//! none for the slot, it is estimated from the nearest slots with a known time and the
+//! transaction is marked as estimated. Estimates are cached for `ESTIMATE_TTL`, after
+//! which the node is asked again, as it may have learned the slot's time by then.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use super::IngestedTransaction;
+use crate::rate_limiter::AdaptiveRateLimiter;
+use crate::rpc_pool::RpcPool;
+
+/// Target slot duration, used to extrapolate from a single neighbouring slot
+pub const SLOT_DURATION_MS: i64 = 400;
+
+/// Slots whose block times are kept, for lookups and as neighbours for estimates
+const BLOCK_TIMES_CACHED: usize = 4096;
+
+/// How long an estimate is reused before `getBlockTime` is tried again
+const ESTIMATE_TTL: Duration = Duration::from_secs(30);
+
+/// A cached block time (unix seconds)
+#[derive(Debug, Clone, Copy)]
+struct CachedTime {
+    time: i64,
+    /// When the time was estimated; None for a time the chain reported
+    estimated_at: Option<Instant>,
+}
+
+/// Block times by slot, shared by the sources that use one
+#[derive(Default)]
+pub struct BlockTimeCache {
+    times: Mutex<BTreeMap<u64, CachedTime>>,
+}
+
+impl BlockTimeCache {
+    /// Remember the block time of `slot`, evicting the cached slot farthest from it
+    fn remember(&self, slot: u64, time: i64, estimated: bool) {
+        let mut times = self.times.lock().unwrap();
+        let estimated_at = estimated.then(Instant::now);
+        times.insert(slot, CachedTime { time, estimated_at });
+        if times.len() > BLOCK_TIMES_CACHED {
+            let first = *times.keys().next().unwrap();
+            let last = *times.keys().next_back().unwrap();
+            let farthest = if slot - first >= last - slot {
+                first
+            } else {
+                last
+            };
+            times.remove(&farthest);
+        }
+    }
+}
+
+/// Fill in the block time of `tx` when its source did not deliver one
+///
+/// The cache is tried first, then `getBlockTime`; if neither knows the slot, the time
+/// is estimated from neighbouring slots or, without any, from the tip.
+pub async fn resolve_block_time(
+    rpc_pool: &RpcPool,
+    rate_limiter: &AdaptiveRateLimiter,
+    block_times: &BlockTimeCache,
+    tx: &mut IngestedTransaction,
+) {
+    let slot = tx.slot;
+    if let Some(block_time) = tx.block_time() {
+        block_times.remember(slot, block_time, false);
+        return;
+    }
+
+    let cached = block_times.times.lock().unwrap().get(&slot).copied();
+    match cached.map(|cached| (cached.time, cached.estimated_at)) {
+        Some((time, None)) => {
+            tx.transaction.block_time = Some(time);
+            return;
+        }
+        Some((time, Some(at))) if at.elapsed() < ESTIMATE_TTL => {
+            tx.estimated_block_time = Some(time);
+            return;
+        }
+        _ => {}
+    }
+
+    rate_limiter.acquire().await;
+    let request_start = Instant::now();
+    match rpc_pool
+        .call(|rpc| async move { rpc.get_block_time(slot).await })
+        .await
+    {
+        Ok(block_time) => {
+            rate_limiter.record_success(request_start.elapsed());
+            block_times.remember(slot, block_time, false);
+            tx.transaction.block_time = Some(block_time);
+            return;
+        }
+        Err(e) => {
+            rate_limiter.record_error(request_start.elapsed(), &e);
+            warn!("⚠️  No block time for slot {}: {}", slot, e);
+        }
+    }
+
+    estimate_block_time(block_times, tx);
+    if tx.estimated_block_time.is_none() {
+        // Nothing known nearby: count back from the tip, which is about now
+        let commitment = rpc_pool.commitment();
+        if let Ok(tip) = rpc_pool
+            .call(|rpc| async move { rpc.get_slot_with_commitment(commitment).await })
+            .await
+        {
+            let now_ms = chrono::Utc::now().timestamp_millis();
+            let behind_ms = tip.saturating_sub(slot) as i64 * SLOT_DURATION_MS;
+            tx.estimated_block_time = Some((now_ms - behind_ms) / 1000);
+        }
+    }
+    if let Some(estimate) = tx.estimated_block_time {
+        block_times.remember(slot, estimate, true);
+    }
+}
+
+/// Estimate the block time of `tx` from cached neighbouring slots, without RPC
+///
+/// Transactions that have a block time only feed the cache. Only known times serve as
+/// neighbours, never other estimates.
+pub fn estimate_block_time(block_times: &BlockTimeCache, tx: &mut IngestedTransaction) {
+    if let Some(block_time) = tx.block_time() {
+        block_times.remember(tx.slot, block_time, false);
+        return;
+    }
+
+    let times = block_times.times.lock().unwrap();
+    if let Some(cached) = times
+        .get(&tx.slot)
+        .filter(|cached| cached.estimated_at.is_none())
+    {
+        tx.transaction.block_time = Some(cached.time);
+        return;
+    }
+    let known = |(slot, cached): (&u64, &CachedTime)| {
+        cached
+            .estimated_at
+            .is_none()
+            .then_some((*slot, cached.time))
+    };
+    let before = times.range(..tx.slot).rev().find_map(known);
+    let after = times.range(tx.slot + 1..).find_map(known);
+    tx.estimated_block_time = interpolate_block_time(tx.slot, before, after);
+}
+
+/// Block time of `slot` (unix seconds) from the nearest earlier and later slots with a
+/// known time: interpolated between both, extrapolated at `SLOT_DURATION_MS` from one
+pub fn interpolate_block_time(
+    slot: u64,
+    before: Option<(u64, i64)>,
+    after: Option<(u64, i64)>,
+) -> Option<i64> {
+    let estimate_ms = match (before, after) {
+        (Some((slot_a, time_a)), Some((slot_b, time_b))) => {
+            let span = (slot_b - slot_a) as i64;
+            let elapsed = (slot - slot_a) as i64;
+            time_a * 1000 + (time_b - time_a) * 1000 * elapsed / span
+        }
+        (Some((slot_a, time_a)), None) => time_a * 1000 + (slot - slot_a) as i64 * SLOT_DURATION_MS,
+        (None, Some((slot_b, time_b))) => time_b * 1000 - (slot_b - slot) as i64 * SLOT_DURATION_MS,
+        (None, None) => return None,
+    };
+    Some(estimate_ms / 1000)
+}
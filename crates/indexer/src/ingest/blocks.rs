@@ -0,0 +1,271 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_config::RpcBlockConfig;
+use solana_client::rpc_custom_error::{
+    JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED, JSON_RPC_SERVER_ERROR_SLOT_SKIPPED,
+};
+use solana_client::rpc_request::RpcError;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{TransactionDetails, UiConfirmedBlock, UiTransactionEncoding};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use super::block_time::{resolve_block_time, BlockTimeCache};
+use super::{touches_program, IngestResult, IngestSource, IngestedTransaction};
+use crate::rate_limiter::AdaptiveRateLimiter;
+use crate::rpc_pool::RpcPool;
+
+/// Pause after a failed round before the failing slot is retried
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Tuning of the block source
+#[derive(Clone)]
+pub struct BlockSourceConfig {
+    /// Wait between tip checks once caught up
+    pub poll_interval: Duration,
+    /// Slots listed per `getBlocks` call (and fetched per round)
+    pub batch_slots: u64,
+    /// Blocks fetched in parallel
+    pub concurrency: usize,
+    pub rate_limiter: Arc<AdaptiveRateLimiter>,
+    pub block_times: Arc<BlockTimeCache>,
+}
+
+/// `getBlock` walking of confirmed slots
+///
+/// `getBlocks` lists the slots that produced a block, those blocks are fetched
+/// concurrently (full transaction details, versioned) and the transactions touching any of
+/// the programs are yielded in slot and in-block order, each with the block time of its
+/// block. One walk serves every indexed program.
+pub struct BlockSource {
+    rpc_pool: Arc<RpcPool>,
+    program_ids: Vec<Pubkey>,
+    config: BlockSourceConfig,
+    next_slot: u64,
+    ready: VecDeque<IngestedTransaction>,
+}
+
+impl BlockSource {
+    /// Start at `start_slot`, or at the current tip when `None`
+    pub async fn start(
+        rpc_pool: Arc<RpcPool>,
+        program_ids: Vec<Pubkey>,
+        start_slot: Option<u64>,
+        config: BlockSourceConfig,
+    ) -> IngestResult<Self> {
+        let next_slot = match start_slot {
+            Some(slot) => slot,
+            None => tip_slot(&rpc_pool).await?,
+        };
+
+        info!(
+            "🧱 Block source starting at slot {} ({} slots per round, {} parallel fetches)",
+            next_slot, config.batch_slots, config.concurrency
+        );
+
+        Ok(Self {
+            rpc_pool,
+            program_ids,
+            config,
+            next_slot,
+            ready: VecDeque::new(),
+        })
+    }
+
+    /// Slot the next round starts at
+    pub fn next_slot(&self) -> u64 {
+        self.next_slot
+    }
+
+    /// Fetch the next round of blocks; returns false when already at the tip
+    async fn load_next_blocks(&mut self) -> IngestResult<bool> {
+        let tip = tip_slot(&self.rpc_pool).await?;
+        let Some((start, end)) = round_slots(self.next_slot, tip, self.config.batch_slots) else {
+            return Ok(false);
+        };
+
+        // Skipped slots have no block: only ask for the ones that were produced
+        let commitment = self.rpc_pool.commitment();
+        let slots = self
+            .rpc_pool
+            .call(|rpc| async move {
+                rpc.get_blocks_with_commitment(start, Some(end), commitment)
+                    .await
+            })
+            .await?;
+
+        let round_start = Instant::now();
+        let mut blocks = futures::stream::iter(slots.iter().copied())
+            .map(|slot| {
+                fetch_block_transactions(
+                    &self.rpc_pool,
+                    &self.config.rate_limiter,
+                    &self.config.block_times,
+                    &self.program_ids,
+                    slot,
+                )
+            })
+            .buffered(self.config.concurrency.max(1));
+
+        // `buffered` keeps slot order; stop at the first failure so that slot is retried
+        let mut loaded = Vec::new();
+        let mut failure = None;
+        while let Some((slot, result)) = blocks.next().await {
+            match result {
+                Ok(transactions) => loaded.push((slot, transactions)),
+                Err(e) => {
+                    failure = Some((slot, e));
+                    break;
+                }
+            }
+        }
+        drop(blocks);
+
+        let mut matched = 0;
+        for (_, transactions) in loaded {
+            matched += transactions.len();
+            self.ready.extend(transactions);
+        }
+
+        if let Some((slot, e)) = failure {
+            self.next_slot = slot;
+            return Err(format!("failed to fetch block {}: {}", slot, e).into());
+        }
+
+        self.next_slot = end + 1;
+
+        if matched > 0 || end - start >= self.config.batch_slots / 2 {
+            info!(
+                "🧱 Slots {}-{}: {} blocks, {} OpenBook transactions ({:.1}s)",
+                start,
+                end,
+                slots.len(),
+                matched,
+                round_start.elapsed().as_secs_f64()
+            );
+        }
+
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl IngestSource for BlockSource {
+    fn name(&self) -> &'static str {
+        "blocks"
+    }
+
+    async fn next_transaction(&mut self) -> IngestResult<Option<IngestedTransaction>> {
+        loop {
+            if let Some(tx) = self.ready.pop_front() {
+                return Ok(Some(tx));
+            }
+
+            match self.load_next_blocks().await {
+                Ok(true) => {}
+                // Caught up: wait for the chain to confirm more slots
+                Ok(false) => tokio::time::sleep(self.config.poll_interval).await,
+                Err(e) => {
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+/// Program transactions of one block, in block order
+async fn fetch_block_transactions(
+    rpc_pool: &RpcPool,
+    rate_limiter: &AdaptiveRateLimiter,
+    block_times: &BlockTimeCache,
+    program_ids: &[Pubkey],
+    slot: u64,
+) -> (u64, Result<Vec<IngestedTransaction>, ClientError>) {
+    let config = RpcBlockConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        transaction_details: Some(TransactionDetails::Full),
+        rewards: Some(false),
+        commitment: Some(rpc_pool.commitment()),
+        max_supported_transaction_version: Some(0),
+    };
+
+    rate_limiter.acquire().await;
+    let request_start = Instant::now();
+
+    let block = match rpc_pool
+        .call(|rpc| async move { rpc.get_block_with_config(slot, config).await })
+        .await
+    {
+        Ok(block) => {
+            rate_limiter.record_success(request_start.elapsed());
+            block
+        }
+        // Listed by getBlocks but skipped after all (e.g. pruned): nothing to index
+        Err(e) if is_skipped_slot(&e) => {
+            warn!("⚠️  Slot {} has no block, skipping", slot);
+            return (slot, Ok(Vec::new()));
+        }
+        Err(e) => {
+            rate_limiter.record_error(request_start.elapsed(), &e);
+            return (slot, Err(e));
+        }
+    };
+
+    let mut transactions = block_transactions(slot, block, program_ids);
+    // Resolved once, the block's other transactions hit the cache
+    for tx in &mut transactions {
+        resolve_block_time(rpc_pool, rate_limiter, block_times, tx).await;
+    }
+
+    (slot, Ok(transactions))
+}
+
+/// First and last slot of the round starting at `next_slot`: at most `batch_slots`, up to
+/// the tip; `None` once caught up
+pub fn round_slots(next_slot: u64, tip: u64, batch_slots: u64) -> Option<(u64, u64)> {
+    if next_slot > tip {
+        return None;
+    }
+    Some((next_slot, tip.min(next_slot + batch_slots.max(1) - 1)))
+}
+
+/// Transactions of a block touching any of the programs, in block order with their index
+pub fn block_transactions(
+    slot: u64,
+    block: UiConfirmedBlock,
+    program_ids: &[Pubkey],
+) -> Vec<IngestedTransaction> {
+    let block_time = block.block_time;
+    block
+        .transactions
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .filter(|(_, tx)| program_ids.iter().any(|p| touches_program(tx, p)))
+        .filter_map(|(index, tx)| {
+            IngestedTransaction::from_block_entry(slot, block_time, Some(index as u32), tx)
+        })
+        .collect()
+}
+
+/// Tip at the pool's commitment
+async fn tip_slot(rpc_pool: &RpcPool) -> Result<u64, ClientError> {
+    let commitment = rpc_pool.commitment();
+    rpc_pool
+        .call(|rpc| async move { rpc.get_slot_with_commitment(commitment).await })
+        .await
+}
+
+/// The slot was skipped by the leader, so it has no block
+pub fn is_skipped_slot(error: &ClientError) -> bool {
+    matches!(
+        error.kind(),
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. })
+            if *code == JSON_RPC_SERVER_ERROR_SLOT_SKIPPED
+                || *code == JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED
+    )
+}
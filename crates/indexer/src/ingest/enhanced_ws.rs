@@ -0,0 +1,342 @@
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::EncodedTransactionWithStatusMeta;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+use tracing::{error, info, warn};
+
+use super::block_time::{resolve_block_time, BlockTimeCache};
+use super::logs::{fetch_transaction, list_signatures_since, BlockSignatureCache};
+use super::{IngestResult, IngestSource, IngestedTransaction, ReconnectBackoff};
+use crate::rate_limiter::AdaptiveRateLimiter;
+use crate::rpc_pool::RpcPool;
+
+/// Interval between keepalive pings
+const PING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Tuning of the enhanced WebSocket source
+#[derive(Clone)]
+pub struct EnhancedWsSourceConfig {
+    /// Provider WebSocket endpoint serving `transactionSubscribe` (e.g. an "atlas" URL)
+    pub url: String,
+    /// No frame (notification or pong) for this long means the connection is stalled
+    pub stall_timeout: Duration,
+    /// Paces the RPC requests of gap filling after a reconnect
+    pub rate_limiter: Arc<AdaptiveRateLimiter>,
+    /// Places gap-filled transactions in their block
+    pub block_signatures: Arc<BlockSignatureCache>,
+    pub block_times: Arc<BlockTimeCache>,
+}
+
+/// `result` of a `transactionNotification`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionNotification {
+    signature: String,
+    slot: u64,
+    transaction: EncodedTransactionWithStatusMeta,
+    #[serde(default)]
+    transaction_index: Option<u32>,
+}
+
+/// Full transactions pushed through a provider's enhanced WebSocket `transactionSubscribe`
+///
+/// Unlike the logs source, notifications already carry the transaction and its status
+/// meta, so no `getTransaction` round trip is needed. This is the JSON-RPC WebSocket API
+/// some providers expose, not a Yellowstone gRPC stream; missed transactions after a
+/// reconnect are recovered through the RPC pool.
+pub struct EnhancedWsSource {
+    receiver: mpsc::UnboundedReceiver<IngestResult<IngestedTransaction>>,
+    /// Tells the background task to close the stream and stop
+    stop: watch::Sender<bool>,
+    feeder: Option<JoinHandle<()>>,
+}
+
+impl EnhancedWsSource {
+    pub fn start(
+        rpc_pool: Arc<RpcPool>,
+        program_id: Pubkey,
+        resume_from: Option<String>,
+        config: EnhancedWsSourceConfig,
+    ) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (stop, stopped) = watch::channel(false);
+
+        let feeder = tokio::spawn(feed_transactions(
+            rpc_pool,
+            program_id,
+            resume_from,
+            config,
+            sender,
+            stopped,
+        ));
+
+        Self {
+            receiver,
+            stop,
+            feeder: Some(feeder),
+        }
+    }
+}
+
+#[async_trait]
+impl IngestSource for EnhancedWsSource {
+    fn name(&self) -> &'static str {
+        "enhanced_ws"
+    }
+
+    async fn next_transaction(&mut self) -> IngestResult<Option<IngestedTransaction>> {
+        self.receiver.recv().await.transpose()
+    }
+
+    async fn close(&mut self) {
+        let _ = self.stop.send(true);
+        if let Some(feeder) = self.feeder.take() {
+            let _ = feeder.await;
+        }
+    }
+}
+
+type Sender = mpsc::UnboundedSender<IngestResult<IngestedTransaction>>;
+
+/// Keep the stream alive and forward transactions until the source is closed or dropped
+async fn feed_transactions(
+    rpc_pool: Arc<RpcPool>,
+    program_id: Pubkey,
+    mut last_signature: Option<String>,
+    config: EnhancedWsSourceConfig,
+    sender: Sender,
+    mut stop: watch::Receiver<bool>,
+) {
+    let mut backoff = ReconnectBackoff::default();
+
+    loop {
+        let session_start = Instant::now();
+
+        if let Err(e) = run_session(
+            &rpc_pool,
+            &program_id,
+            &mut last_signature,
+            &config,
+            &sender,
+            &mut stop,
+        )
+        .await
+        {
+            warn!("⚠️  Enhanced WebSocket session ended: {}", e);
+        }
+
+        if sender.is_closed() || *stop.borrow() {
+            return;
+        }
+
+        let delay = backoff.after_session(session_start.elapsed());
+        info!(
+            "🔄 Reconnecting to the enhanced WebSocket in {}s, then gap-filling from the last signature...",
+            delay.as_secs()
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = stop.changed() => return,
+        }
+    }
+}
+
+/// Subscribe, gap-fill while buffering the stream, then follow it
+async fn run_session(
+    rpc_pool: &Arc<RpcPool>,
+    program_id: &Pubkey,
+    last_signature: &mut Option<String>,
+    config: &EnhancedWsSourceConfig,
+    sender: &Sender,
+    stop: &mut watch::Receiver<bool>,
+) -> IngestResult<()> {
+    info!(
+        "🔌 Connecting to enhanced WebSocket {}",
+        redact(&config.url)
+    );
+    let (mut ws, _) = tokio_tungstenite::connect_async(config.url.as_str()).await?;
+
+    let commitment = rpc_pool.commitment().commitment;
+    let subscribe = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "transactionSubscribe",
+        "params": [
+            {
+                "accountInclude": [program_id.to_string()],
+                "vote": false,
+                "failed": true
+            },
+            {
+                "commitment": commitment,
+                "encoding": "json",
+                "transactionDetails": "full",
+                "showRewards": false,
+                "maxSupportedTransactionVersion": 0
+            }
+        ]
+    });
+    ws.send(Message::Text(subscribe.to_string())).await?;
+
+    info!("✅ Subscribed to OpenBook transactions via the enhanced WebSocket");
+
+    // Recover what was missed while disconnected, buffering the live stream meanwhile
+    let mut buffered = Vec::new();
+    let gap = list_signatures_since(
+        rpc_pool,
+        &config.rate_limiter,
+        program_id,
+        last_signature.clone(),
+    );
+    tokio::pin!(gap);
+
+    let gap_result = loop {
+        tokio::select! {
+            result = &mut gap => break result,
+            frame = ws.next() => match frame {
+                Some(frame) => {
+                    if let Some(tx) = parse_frame(frame?)? {
+                        buffered.push(tx);
+                    }
+                }
+                None => return Err("enhanced WebSocket closed".into()),
+            },
+            _ = stop.changed() => return close_stream(ws).await,
+        }
+    };
+
+    let mut gap_signatures = HashSet::new();
+    match gap_result {
+        Ok(mut gap) => {
+            // Everything from the first buffered slot on is delivered by the stream
+            if let Some(first_live_slot) = buffered.iter().map(|tx| tx.slot).min() {
+                gap.retain(|sig| sig.slot <= first_live_slot);
+            }
+
+            if !gap.is_empty() {
+                info!("📜 Gap fill: {} transactions to backfill", gap.len());
+            }
+
+            for pending in gap {
+                gap_signatures.insert(pending.signature.clone());
+                let fetched = fetch_transaction(
+                    rpc_pool.clone(),
+                    config.rate_limiter.clone(),
+                    config.block_signatures.clone(),
+                    config.block_times.clone(),
+                    *program_id,
+                    pending,
+                )
+                .await;
+                if let Ok(tx) = &fetched {
+                    *last_signature = Some(tx.signature.clone());
+                }
+                sender.send(fetched)?;
+            }
+        }
+        Err(e) => {
+            error!("❌ Gap fill failed: {}", e);
+            error!("⚠️  Continuing with real-time indexing only...");
+        }
+    }
+
+    for mut tx in buffered {
+        if gap_signatures.contains(&tx.signature) {
+            continue;
+        }
+        resolve_block_time(rpc_pool, &config.rate_limiter, &config.block_times, &mut tx).await;
+        *last_signature = Some(tx.signature.clone());
+        sender.send(Ok(tx))?;
+    }
+
+    info!("🚀 Following live OpenBook transactions...");
+
+    let mut last_frame = Instant::now();
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+
+    loop {
+        tokio::select! {
+            frame = ws.next() => match frame {
+                Some(frame) => {
+                    last_frame = Instant::now();
+                    if let Some(mut tx) = parse_frame(frame?)? {
+                        resolve_block_time(rpc_pool, &config.rate_limiter, &config.block_times, &mut tx).await;
+                        *last_signature = Some(tx.signature.clone());
+                        sender.send(Ok(tx))?;
+                    }
+                }
+                None => return Err("enhanced WebSocket closed".into()),
+            },
+            _ = ping.tick() => {
+                if last_frame.elapsed() > config.stall_timeout {
+                    return Err(format!(
+                        "no frames for {}s, connection stalled",
+                        last_frame.elapsed().as_secs()
+                    )
+                    .into());
+                }
+                ws.send(Message::Ping(Vec::new())).await?;
+            }
+            _ = stop.changed() => return close_stream(ws).await,
+        }
+    }
+}
+
+/// Close the WebSocket, which ends the subscription
+async fn close_stream<S>(mut ws: WebSocketStream<S>) -> IngestResult<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    ws.close(None).await?;
+    info!("🔌 Closed the enhanced WebSocket");
+    Ok(())
+}
+
+/// Transaction carried by a WebSocket frame, if it is a transaction notification
+fn parse_frame(frame: Message) -> IngestResult<Option<IngestedTransaction>> {
+    let text = match frame {
+        Message::Text(text) => text,
+        Message::Close(reason) => return Err(format!("server closed stream: {:?}", reason).into()),
+        _ => return Ok(None),
+    };
+
+    let value: serde_json::Value = serde_json::from_str(&text)?;
+
+    if let Some(error) = value.get("error") {
+        return Err(format!("subscription error: {}", error).into());
+    }
+    if value["method"] != "transactionNotification" {
+        // Subscription confirmation
+        return Ok(None);
+    }
+
+    let notification: TransactionNotification =
+        serde_json::from_value(value["params"]["result"].clone())?;
+
+    // The stream has no block time; `resolve_block_time` fills it in before delivery
+    let mut tx = IngestedTransaction::from_block_entry(
+        notification.slot,
+        None,
+        notification.transaction_index,
+        notification.transaction,
+    )
+    .ok_or("notification without a JSON transaction")?;
+    tx.signature = notification.signature;
+
+    Ok(Some(tx))
+}
+
+/// Hide API keys passed in the query string
+fn redact(url: &str) -> &str {
+    url.split('?').next().unwrap_or(url)
+}
@@ -0,0 +1,570 @@
+use async_trait::async_trait;
+use futures::channel::mpsc::{unbounded, UnboundedSender};
+use solana_account_decoder::parse_token::UiTokenAmount;
+use solana_sdk::commitment_config::CommitmentLevel as RpcCommitmentLevel;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::message::v0::{self, LoadedAddresses};
+use solana_sdk::message::{legacy, MessageHeader, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use solana_sdk::transaction_context::TransactionReturnData;
+use solana_transaction_status::{
+    InnerInstruction, InnerInstructions, TransactionStatusMeta, TransactionTokenBalance,
+    UiTransactionEncoding, VersionedTransactionWithStatusMeta,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tonic::codec::{ProstCodec, Streaming};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{ClientTlsConfig, Endpoint};
+use tracing::{error, info, warn};
+
+use super::block_time::{resolve_block_time, BlockTimeCache};
+use super::geyser_proto::{
+    self as proto, subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+    SubscribeRequestFilterSlots, SubscribeRequestFilterTransactions, SubscribeRequestPing,
+    SubscribeUpdate, SubscribeUpdateTransaction,
+};
+use super::logs::{fetch_transaction, list_signatures_since, BlockSignatureCache};
+use super::{IngestResult, IngestSource, IngestedTransaction, ReconnectBackoff};
+use crate::rate_limiter::AdaptiveRateLimiter;
+use crate::rpc_pool::RpcPool;
+
+/// Time allowed to establish the gRPC connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// HTTP/2 keepalive pings, so idle load balancers keep the stream open
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Tuning of the Geyser gRPC source
+#[derive(Clone)]
+pub struct GeyserSourceConfig {
+    /// Yellowstone gRPC endpoint, `https://` for TLS
+    pub url: String,
+    /// Sent as the `x-token` header, for endpoints that need one
+    pub x_token: Option<String>,
+    /// No update (transaction, slot or ping) for this long means the stream is stalled
+    pub stall_timeout: Duration,
+    /// Paces the RPC requests of gap filling after a reconnect
+    pub rate_limiter: Arc<AdaptiveRateLimiter>,
+    /// Places gap-filled transactions in their block
+    pub block_signatures: Arc<BlockSignatureCache>,
+    pub block_times: Arc<BlockTimeCache>,
+}
+
+/// Transactions streamed by a Yellowstone Geyser gRPC `Subscribe`
+///
+/// Updates carry the transaction, its status meta and its position in the block, so no
+/// `getTransaction` round trip is needed. Slot updates double as the heartbeat; missed
+/// transactions after a reconnect are recovered through the RPC pool.
+pub struct GeyserSource {
+    receiver: mpsc::UnboundedReceiver<IngestResult<IngestedTransaction>>,
+    /// Tells the background task to close the stream and stop
+    stop: watch::Sender<bool>,
+    feeder: Option<JoinHandle<()>>,
+}
+
+impl GeyserSource {
+    pub fn start(
+        rpc_pool: Arc<RpcPool>,
+        program_id: Pubkey,
+        resume_from: Option<String>,
+        config: GeyserSourceConfig,
+    ) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (stop, stopped) = watch::channel(false);
+
+        let feeder = tokio::spawn(feed_transactions(
+            rpc_pool,
+            program_id,
+            resume_from,
+            config,
+            sender,
+            stopped,
+        ));
+
+        Self {
+            receiver,
+            stop,
+            feeder: Some(feeder),
+        }
+    }
+}
+
+#[async_trait]
+impl IngestSource for GeyserSource {
+    fn name(&self) -> &'static str {
+        "geyser"
+    }
+
+    async fn next_transaction(&mut self) -> IngestResult<Option<IngestedTransaction>> {
+        self.receiver.recv().await.transpose()
+    }
+
+    async fn close(&mut self) {
+        let _ = self.stop.send(true);
+        if let Some(feeder) = self.feeder.take() {
+            let _ = feeder.await;
+        }
+    }
+}
+
+type Sender = mpsc::UnboundedSender<IngestResult<IngestedTransaction>>;
+
+/// Keep the stream alive and forward transactions until the source is closed or dropped
+async fn feed_transactions(
+    rpc_pool: Arc<RpcPool>,
+    program_id: Pubkey,
+    mut last_signature: Option<String>,
+    config: GeyserSourceConfig,
+    sender: Sender,
+    mut stop: watch::Receiver<bool>,
+) {
+    let mut backoff = ReconnectBackoff::default();
+
+    loop {
+        let session_start = Instant::now();
+
+        if let Err(e) = run_session(
+            &rpc_pool,
+            &program_id,
+            &mut last_signature,
+            &config,
+            &sender,
+            &mut stop,
+        )
+        .await
+        {
+            warn!("⚠️  Geyser session ended: {}", e);
+        }
+
+        if sender.is_closed() || *stop.borrow() {
+            return;
+        }
+
+        let delay = backoff.after_session(session_start.elapsed());
+        info!(
+            "🔄 Reconnecting to Geyser in {}s, then gap-filling from the last signature...",
+            delay.as_secs()
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = stop.changed() => return,
+        }
+    }
+}
+
+/// Subscribe, gap-fill while buffering the stream, then follow it
+async fn run_session(
+    rpc_pool: &Arc<RpcPool>,
+    program_id: &Pubkey,
+    last_signature: &mut Option<String>,
+    config: &GeyserSourceConfig,
+    sender: &Sender,
+    stop: &mut watch::Receiver<bool>,
+) -> IngestResult<()> {
+    info!("🔌 Connecting to Geyser gRPC {}", config.url);
+    let mut endpoint = Endpoint::from_shared(config.url.clone())?
+        .connect_timeout(CONNECT_TIMEOUT)
+        .http2_keep_alive_interval(KEEPALIVE_INTERVAL)
+        .keep_alive_while_idle(true);
+    if config.url.starts_with("https://") {
+        endpoint = endpoint.tls_config(ClientTlsConfig::new())?;
+    }
+    let mut grpc = tonic::client::Grpc::new(endpoint.connect().await?);
+
+    // The request stream stays open for the whole session: pings are answered through it
+    let (requests, request_stream) = unbounded();
+    send_request(
+        &requests,
+        subscribe_request(program_id, rpc_pool.commitment().commitment),
+    )?;
+    let mut request = tonic::Request::new(request_stream);
+    if let Some(token) = &config.x_token {
+        request.metadata_mut().insert("x-token", token.parse()?);
+    }
+
+    grpc.ready().await?;
+    let mut updates: Streaming<SubscribeUpdate> = grpc
+        .streaming(
+            request,
+            PathAndQuery::from_static(proto::SUBSCRIBE_PATH),
+            ProstCodec::default(),
+        )
+        .await?
+        .into_inner();
+
+    info!("✅ Subscribed to OpenBook transactions via Geyser");
+
+    // Recover what was missed while disconnected, buffering the live stream meanwhile
+    let mut buffered = Vec::new();
+    let gap = list_signatures_since(
+        rpc_pool,
+        &config.rate_limiter,
+        program_id,
+        last_signature.clone(),
+    );
+    tokio::pin!(gap);
+
+    let gap_result = loop {
+        tokio::select! {
+            result = &mut gap => break result,
+            update = updates.message() => match update? {
+                Some(update) => {
+                    if let Some(tx) = handle_update(update, &requests)? {
+                        buffered.push(tx);
+                    }
+                }
+                None => return Err("Geyser stream ended".into()),
+            },
+            _ = stop.changed() => return close_stream(requests),
+        }
+    };
+
+    let mut gap_signatures = HashSet::new();
+    match gap_result {
+        Ok(mut gap) => {
+            // Everything from the first buffered slot on is delivered by the stream
+            if let Some(first_live_slot) = buffered.iter().map(|tx| tx.slot).min() {
+                gap.retain(|sig| sig.slot <= first_live_slot);
+            }
+
+            if !gap.is_empty() {
+                info!("📜 Gap fill: {} transactions to backfill", gap.len());
+            }
+
+            for pending in gap {
+                gap_signatures.insert(pending.signature.clone());
+                let fetched = fetch_transaction(
+                    rpc_pool.clone(),
+                    config.rate_limiter.clone(),
+                    config.block_signatures.clone(),
+                    config.block_times.clone(),
+                    *program_id,
+                    pending,
+                )
+                .await;
+                if let Ok(tx) = &fetched {
+                    *last_signature = Some(tx.signature.clone());
+                }
+                sender.send(fetched)?;
+            }
+        }
+        Err(e) => {
+            error!("❌ Gap fill failed: {}", e);
+            error!("⚠️  Continuing with real-time indexing only...");
+        }
+    }
+
+    for mut tx in buffered {
+        if gap_signatures.contains(&tx.signature) {
+            continue;
+        }
+        resolve_block_time(rpc_pool, &config.rate_limiter, &config.block_times, &mut tx).await;
+        *last_signature = Some(tx.signature.clone());
+        sender.send(Ok(tx))?;
+    }
+
+    info!("🚀 Following live OpenBook transactions...");
+
+    let mut last_update = Instant::now();
+    let mut stall_check = tokio::time::interval(config.stall_timeout / 2);
+
+    loop {
+        tokio::select! {
+            update = updates.message() => match update? {
+                Some(update) => {
+                    last_update = Instant::now();
+                    if let Some(mut tx) = handle_update(update, &requests)? {
+                        resolve_block_time(rpc_pool, &config.rate_limiter, &config.block_times, &mut tx).await;
+                        *last_signature = Some(tx.signature.clone());
+                        sender.send(Ok(tx))?;
+                    }
+                }
+                None => return Err("Geyser stream ended".into()),
+            },
+            _ = stall_check.tick() => {
+                if last_update.elapsed() > config.stall_timeout {
+                    return Err(format!(
+                        "no updates for {}s, stream stalled",
+                        last_update.elapsed().as_secs()
+                    )
+                    .into());
+                }
+            }
+            _ = stop.changed() => return close_stream(requests),
+        }
+    }
+}
+
+/// Closing the request stream ends the subscription
+fn close_stream(requests: UnboundedSender<SubscribeRequest>) -> IngestResult<()> {
+    drop(requests);
+    info!("🔌 Closed the Geyser stream");
+    Ok(())
+}
+
+fn send_request(
+    requests: &UnboundedSender<SubscribeRequest>,
+    request: SubscribeRequest,
+) -> IngestResult<()> {
+    requests
+        .unbounded_send(request)
+        .map_err(|_| "Geyser request stream closed".into())
+}
+
+/// Transaction carried by an update; server pings are answered so the stream stays open
+fn handle_update(
+    update: SubscribeUpdate,
+    requests: &UnboundedSender<SubscribeRequest>,
+) -> IngestResult<Option<IngestedTransaction>> {
+    match update.update_oneof {
+        Some(UpdateOneof::Transaction(update)) => transaction_from_update(*update).map(Some),
+        Some(UpdateOneof::Ping(_)) => {
+            send_request(
+                requests,
+                SubscribeRequest {
+                    ping: Some(SubscribeRequestPing { id: 1 }),
+                    ..Default::default()
+                },
+            )?;
+            Ok(None)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Transactions touching `program_id` (failed ones included, votes excluded) and slot
+/// updates as the heartbeat, at `commitment`
+pub fn subscribe_request(program_id: &Pubkey, commitment: RpcCommitmentLevel) -> SubscribeRequest {
+    let commitment = match commitment {
+        RpcCommitmentLevel::Processed => CommitmentLevel::Processed,
+        RpcCommitmentLevel::Finalized => CommitmentLevel::Finalized,
+        _ => CommitmentLevel::Confirmed,
+    };
+
+    SubscribeRequest {
+        slots: HashMap::from([(
+            "slots".to_string(),
+            SubscribeRequestFilterSlots {
+                filter_by_commitment: Some(true),
+            },
+        )]),
+        transactions: HashMap::from([(
+            "openbook".to_string(),
+            SubscribeRequestFilterTransactions {
+                vote: Some(false),
+                failed: Some(true),
+                account_include: vec![program_id.to_string()],
+                ..Default::default()
+            },
+        )]),
+        commitment: Some(commitment as i32),
+        ping: None,
+    }
+}
+
+/// Convert a streamed transaction to the JSON encoding `getTransaction` returns
+///
+/// The stream has no block time; `resolve_block_time` fills it in before delivery.
+pub fn transaction_from_update(
+    update: SubscribeUpdateTransaction,
+) -> IngestResult<IngestedTransaction> {
+    let info = update
+        .transaction
+        .ok_or("transaction update without a transaction")?;
+    let transaction = info
+        .transaction
+        .ok_or("transaction update without a body")?;
+    let meta = info
+        .meta
+        .ok_or("transaction update without a status meta")?;
+
+    let encoded = VersionedTransactionWithStatusMeta {
+        transaction: versioned_transaction(transaction)?,
+        meta: status_meta(meta)?,
+    }
+    .encode(UiTransactionEncoding::Json, Some(0), false)?;
+
+    IngestedTransaction::from_block_entry(
+        update.slot,
+        None,
+        u32::try_from(info.index).ok(),
+        encoded,
+    )
+    .ok_or_else(|| "transaction update without a signature".into())
+}
+
+fn versioned_transaction(transaction: proto::Transaction) -> IngestResult<VersionedTransaction> {
+    let message = transaction
+        .message
+        .ok_or("transaction update without a message")?;
+    let header = message.header.unwrap_or_default();
+    let header = MessageHeader {
+        num_required_signatures: byte(header.num_required_signatures)?,
+        num_readonly_signed_accounts: byte(header.num_readonly_signed_accounts)?,
+        num_readonly_unsigned_accounts: byte(header.num_readonly_unsigned_accounts)?,
+    };
+    let account_keys = pubkeys(message.account_keys)?;
+    let recent_blockhash = Hash::new_from_array(
+        message
+            .recent_blockhash
+            .as_slice()
+            .try_into()
+            .map_err(|_| "invalid recent blockhash")?,
+    );
+    let instructions = message
+        .instructions
+        .into_iter()
+        .map(|ix| compiled_instruction(ix.program_id_index, ix.accounts, ix.data))
+        .collect::<IngestResult<Vec<_>>>()?;
+
+    let message = if message.versioned {
+        VersionedMessage::V0(v0::Message {
+            header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+            address_table_lookups: message
+                .address_table_lookups
+                .into_iter()
+                .map(|lookup| {
+                    Ok(v0::MessageAddressTableLookup {
+                        account_key: pubkey(&lookup.account_key)?,
+                        writable_indexes: lookup.writable_indexes,
+                        readonly_indexes: lookup.readonly_indexes,
+                    })
+                })
+                .collect::<IngestResult<Vec<_>>>()?,
+        })
+    } else {
+        VersionedMessage::Legacy(legacy::Message {
+            header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+        })
+    };
+
+    Ok(VersionedTransaction {
+        signatures: transaction
+            .signatures
+            .iter()
+            .map(|signature| {
+                Signature::try_from(signature.as_slice()).map_err(|_| "invalid signature".into())
+            })
+            .collect::<IngestResult<Vec<_>>>()?,
+        message,
+    })
+}
+
+fn status_meta(meta: proto::TransactionStatusMeta) -> IngestResult<TransactionStatusMeta> {
+    let status = match meta.err {
+        Some(err) => Err(bincode::deserialize::<TransactionError>(&err.err)?),
+        None => Ok(()),
+    };
+
+    let inner_instructions = meta
+        .inner_instructions
+        .into_iter()
+        .map(|inner| {
+            Ok(InnerInstructions {
+                index: byte(inner.index)?,
+                instructions: inner
+                    .instructions
+                    .into_iter()
+                    .map(|ix| {
+                        Ok(InnerInstruction {
+                            instruction: compiled_instruction(
+                                ix.program_id_index,
+                                ix.accounts,
+                                ix.data,
+                            )?,
+                            stack_height: ix.stack_height,
+                        })
+                    })
+                    .collect::<IngestResult<Vec<_>>>()?,
+            })
+        })
+        .collect::<IngestResult<Vec<_>>>()?;
+
+    let return_data = match meta.return_data {
+        Some(data) if !meta.return_data_none => Some(TransactionReturnData {
+            program_id: pubkey(&data.program_id)?,
+            data: data.data,
+        }),
+        _ => None,
+    };
+
+    Ok(TransactionStatusMeta {
+        status,
+        fee: meta.fee,
+        pre_balances: meta.pre_balances,
+        post_balances: meta.post_balances,
+        inner_instructions: (!meta.inner_instructions_none).then_some(inner_instructions),
+        log_messages: (!meta.log_messages_none).then_some(meta.log_messages),
+        pre_token_balances: Some(token_balances(meta.pre_token_balances)?),
+        post_token_balances: Some(token_balances(meta.post_token_balances)?),
+        rewards: None,
+        loaded_addresses: LoadedAddresses {
+            writable: pubkeys(meta.loaded_writable_addresses)?,
+            readonly: pubkeys(meta.loaded_readonly_addresses)?,
+        },
+        return_data,
+        compute_units_consumed: meta.compute_units_consumed,
+    })
+}
+
+fn token_balances(
+    balances: Vec<proto::TokenBalance>,
+) -> IngestResult<Vec<TransactionTokenBalance>> {
+    balances
+        .into_iter()
+        .map(|balance| {
+            let amount = balance.ui_token_amount.unwrap_or_default();
+            Ok(TransactionTokenBalance {
+                account_index: byte(balance.account_index)?,
+                mint: balance.mint,
+                ui_token_amount: UiTokenAmount {
+                    // Protobuf has no null: an unknown UI amount is sent as 0
+                    ui_amount: (amount.ui_amount != 0.0).then_some(amount.ui_amount),
+                    decimals: byte(amount.decimals)?,
+                    amount: amount.amount,
+                    ui_amount_string: amount.ui_amount_string,
+                },
+                owner: balance.owner,
+                program_id: balance.program_id,
+            })
+        })
+        .collect()
+}
+
+fn compiled_instruction(
+    program_id_index: u32,
+    accounts: Vec<u8>,
+    data: Vec<u8>,
+) -> IngestResult<CompiledInstruction> {
+    Ok(CompiledInstruction {
+        program_id_index: byte(program_id_index)?,
+        accounts,
+        data,
+    })
+}
+
+fn pubkeys(keys: Vec<Vec<u8>>) -> IngestResult<Vec<Pubkey>> {
+    keys.iter().map(|key| pubkey(key)).collect()
+}
+
+fn pubkey(key: &[u8]) -> IngestResult<Pubkey> {
+    Pubkey::try_from(key).map_err(|_| "invalid public key".into())
+}
+
+/// Indexes and counts the wire format widens to 32 bits
+fn byte(value: u32) -> IngestResult<u8> {
+    u8::try_from(value).map_err(|_| format!("{} does not fit in a byte", value).into())
+}
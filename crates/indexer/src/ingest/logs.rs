@@ -0,0 +1,628 @@
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::{
+        RpcBlockConfig, RpcTransactionConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+    },
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{TransactionDetails, UiTransactionEncoding};
+use std::collections::{HashSet, VecDeque};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch, OnceCell};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use super::block_time::{resolve_block_time, BlockTimeCache};
+use super::{FetchError, IngestResult, IngestSource, IngestedTransaction, ReconnectBackoff};
+use crate::metrics::metrics;
+use crate::rate_limiter::AdaptiveRateLimiter;
+use crate::rpc_pool::RpcPool;
+
+/// Slots before the subscription slot whose gap-filled signatures are kept for de-duplication
+/// (notifications can arrive for slots slightly older than `getSlot` reported)
+const HANDOFF_SLOT_MARGIN: u64 = 150;
+
+/// Attempts per transaction before the source gives up on it
+const MAX_FETCH_ATTEMPTS: u32 = 5;
+
+/// Blocks whose signature lists are kept to place fetched transactions in their block
+const BLOCK_SIGNATURES_CACHED: usize = 64;
+
+/// Tuning of the logs-plus-fetch source
+#[derive(Clone)]
+pub struct LogsSourceConfig {
+    /// No slot notification for this long means the WebSocket is stalled
+    pub stall_timeout: Duration,
+    /// Transactions fetched in parallel
+    pub concurrency: usize,
+    pub rate_limiter: Arc<AdaptiveRateLimiter>,
+    pub block_signatures: Arc<BlockSignatureCache>,
+    pub block_times: Arc<BlockTimeCache>,
+}
+
+/// A signature to fetch, from a logs notification or from gap filling
+pub struct PendingSignature {
+    pub signature: String,
+    pub slot: u64,
+    /// Notified at the tip: left unplaced in its block (the finalizer places it) rather
+    /// than fetching the block of every live slot
+    pub live: bool,
+}
+
+/// `logsSubscribe` notifications resolved to full transactions with `getTransaction`
+///
+/// A background task keeps the subscription alive (reconnecting with backoff on drops
+/// and stalls) and, after every (re)connect, lists the signatures missed since the last
+/// one it delivered. The first connect gap-fills from `resume_from`, which is how history
+/// is backfilled. Signatures are fetched concurrently and yielded in delivery order.
+pub struct LogsSource {
+    transactions: BoxStream<'static, IngestResult<IngestedTransaction>>,
+    /// Tells the background task to unsubscribe and stop
+    stop: watch::Sender<bool>,
+    feeder: Option<JoinHandle<()>>,
+}
+
+impl LogsSource {
+    pub fn start(
+        rpc_pool: Arc<RpcPool>,
+        program_id: Pubkey,
+        resume_from: Option<String>,
+        config: LogsSourceConfig,
+    ) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (stop, stopped) = watch::channel(false);
+
+        let feeder = tokio::spawn(feed_signatures(
+            rpc_pool.clone(),
+            program_id,
+            resume_from,
+            config.clone(),
+            sender,
+            stopped,
+        ));
+
+        let pending = futures::stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|pending| (pending, receiver))
+        });
+
+        // `buffered` yields results in input order, so transactions stay in slot order
+        let rate_limiter = config.rate_limiter;
+        let block_signatures = config.block_signatures;
+        let block_times = config.block_times;
+        let transactions = pending
+            .map(move |pending| {
+                fetch_transaction(
+                    rpc_pool.clone(),
+                    rate_limiter.clone(),
+                    block_signatures.clone(),
+                    block_times.clone(),
+                    program_id,
+                    pending,
+                )
+            })
+            .buffered(config.concurrency.max(1))
+            .boxed();
+
+        Self {
+            transactions,
+            stop,
+            feeder: Some(feeder),
+        }
+    }
+}
+
+#[async_trait]
+impl IngestSource for LogsSource {
+    fn name(&self) -> &'static str {
+        "logs"
+    }
+
+    async fn next_transaction(&mut self) -> IngestResult<Option<IngestedTransaction>> {
+        self.transactions.next().await.transpose()
+    }
+
+    async fn close(&mut self) {
+        let _ = self.stop.send(true);
+        if let Some(feeder) = self.feeder.take() {
+            let _ = feeder.await;
+        }
+    }
+}
+
+/// Fetch one transaction of `program_id` through the rate limiter, retrying failed
+/// requests, and place it in its block with `block_signatures` unless it is live
+pub(crate) async fn fetch_transaction(
+    rpc_pool: Arc<RpcPool>,
+    rate_limiter: Arc<AdaptiveRateLimiter>,
+    block_signatures: Arc<BlockSignatureCache>,
+    block_times: Arc<BlockTimeCache>,
+    program_id: Pubkey,
+    pending: PendingSignature,
+) -> IngestResult<IngestedTransaction> {
+    let sig = Signature::from_str(&pending.signature)?;
+
+    // Fetch the transaction with support for versioned transactions
+    let tx_config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        commitment: Some(rpc_pool.commitment()),
+        max_supported_transaction_version: Some(0),
+    };
+
+    let mut attempt = 1;
+    loop {
+        rate_limiter.acquire().await;
+        let request_start = Instant::now();
+
+        match rpc_pool
+            .call(|rpc| async move { rpc.get_transaction_with_config(&sig, tx_config).await })
+            .await
+        {
+            Ok(transaction) => {
+                rate_limiter.record_success(request_start.elapsed());
+                let tx_index = if pending.live {
+                    None
+                } else {
+                    resolve_tx_index(
+                        &rpc_pool,
+                        &rate_limiter,
+                        &block_signatures,
+                        pending.slot,
+                        &pending.signature,
+                    )
+                    .await
+                };
+                let mut tx = IngestedTransaction {
+                    signature: pending.signature,
+                    slot: pending.slot,
+                    tx_index,
+                    transaction,
+                    estimated_block_time: None,
+                };
+                resolve_block_time(&rpc_pool, &rate_limiter, &block_times, &mut tx).await;
+                return Ok(tx);
+            }
+            Err(e) => {
+                rate_limiter.record_error(request_start.elapsed(), &e);
+                if attempt >= MAX_FETCH_ATTEMPTS {
+                    return Err(Box::new(FetchError {
+                        program_id,
+                        signature: pending.signature,
+                        slot: pending.slot,
+                        message: e.to_string(),
+                    }));
+                }
+                // The limiter already slowed down; retry once it lets us through
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// A block's signature list, fetched once by whichever fetcher asks first
+type BlockSignatures = Arc<OnceCell<Arc<Vec<String>>>>;
+
+/// Signature lists of the latest blocks fetched to place transactions, in block order
+///
+/// Fetchers that share one place transactions of the same block with a single
+/// `getBlock`, also while it is in flight; lookups are counted in the
+/// `openbook_block_signatures_lookups_total` metric.
+#[derive(Default)]
+pub struct BlockSignatureCache {
+    blocks: Mutex<VecDeque<(u64, BlockSignatures)>>,
+}
+
+impl BlockSignatureCache {
+    /// The entry of `slot`, added (evicting the oldest) if there is none yet
+    fn entry(&self, slot: u64) -> BlockSignatures {
+        let mut blocks = self.blocks.lock().unwrap();
+        if let Some((_, entry)) = blocks.iter().find(|(cached_slot, _)| *cached_slot == slot) {
+            metrics().block_signatures_lookups.inc(&["hit"]);
+            return entry.clone();
+        }
+        metrics().block_signatures_lookups.inc(&["miss"]);
+
+        if blocks.len() >= BLOCK_SIGNATURES_CACHED {
+            blocks.pop_front();
+        }
+        let entry = BlockSignatures::default();
+        blocks.push_back((slot, entry.clone()));
+        entry
+    }
+}
+
+/// Position of `signature` in the block of `slot`, which `getTransaction` does not report,
+/// from the block's signature list (None if the block cannot be fetched)
+async fn resolve_tx_index(
+    rpc_pool: &RpcPool,
+    rate_limiter: &AdaptiveRateLimiter,
+    block_signatures: &BlockSignatureCache,
+    slot: u64,
+    signature: &str,
+) -> Option<u32> {
+    // A failed fetch leaves the entry empty, for the next fetcher to try again
+    let entry = block_signatures.entry(slot);
+    let signatures = entry
+        .get_or_try_init(|| async {
+            let config = RpcBlockConfig {
+                encoding: None,
+                transaction_details: Some(TransactionDetails::Signatures),
+                rewards: Some(false),
+                commitment: Some(rpc_pool.commitment()),
+                max_supported_transaction_version: Some(0),
+            };
+
+            rate_limiter.acquire().await;
+            let request_start = Instant::now();
+            match rpc_pool
+                .call(|rpc| async move { rpc.get_block_with_config(slot, config).await })
+                .await
+            {
+                Ok(block) => {
+                    rate_limiter.record_success(request_start.elapsed());
+                    Ok(Arc::new(block.signatures.unwrap_or_default()))
+                }
+                Err(e) => {
+                    rate_limiter.record_error(request_start.elapsed(), &e);
+                    Err(e)
+                }
+            }
+        })
+        .await;
+
+    match signatures {
+        Ok(signatures) => signatures
+            .iter()
+            .position(|block_signature| block_signature == signature)
+            .map(|index| index as u32),
+        Err(e) => {
+            warn!("⚠️  Could not place {} in slot {}: {}", signature, slot, e);
+            None
+        }
+    }
+}
+
+/// Keep the subscription alive and feed signatures until the source is closed or dropped
+async fn feed_signatures(
+    rpc_pool: Arc<RpcPool>,
+    program_id: Pubkey,
+    mut last_signature: Option<String>,
+    config: LogsSourceConfig,
+    sender: mpsc::UnboundedSender<PendingSignature>,
+    mut stop: watch::Receiver<bool>,
+) {
+    let mut backoff = ReconnectBackoff::default();
+
+    loop {
+        let session_start = Instant::now();
+
+        if let Err(e) = run_session(
+            &rpc_pool,
+            &program_id,
+            &mut last_signature,
+            &config,
+            &sender,
+            &mut stop,
+        )
+        .await
+        {
+            warn!("⚠️  Live session ended: {}", e);
+        }
+
+        if sender.is_closed() || *stop.borrow() {
+            return;
+        }
+
+        let delay = backoff.after_session(session_start.elapsed());
+        info!(
+            "🔄 Reconnecting in {}s, then gap-filling from the last signature...",
+            delay.as_secs()
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = stop.changed() => return,
+        }
+    }
+}
+
+/// Connect to the best WebSocket endpoint and feed signatures until it drops or stalls
+async fn run_session(
+    rpc_pool: &RpcPool,
+    program_id: &Pubkey,
+    last_signature: &mut Option<String>,
+    config: &LogsSourceConfig,
+    sender: &mpsc::UnboundedSender<PendingSignature>,
+    stop: &mut watch::Receiver<bool>,
+) -> IngestResult<()> {
+    let ws_url = rpc_pool
+        .ws_url()
+        .ok_or("no WebSocket endpoint configured")?;
+    info!("🔌 Connecting to {}", ws_url);
+
+    let client = match PubsubClient::new(&ws_url).await {
+        Ok(client) => client,
+        Err(e) => {
+            rpc_pool.record_ws_error(&ws_url);
+            return Err(e.into());
+        }
+    };
+
+    let result = subscribe_and_feed(
+        &client,
+        rpc_pool,
+        program_id,
+        last_signature,
+        config,
+        sender,
+        stop,
+    )
+    .await;
+
+    // Streams are gone at this point; stop the client's socket task
+    if let Err(e) = client.shutdown().await {
+        warn!("Failed to shut down WebSocket client: {}", e);
+    }
+
+    // Steer the next reconnect towards another provider
+    if result.is_err() {
+        rpc_pool.record_ws_error(&ws_url);
+    }
+
+    result
+}
+
+/// Subscribe, gap-fill up to the first live slot, drain the buffer and follow the stream
+async fn subscribe_and_feed(
+    client: &PubsubClient,
+    rpc_pool: &RpcPool,
+    program_id: &Pubkey,
+    last_signature: &mut Option<String>,
+    config: &LogsSourceConfig,
+    sender: &mpsc::UnboundedSender<PendingSignature>,
+    stop: &mut watch::Receiver<bool>,
+) -> IngestResult<()> {
+    // Open the live subscription before gap filling so nothing landing meanwhile is missed
+    let commitment = rpc_pool.commitment();
+    let logs_config = RpcTransactionLogsConfig {
+        commitment: Some(commitment),
+    };
+    let filter = RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]);
+    let (mut stream, unsubscribe_logs) = client.logs_subscribe(filter, logs_config).await?;
+
+    // Slots advance every ~400ms, so this doubles as a heartbeat even when the program is quiet
+    let (mut slots, unsubscribe_slots) = client.slot_subscribe().await?;
+
+    info!("✅ Subscribed to OpenBook program logs");
+
+    // Anything confirmed from here on is delivered by the subscription
+    let subscribed_at_slot = rpc_pool
+        .call(|rpc| async move { rpc.get_slot_with_commitment(commitment).await })
+        .await
+        .unwrap_or(0);
+
+    // List missed signatures while buffering live notifications
+    let mut buffered = Vec::new();
+    let gap = list_signatures_since(
+        rpc_pool,
+        &config.rate_limiter,
+        program_id,
+        last_signature.clone(),
+    );
+    tokio::pin!(gap);
+
+    let gap_result = loop {
+        tokio::select! {
+            result = &mut gap => break result,
+            Some(log) = stream.next() => buffered.push(log),
+            Some(_) = slots.next() => {}
+            _ = stop.changed() => {
+                unsubscribe_logs().await;
+                unsubscribe_slots().await;
+                info!("🔌 Unsubscribed from OpenBook program logs");
+                return Ok(());
+            }
+        }
+    };
+
+    let mut handoff_signatures = HashSet::new();
+    match gap_result {
+        Ok(mut gap) => {
+            let first_live_slot = buffered.iter().map(|log| log.context.slot).min();
+            handoff_signatures = hand_off(&mut gap, first_live_slot, subscribed_at_slot);
+
+            if !gap.is_empty() {
+                info!("📜 Gap fill: {} transactions to backfill", gap.len());
+            }
+            let program = program_id.to_string();
+            metrics()
+                .backfill_transactions
+                .inc_by(&[&program], gap.len() as f64);
+            metrics()
+                .backfill_target_slot
+                .set(&[&program], subscribed_at_slot as f64);
+
+            for pending in gap {
+                *last_signature = Some(pending.signature.clone());
+                sender.send(pending)?;
+            }
+        }
+        Err(e) => {
+            error!("❌ Gap fill failed: {}", e);
+            error!("⚠️  Continuing with real-time indexing only...");
+        }
+    }
+
+    // Drain what arrived during gap filling, skipping transactions already queued
+    let mut duplicates = 0;
+    for log in buffered {
+        if handoff_signatures.contains(&log.value.signature) {
+            duplicates += 1;
+            continue;
+        }
+        *last_signature = Some(log.value.signature.clone());
+        if has_openbook_events(&log.value.logs) {
+            sender.send(PendingSignature {
+                signature: log.value.signature,
+                slot: log.context.slot,
+                live: true,
+            })?;
+        }
+    }
+    if duplicates > 0 {
+        info!(
+            "🔁 Skipped {} buffered transactions already backfilled",
+            duplicates
+        );
+    }
+
+    info!("🚀 Following live OpenBook transactions...");
+
+    let mut last_heartbeat = Instant::now();
+    let mut stall_check = tokio::time::interval(config.stall_timeout / 2);
+
+    loop {
+        tokio::select! {
+            log = stream.next() => match log {
+                Some(log) => {
+                    *last_signature = Some(log.value.signature.clone());
+                    if has_openbook_events(&log.value.logs) {
+                        sender.send(PendingSignature {
+                            signature: log.value.signature,
+                            slot: log.context.slot,
+                            live: true,
+                        })?;
+                    }
+                }
+                None => return Err("logs stream ended".into()),
+            },
+            slot = slots.next() => match slot {
+                Some(_) => last_heartbeat = Instant::now(),
+                None => return Err("slot stream ended".into()),
+            },
+            _ = stall_check.tick() => {
+                if last_heartbeat.elapsed() > config.stall_timeout {
+                    return Err(format!(
+                        "no slot updates for {}s, connection stalled",
+                        last_heartbeat.elapsed().as_secs()
+                    )
+                    .into());
+                }
+            }
+            _ = stop.changed() => {
+                unsubscribe_logs().await;
+                unsubscribe_slots().await;
+                info!("🔌 Unsubscribed from OpenBook program logs");
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Hand the gap fill over to the notifications buffered while it ran: gap signatures after
+/// the first buffered slot are left to the subscription, and the signatures returned are
+/// those close enough to the subscription slot to be buffered too (skipped when drained)
+pub fn hand_off(
+    gap: &mut Vec<PendingSignature>,
+    first_live_slot: Option<u64>,
+    subscribed_at_slot: u64,
+) -> HashSet<String> {
+    if let Some(first_live_slot) = first_live_slot {
+        gap.retain(|sig| sig.slot <= first_live_slot);
+    }
+
+    gap.iter()
+        .filter(|sig| sig.slot + HANDOFF_SLOT_MARGIN >= subscribed_at_slot)
+        .map(|sig| sig.signature.clone())
+        .collect()
+}
+
+/// Only notifications whose logs show an OpenBook instruction are worth a `getTransaction`
+fn has_openbook_events(logs: &[String]) -> bool {
+    !crate::event_processor::parse_events_from_logs(logs).is_empty()
+}
+
+/// Signatures of the program newer than `until`, oldest first (all history when `None`)
+pub(super) async fn list_signatures_since(
+    rpc_pool: &RpcPool,
+    rate_limiter: &AdaptiveRateLimiter,
+    program_id: &Pubkey,
+    until: Option<String>,
+) -> IngestResult<Vec<PendingSignature>> {
+    list_signatures(rpc_pool, rate_limiter, program_id, None, until, None).await
+}
+
+/// Signatures of `address` strictly between `until` (older) and `before` (newer), oldest
+/// first, paging back from `before` (or the tip) until `until`, or past `min_slot`
+pub(crate) async fn list_signatures(
+    rpc_pool: &RpcPool,
+    rate_limiter: &AdaptiveRateLimiter,
+    address: &Pubkey,
+    before: Option<String>,
+    until: Option<String>,
+    min_slot: Option<u64>,
+) -> IngestResult<Vec<PendingSignature>> {
+    let until = until.as_deref().map(Signature::from_str).transpose()?;
+    let mut before = before.as_deref().map(Signature::from_str).transpose()?;
+    let address = *address;
+    let commitment = rpc_pool.commitment();
+
+    let mut pending = Vec::new();
+
+    loop {
+        rate_limiter.acquire().await;
+        let request_start = Instant::now();
+        let sigs = match rpc_pool
+            .call(|rpc| async move {
+                rpc.get_signatures_for_address_with_config(
+                    &address,
+                    GetConfirmedSignaturesForAddress2Config {
+                        before,
+                        until,
+                        limit: Some(1000),
+                        commitment: Some(commitment),
+                    },
+                )
+                .await
+            })
+            .await
+        {
+            Ok(sigs) => {
+                rate_limiter.record_success(request_start.elapsed());
+                sigs
+            }
+            Err(e) => {
+                rate_limiter.record_error(request_start.elapsed(), &e);
+                return Err(e.into());
+            }
+        };
+
+        let Some(last) = sigs.last() else {
+            break;
+        };
+        before = Some(Signature::from_str(&last.signature)?);
+        let reached_min_slot = min_slot.is_some_and(|min| last.slot < min);
+
+        pending.extend(
+            sigs.into_iter()
+                .filter(|sig| min_slot.is_none_or(|min| sig.slot >= min))
+                .map(|sig| PendingSignature {
+                    signature: sig.signature,
+                    slot: sig.slot,
+                    live: false,
+                }),
+        );
+
+        // Pages go back in time: nothing older is wanted
+        if reached_min_slot {
+            break;
+        }
+    }
+
+    // Oldest first
+    pending.reverse();
+    Ok(pending)
+}
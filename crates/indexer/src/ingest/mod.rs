@@ -0,0 +1,249 @@
+//! Ingestion sources: where confirmed OpenBook transactions come from
+//!
+//! Every source yields [`IngestedTransaction`]s (transaction + status meta) in order;
+//! `event_processor` consumes them without knowing how they were obtained.
+
+use async_trait::async_trait;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::TransactionError;
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta,
+    EncodedTransaction, EncodedTransactionWithStatusMeta, UiMessage,
+};
+use std::str::FromStr;
+use std::time::Duration;
+
+pub mod block_time;
+pub mod blocks;
+pub mod enhanced_ws;
+pub mod geyser;
+pub mod geyser_proto;
+pub mod logs;
+pub mod replay;
+
+pub use blocks::BlockSource;
+pub use enhanced_ws::EnhancedWsSource;
+pub use geyser::GeyserSource;
+pub use logs::LogsSource;
+pub use replay::{ReplaySource, TransactionRecorder};
+
+pub type IngestResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// First delay before reconnecting a WebSocket, doubled after each failed attempt
+const RECONNECT_BACKOFF_MIN: Duration = Duration::from_secs(1);
+
+/// Upper bound for the reconnect delay
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// Sessions that lasted this long reset the reconnect backoff
+const HEALTHY_SESSION: Duration = Duration::from_secs(60);
+
+/// A confirmed transaction with its meta, as delivered by an ingestion source
+#[derive(Debug)]
+pub struct IngestedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    /// Position of the transaction inside its block, when the source knows it
+    pub tx_index: Option<u32>,
+    pub transaction: EncodedConfirmedTransactionWithStatusMeta,
+    /// Block time (unix seconds) estimated from neighbouring slots, when the block time
+    /// is unknown (see `block_time`)
+    pub estimated_block_time: Option<i64>,
+}
+
+impl IngestedTransaction {
+    /// Build from a block/stream entry, taking the signature from the transaction itself
+    pub fn from_block_entry(
+        slot: u64,
+        block_time: Option<i64>,
+        tx_index: Option<u32>,
+        transaction: EncodedTransactionWithStatusMeta,
+    ) -> Option<Self> {
+        let signature = transaction_signature(&transaction)?;
+        Some(Self {
+            signature,
+            slot,
+            tx_index,
+            transaction: EncodedConfirmedTransactionWithStatusMeta {
+                slot,
+                transaction,
+                block_time,
+            },
+            estimated_block_time: None,
+        })
+    }
+
+    pub fn block_time(&self) -> Option<i64> {
+        self.transaction.block_time
+    }
+
+    /// Block time in milliseconds, estimated when unknown (0 without an estimate)
+    pub fn timestamp_ms(&self) -> i64 {
+        self.block_time().or(self.estimated_block_time).unwrap_or(0) * 1000
+    }
+
+    /// Whether `timestamp_ms` is not the block time reported by the chain
+    pub fn time_estimated(&self) -> bool {
+        self.block_time().is_none()
+    }
+
+    /// Program log lines from the status meta
+    pub fn log_messages(&self) -> Vec<String> {
+        self.transaction
+            .transaction
+            .meta
+            .as_ref()
+            .and_then(|meta| match &meta.log_messages {
+                OptionSerializer::Some(logs) => Some(logs.clone()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// Why the transaction failed on-chain; `None` when it succeeded
+    pub fn error(&self) -> Option<TransactionError> {
+        self.transaction
+            .transaction
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.err.clone())
+    }
+}
+
+/// A transaction a source knows about but could not fetch
+#[derive(Debug)]
+pub struct FetchError {
+    /// Program the transaction was listed for
+    pub program_id: Pubkey,
+    pub signature: String,
+    pub slot: u64,
+    pub message: String,
+}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "failed to fetch transaction {}: {}",
+            self.signature, self.message
+        )
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+/// Delays between the reconnects of a WebSocket source
+#[derive(Debug)]
+pub struct ReconnectBackoff {
+    next: Duration,
+}
+
+impl Default for ReconnectBackoff {
+    fn default() -> Self {
+        Self {
+            next: RECONNECT_BACKOFF_MIN,
+        }
+    }
+}
+
+impl ReconnectBackoff {
+    /// Delay before reconnecting after a session that lasted `session`
+    ///
+    /// Doubles with every reconnect up to `RECONNECT_BACKOFF_MAX`; a session that stayed
+    /// up for a while was healthy, so backing off starts from scratch.
+    pub fn after_session(&mut self, session: Duration) -> Duration {
+        if session >= HEALTHY_SESSION {
+            self.next = RECONNECT_BACKOFF_MIN;
+        }
+        let delay = self.next;
+        self.next = (self.next * 2).min(RECONNECT_BACKOFF_MAX);
+        delay
+    }
+}
+
+/// A stream of confirmed transactions touching the indexed program
+#[async_trait]
+pub trait IngestSource: Send {
+    /// Short name for logs
+    fn name(&self) -> &'static str;
+
+    /// Next transaction in order; `None` once the source is exhausted
+    ///
+    /// An error concerns a single transaction or request: the source stays usable. A
+    /// transaction that could not be fetched is reported as a [`FetchError`].
+    async fn next_transaction(&mut self) -> IngestResult<Option<IngestedTransaction>>;
+
+    /// Stop intake on shutdown: unsubscribe and close connections
+    ///
+    /// Transactions already fetched but not yet taken are dropped; they are fetched
+    /// again after the restart, from the checkpoint.
+    async fn close(&mut self) {}
+}
+
+/// Which [`IngestSource`] the listener runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    /// `logsSubscribe` notifications, each followed by `getTransaction`
+    Logs,
+    /// `getBlock` polling of every confirmed slot
+    Blocks,
+    /// Provider enhanced WebSocket `transactionSubscribe` with full transactions
+    EnhancedWebsocket,
+    /// Yellowstone Geyser gRPC `Subscribe` stream with full transactions
+    Geyser,
+    /// Transactions recorded to a JSON lines file, no RPC needed
+    Replay,
+}
+
+impl FromStr for SourceKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "logs" => Ok(SourceKind::Logs),
+            "blocks" | "block" => Ok(SourceKind::Blocks),
+            "enhanced_ws" => Ok(SourceKind::EnhancedWebsocket),
+            "geyser" => Ok(SourceKind::Geyser),
+            "replay" => Ok(SourceKind::Replay),
+            other => Err(format!(
+                "unknown ingest source '{}' (expected logs, blocks, enhanced_ws, geyser or replay)",
+                other
+            )),
+        }
+    }
+}
+
+/// First signature of a JSON-encoded transaction
+pub fn transaction_signature(tx: &EncodedTransactionWithStatusMeta) -> Option<String> {
+    match &tx.transaction {
+        EncodedTransaction::Json(ui_tx) => ui_tx.signatures.first().cloned(),
+        _ => None,
+    }
+}
+
+/// Whether a transaction references `program_id` in its static or loaded account keys
+pub fn touches_program(tx: &EncodedTransactionWithStatusMeta, program_id: &Pubkey) -> bool {
+    let program = program_id.to_string();
+
+    let in_message = match &tx.transaction {
+        EncodedTransaction::Json(ui_tx) => match &ui_tx.message {
+            UiMessage::Raw(raw) => raw.account_keys.contains(&program),
+            UiMessage::Parsed(parsed) => parsed.account_keys.iter().any(|k| k.pubkey == program),
+        },
+        _ => false,
+    };
+
+    let in_lookup_tables = tx
+        .meta
+        .as_ref()
+        .is_some_and(|meta| match &meta.loaded_addresses {
+            OptionSerializer::Some(loaded) => loaded
+                .writable
+                .iter()
+                .chain(loaded.readonly.iter())
+                .any(|k| *k == program),
+            _ => false,
+        });
+
+    in_message || in_lookup_tables
+}
@@ -0,0 +1,122 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use std::path::Path;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+
+use super::{transaction_signature, IngestResult, IngestSource, IngestedTransaction};
+
+/// Extra field carrying [`IngestedTransaction::tx_index`] next to the `getTransaction` fields
+const TX_INDEX_FIELD: &str = "transactionIndex";
+
+/// Transactions read back from a recording
+///
+/// Each line is a JSON `EncodedConfirmedTransactionWithStatusMeta`, exactly as returned by
+/// `getTransaction` (json encoding), optionally with a `transactionIndex`. Blank lines and
+/// lines starting with `#` are skipped. The source ends at the end of the file.
+pub struct ReplaySource {
+    lines: Lines<BufReader<File>>,
+    line_number: usize,
+}
+
+impl ReplaySource {
+    pub async fn open(path: impl AsRef<Path>) -> IngestResult<Self> {
+        let file = File::open(path.as_ref())
+            .await
+            .map_err(|e| format!("cannot open replay file {}: {}", path.as_ref().display(), e))?;
+
+        Ok(Self {
+            lines: BufReader::new(file).lines(),
+            line_number: 0,
+        })
+    }
+}
+
+#[async_trait]
+impl IngestSource for ReplaySource {
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+
+    async fn next_transaction(&mut self) -> IngestResult<Option<IngestedTransaction>> {
+        while let Some(line) = self.lines.next_line().await? {
+            self.line_number += 1;
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            return parse_line(line)
+                .map(Some)
+                .map_err(|e| format!("replay line {}: {}", self.line_number, e).into());
+        }
+
+        Ok(None)
+    }
+}
+
+/// Appends every ingested transaction to a file readable by [`ReplaySource`]
+pub struct TransactionRecorder {
+    file: File,
+}
+
+impl TransactionRecorder {
+    /// Open `path` for appending, creating it if needed
+    pub async fn create(path: impl AsRef<Path>) -> IngestResult<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path.as_ref())
+            .await
+            .map_err(|e| format!("cannot open record file {}: {}", path.as_ref().display(), e))?;
+
+        Ok(Self { file })
+    }
+
+    /// Write one transaction as a JSON line
+    pub async fn record(&mut self, tx: &IngestedTransaction) -> IngestResult<()> {
+        let mut line = to_line(tx)?;
+        line.push('\n');
+
+        // One write per line: a crash never leaves more than the last line truncated
+        self.file.write_all(line.as_bytes()).await?;
+        self.file.flush().await?;
+        Ok(())
+    }
+}
+
+/// Serialize a transaction the way [`ReplaySource`] reads it
+pub fn to_line(tx: &IngestedTransaction) -> IngestResult<String> {
+    let mut value = serde_json::to_value(&tx.transaction)?;
+    if let (Some(index), Value::Object(fields)) = (tx.tx_index, &mut value) {
+        fields.insert(TX_INDEX_FIELD.to_string(), Value::from(index));
+    }
+    Ok(serde_json::to_string(&value)?)
+}
+
+/// Parse one recorded line
+pub fn parse_line(line: &str) -> IngestResult<IngestedTransaction> {
+    let mut value: Value = serde_json::from_str(line)?;
+
+    let tx_index = match &mut value {
+        Value::Object(fields) => fields
+            .remove(TX_INDEX_FIELD)
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32),
+        _ => None,
+    };
+
+    let transaction: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_value(value)?;
+    let signature = transaction_signature(&transaction.transaction)
+        .ok_or("transaction is not json-encoded or has no signature")?;
+
+    Ok(IngestedTransaction {
+        signature,
+        slot: transaction.slot,
+        tx_index,
+        transaction,
+        estimated_block_time: None,
+    })
+}
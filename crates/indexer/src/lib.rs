@@ -1,11 +1,28 @@
 // Library modules
 pub mod api;
+pub mod archive;
+pub mod backfill;
+pub mod config;
 pub mod db;
+pub mod dead_letter;
+pub mod event_heap;
 pub mod event_processor;
+pub mod finalizer;
 pub mod indexer;
+pub mod ingest;
 pub mod market_scanner;
+pub mod metrics;
+pub mod open_orders;
+pub mod oracle;
+pub mod program_errors;
+pub mod programs;
+pub mod rate_limiter;
+pub mod reprocess;
+pub mod rpc_pool;
+pub mod shutdown;
 pub mod types;
 pub mod utils;
+pub mod vaults;
 
 // Re-exports
 pub use db::Database;
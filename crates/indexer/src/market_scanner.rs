@@ -1,11 +1,13 @@
-use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::pubkey::Pubkey;
 use solana_account_decoder::UiAccountEncoding;
 use solana_client::rpc_config::RpcAccountInfoConfig;
 use solana_client::rpc_config::RpcProgramAccountsConfig;
-use solana_client::rpc_filter::{RpcFilterType, Memcmp, MemcmpEncodedBytes};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
 use tracing::{info, warn};
 
+use crate::programs::{Deployment, MarketLayout};
+use crate::rpc_pool::RpcPool;
+
 /// OpenBook V2 Market account discriminator
 /// First 8 bytes from actual market account on devnet
 const MARKET_DISCRIMINATOR: [u8; 8] = [219, 190, 213, 55, 0, 227, 198, 154];
@@ -21,13 +23,15 @@ pub struct MarketAccount {
     pub quote_decimals: i32,
 }
 
-/// Scan for all OpenBook V2 market accounts
+/// Scan for all market accounts of an OpenBook deployment
 pub async fn scan_markets(
-    rpc_client: &RpcClient,
-    program_id: &Pubkey,
+    rpc_pool: &RpcPool,
+    deployment: &Deployment,
 ) -> Result<Vec<MarketAccount>, Box<dyn std::error::Error>> {
+    let program_id = &deployment.program_id;
     info!("🔍 Scanning for OpenBook V2 market accounts...");
     info!("   Program ID: {}", program_id);
+    info!("   Layout: {}", deployment.layout.as_str());
     info!("   Discriminator (hex): {:02x?}", MARKET_DISCRIMINATOR);
 
     // Instead of fetching all accounts first (which may timeout/fail),
@@ -56,21 +60,32 @@ pub async fn scan_markets(
 
     // Fetch all market accounts
     info!("🔍 Calling get_program_accounts_with_config...");
-    let accounts = match rpc_client
-        .get_program_accounts_with_config(program_id, config)
-        .await {
-            Ok(accts) => {
-                info!("✅ RPC call successful - got {} accounts", accts.len());
-                accts
-            }
-            Err(e) => {
-                warn!("❌ RPC call failed: {}", e);
-                warn!("    This likely means the RPC filter encoding is wrong");
-                return Ok(Vec::new());
+    let program_id_copy = *program_id;
+    let accounts = match rpc_pool
+        .call(|rpc| {
+            let config = config.clone();
+            async move {
+                rpc.get_program_accounts_with_config(&program_id_copy, config)
+                    .await
             }
-        };
+        })
+        .await
+    {
+        Ok(accts) => {
+            info!("✅ RPC call successful - got {} accounts", accts.len());
+            accts
+        }
+        Err(e) => {
+            warn!("❌ RPC call failed: {}", e);
+            warn!("    This likely means the RPC filter encoding is wrong");
+            return Ok(Vec::new());
+        }
+    };
 
-    info!("✅ Found {} accounts matching discriminator", accounts.len());
+    info!(
+        "✅ Found {} accounts matching discriminator",
+        accounts.len()
+    );
 
     let mut markets = Vec::new();
 
@@ -85,7 +100,7 @@ pub async fn scan_markets(
         }
 
         // Parse market data
-        match parse_market_account(&account.data, &pubkey.to_string()) {
+        match parse_market_account(&account.data, &pubkey.to_string(), deployment.layout) {
             Ok(market) => {
                 info!(
                     "  📊 Market: {} - {} (base: {}, quote: {})",
@@ -106,43 +121,25 @@ pub async fn scan_markets(
     Ok(markets)
 }
 
-/// Parse market account data
-fn parse_market_account(
+/// Parse market account data with the deployment's layout
+pub fn parse_market_account(
     data: &[u8],
     address: &str,
+    layout: MarketLayout,
 ) -> Result<MarketAccount, Box<dyn std::error::Error>> {
-    // OpenBook V2 Market structure (simplified offsets based on the Rust struct):
-    // - 8 bytes: discriminator
-    // - 32 bytes: admin
-    // - 32 bytes: market_authority (PDA)
-    // - 32 bytes: bids (pubkey)
-    // - 32 bytes: asks (pubkey)
-    // - 32 bytes: event_heap (pubkey)
-    // - 8 bytes: oracle_a (Option<Pubkey> - 1 byte enum + 32 bytes if Some)
-    // - 8 bytes: oracle_b
-    // - 32 bytes: collect_fee_admin
-    // - 32 bytes: open_orders_admin (Option)
-    // - 32 bytes: consume_events_admin (Option)
-    // - 32 bytes: close_market_admin (Option)
-    // - 16 bytes: name (fixed array)
-    // - 32 bytes: base_mint
-    // - 32 bytes: quote_mint
-    // ... more fields
+    // Field offsets depend on the IDL version of the deployment (see `MarketLayout`)
+    let offsets = layout.offsets();
 
     if data.len() < 500 {
         return Err("Account data too small to be a market".into());
     }
 
     // Verify discriminator
-    if &data[0..8] != MARKET_DISCRIMINATOR {
+    if data[0..8] != MARKET_DISCRIMINATOR {
         return Err("Invalid market discriminator".into());
     }
 
-    // Parse name at offset 184 (for custom OpenBook deployment)
-    // Note: This offset is different from official OpenBook V2
-    // The custom deployment has a shorter struct layout before the name field
-    // Verified by analyzing raw account data: "MARKET" string found at offset 184
-    let name_offset = 184;
+    let name_offset = offsets.name;
 
     let name_bytes = if data.len() > name_offset + 16 {
         &data[name_offset..name_offset + 16]
@@ -154,8 +151,7 @@ fn parse_market_account(
         .trim_end_matches('\0')
         .to_string();
 
-    // Base mint is after name
-    let base_mint_offset = name_offset + 16;
+    let base_mint_offset = offsets.base_mint;
     let base_mint = if data.len() > base_mint_offset + 32 {
         Pubkey::try_from(&data[base_mint_offset..base_mint_offset + 32])
             .map(|p| p.to_string())
@@ -164,8 +160,7 @@ fn parse_market_account(
         "unknown".to_string()
     };
 
-    // Quote mint is after base mint
-    let quote_mint_offset = base_mint_offset + 32;
+    let quote_mint_offset = offsets.quote_mint;
     let quote_mint = if data.len() > quote_mint_offset + 32 {
         Pubkey::try_from(&data[quote_mint_offset..quote_mint_offset + 32])
             .map(|p| p.to_string())
@@ -174,6 +169,12 @@ fn parse_market_account(
         "unknown".to_string()
     };
 
+    // Layouts without stored decimals fall back to the usual token decimals
+    let (base_decimals, quote_decimals) = match offsets.decimals {
+        Some((base, quote)) => (data[base] as i32, data[quote] as i32),
+        None => (8, 6),
+    };
+
     Ok(MarketAccount {
         address: address.to_string(),
         base_mint,
@@ -183,22 +184,22 @@ fn parse_market_account(
         } else {
             name
         },
-        base_decimals: 8, // Default - could fetch from token mint
-        quote_decimals: 6, // Default - could fetch from token mint
+        base_decimals,
+        quote_decimals,
     })
 }
 
-/// Index all markets into the database
+/// Index all markets of a deployment into the database
 pub async fn index_markets(
-    rpc_client: &RpcClient,
-    program_id: &Pubkey,
+    rpc_pool: &RpcPool,
+    deployment: &Deployment,
     db: &crate::Database,
 ) -> Result<usize, Box<dyn std::error::Error>> {
     info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     info!("📊 Market Scanner - Indexing Real OpenBook Markets");
     info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
 
-    let markets = scan_markets(rpc_client, program_id).await?;
+    let markets = scan_markets(rpc_pool, deployment).await?;
 
     if markets.is_empty() {
         info!("⚠️  No markets found on-chain");
@@ -215,6 +216,8 @@ pub async fn index_markets(
         match db
             .upsert_market(
                 &market.address,
+                &deployment.cluster,
+                &deployment.program(),
                 &market.base_mint,
                 &market.quote_mint,
                 &market.name,
@@ -0,0 +1,366 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+/// Upper bounds (seconds) of the latency histogram buckets
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// Process-wide metrics registry, rendered by each binary's `/metrics` endpoint
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+/// Everything the indexer and the API server report, in the Prometheus text format
+pub struct Metrics {
+    pub chain_slot: Gauge,
+    /// Newest slot the finalizer has settled, per cluster
+    pub finalized_slot: Gauge,
+    /// Slot of the last indexed transaction, per program
+    pub indexed_slot: Gauge,
+    pub slot_lag: Gauge,
+    pub transactions: Counter,
+    pub events: Counter,
+    pub decode_failures: Counter,
+    pub failed_transactions: Counter,
+    pub failed_attempts: Counter,
+    /// Transactions of skipped slots whose rows were deleted
+    pub rolled_back_transactions: Counter,
+    pub rpc_request_duration: Histogram,
+    pub rpc_errors: Counter,
+    pub db_query_duration: Histogram,
+    /// Transactions queued by the gap fill after each (re)connect of the logs source
+    pub backfill_transactions: Counter,
+    /// Slot the gap fill catches up to
+    pub backfill_target_slot: Gauge,
+    /// Block signature lists looked up to place fetched transactions, by cache outcome
+    pub block_signatures_lookups: Counter,
+    /// Crank backlog of each market's event heap, from the event heap monitor
+    pub event_heap_pending: Gauge,
+    pub event_heap_oldest_event_age: Gauge,
+    pub event_heap_fullness: Gauge,
+    pub http_request_duration: Histogram,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            chain_slot: Gauge::new(
+                "openbook_chain_slot",
+                "Latest confirmed slot reported by the RPC",
+                &[],
+            ),
+            finalized_slot: Gauge::new(
+                "openbook_finalized_slot",
+                "Newest slot whose confirmed rows have been finalized or rolled back",
+                &["cluster"],
+            ),
+            indexed_slot: Gauge::new(
+                "openbook_indexed_slot",
+                "Slot of the last indexed transaction",
+                &["program"],
+            ),
+            slot_lag: Gauge::new(
+                "openbook_slot_lag",
+                "Slots between the chain tip and the last indexed transaction",
+                &[],
+            ),
+            transactions: Counter::new(
+                "openbook_transactions_indexed_total",
+                "Transactions written to the database",
+                &["program"],
+            ),
+            events: Counter::new(
+                "openbook_events_processed_total",
+                "OpenBook events found in indexed transactions",
+                &["program", "event_type"],
+            ),
+            decode_failures: Counter::new(
+                "openbook_decode_failures_total",
+                "Events whose structured data could not be extracted",
+                &["program", "event_type"],
+            ),
+            failed_transactions: Counter::new(
+                "openbook_failed_transactions_total",
+                "Transactions sent to the dead-letter queue",
+                &["stage"],
+            ),
+            failed_attempts: Counter::new(
+                "openbook_failed_attempts_total",
+                "Transactions that failed on-chain",
+                &["program", "error"],
+            ),
+            rolled_back_transactions: Counter::new(
+                "openbook_rolled_back_transactions_total",
+                "Indexed transactions of confirmed slots that never finalized",
+                &["cluster"],
+            ),
+            rpc_request_duration: Histogram::new(
+                "openbook_rpc_request_duration_seconds",
+                "Latency of RPC requests",
+                &["endpoint", "outcome"],
+            ),
+            rpc_errors: Counter::new(
+                "openbook_rpc_errors_total",
+                "Failed RPC requests",
+                &["endpoint"],
+            ),
+            db_query_duration: Histogram::new(
+                "openbook_db_query_duration_seconds",
+                "Latency of database calls, connection checkout included",
+                &["query"],
+            ),
+            backfill_transactions: Counter::new(
+                "openbook_backfill_transactions_total",
+                "Missed transactions queued for backfilling",
+                &["program"],
+            ),
+            backfill_target_slot: Gauge::new(
+                "openbook_backfill_target_slot",
+                "Slot the current backfill catches up to",
+                &["program"],
+            ),
+            block_signatures_lookups: Counter::new(
+                "openbook_block_signatures_lookups_total",
+                "Block signature lists looked up to place fetched transactions (a miss costs a getBlock)",
+                &["outcome"],
+            ),
+            event_heap_pending: Gauge::new(
+                "openbook_event_heap_pending_events",
+                "Events waiting in the market's event heap to be consumed",
+                &["market"],
+            ),
+            event_heap_oldest_event_age: Gauge::new(
+                "openbook_event_heap_oldest_event_age_seconds",
+                "Age of the oldest pending event of the market's event heap (0 when empty)",
+                &["market"],
+            ),
+            event_heap_fullness: Gauge::new(
+                "openbook_event_heap_fullness_ratio",
+                "Share of the market's event heap slots in use",
+                &["market"],
+            ),
+            http_request_duration: Histogram::new(
+                "openbook_http_request_duration_seconds",
+                "Latency of API requests",
+                &["method", "route", "status"],
+            ),
+        }
+    }
+
+    /// All metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.chain_slot.render(&mut out);
+        self.finalized_slot.render(&mut out);
+        self.indexed_slot.render(&mut out);
+        self.slot_lag.render(&mut out);
+        self.transactions.render(&mut out);
+        self.events.render(&mut out);
+        self.decode_failures.render(&mut out);
+        self.failed_transactions.render(&mut out);
+        self.failed_attempts.render(&mut out);
+        self.rolled_back_transactions.render(&mut out);
+        self.rpc_request_duration.render(&mut out);
+        self.rpc_errors.render(&mut out);
+        self.db_query_duration.render(&mut out);
+        self.backfill_transactions.render(&mut out);
+        self.backfill_target_slot.render(&mut out);
+        self.block_signatures_lookups.render(&mut out);
+        self.event_heap_pending.render(&mut out);
+        self.event_heap_oldest_event_age.render(&mut out);
+        self.event_heap_fullness.render(&mut out);
+        self.http_request_duration.render(&mut out);
+        out
+    }
+}
+
+/// Name, help text and label names shared by every metric kind
+struct Family<T> {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    series: Mutex<BTreeMap<Vec<String>, T>>,
+}
+
+impl<T: Default> Family<T> {
+    fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            help,
+            labels,
+            series: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn update(&self, values: &[&str], update: impl FnOnce(&mut T)) {
+        debug_assert_eq!(values.len(), self.labels.len(), "{}", self.name);
+        let key = values.iter().map(|v| v.to_string()).collect();
+        update(self.series.lock().unwrap().entry(key).or_default());
+    }
+
+    fn header(&self, out: &mut String, kind: &str) {
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} {}", self.name, kind);
+    }
+
+    /// `{a="x",b="y"}` for a series, with `extra` appended (e.g. a histogram's `le`)
+    fn label_set(&self, values: &[String], extra: Option<(&str, &str)>) -> String {
+        let pairs: Vec<String> = self
+            .labels
+            .iter()
+            .zip(values)
+            .map(|(name, value)| (*name, value.as_str()))
+            .chain(extra)
+            .map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+            .collect();
+        if pairs.is_empty() {
+            String::new()
+        } else {
+            format!("{{{}}}", pairs.join(","))
+        }
+    }
+}
+
+/// Monotonically increasing count
+pub struct Counter(Family<f64>);
+
+impl Counter {
+    fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Self {
+        Self(Family::new(name, help, labels))
+    }
+
+    pub fn inc(&self, labels: &[&str]) {
+        self.inc_by(labels, 1.0);
+    }
+
+    pub fn inc_by(&self, labels: &[&str], amount: f64) {
+        self.0.update(labels, |value| *value += amount);
+    }
+
+    fn render(&self, out: &mut String) {
+        self.0.header(out, "counter");
+        for (values, value) in self.0.series.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "{}{} {}",
+                self.0.name,
+                self.0.label_set(values, None),
+                value
+            );
+        }
+    }
+}
+
+/// Value that goes up and down
+pub struct Gauge(Family<f64>);
+
+impl Gauge {
+    fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Self {
+        Self(Family::new(name, help, labels))
+    }
+
+    pub fn set(&self, labels: &[&str], value: f64) {
+        self.0.update(labels, |current| *current = value);
+    }
+
+    fn render(&self, out: &mut String) {
+        self.0.header(out, "gauge");
+        for (values, value) in self.0.series.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "{}{} {}",
+                self.0.name,
+                self.0.label_set(values, None),
+                value
+            );
+        }
+    }
+}
+
+#[derive(Default)]
+struct HistogramSeries {
+    /// Observations per bucket (not cumulative), the last one past the largest bound
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+/// Distribution of durations over `LATENCY_BUCKETS`
+pub struct Histogram(Family<HistogramSeries>);
+
+impl Histogram {
+    fn new(name: &'static str, help: &'static str, labels: &'static [&'static str]) -> Self {
+        Self(Family::new(name, help, labels))
+    }
+
+    pub fn observe(&self, labels: &[&str], duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        self.0.update(labels, |series| {
+            if series.buckets.is_empty() {
+                series.buckets = vec![0; LATENCY_BUCKETS.len() + 1];
+            }
+            let bucket = LATENCY_BUCKETS
+                .iter()
+                .position(|bound| seconds <= *bound)
+                .unwrap_or(LATENCY_BUCKETS.len());
+            series.buckets[bucket] += 1;
+            series.sum += seconds;
+            series.count += 1;
+        });
+    }
+
+    /// Observe the time until the returned guard is dropped
+    pub fn start_timer(&self, labels: &[&str]) -> HistogramTimer<'_> {
+        HistogramTimer {
+            histogram: self,
+            labels: labels.iter().map(|v| v.to_string()).collect(),
+            start: Instant::now(),
+        }
+    }
+
+    fn render(&self, out: &mut String) {
+        let name = self.0.name;
+        self.0.header(out, "histogram");
+        for (values, series) in self.0.series.lock().unwrap().iter() {
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(&series.buckets) {
+                cumulative += count;
+                let le = bound.to_string();
+                let labels = self.0.label_set(values, Some(("le", &le)));
+                let _ = writeln!(out, "{}_bucket{} {}", name, labels, cumulative);
+            }
+            let labels = self.0.label_set(values, Some(("le", "+Inf")));
+            let _ = writeln!(out, "{}_bucket{} {}", name, labels, series.count);
+            let labels = self.0.label_set(values, None);
+            let _ = writeln!(out, "{}_sum{} {}", name, labels, series.sum);
+            let _ = writeln!(out, "{}_count{} {}", name, labels, series.count);
+        }
+    }
+}
+
+/// Records the elapsed time into its histogram when dropped
+pub struct HistogramTimer<'a> {
+    histogram: &'a Histogram,
+    labels: Vec<String>,
+    start: Instant,
+}
+
+impl Drop for HistogramTimer<'_> {
+    fn drop(&mut self) {
+        let labels: Vec<&str> = self.labels.iter().map(String::as_str).collect();
+        self.histogram.observe(&labels, self.start.elapsed());
+    }
+}
+
+/// Label values may hold anything; quotes, backslashes and newlines must be escaped
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
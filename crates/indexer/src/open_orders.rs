@@ -0,0 +1,275 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use tracing::warn;
+
+/// OpenBook V2 OpenOrdersAccount discriminator (sha256("account:OpenOrdersAccount")[..8])
+const OPEN_ORDERS_ACCOUNT_DISCRIMINATOR: [u8; 8] = [255, 194, 78, 123, 16, 105, 208, 165];
+
+/// OpenBook V2 OpenOrdersIndexer discriminator (sha256("account:OpenOrdersIndexer")[..8])
+const OPEN_ORDERS_INDEXER_DISCRIMINATOR: [u8; 8] = [195, 83, 128, 213, 204, 91, 19, 150];
+
+/// Number of order slots per OpenOrdersAccount (`openOrders: [OpenOrder; 24]` in the IDL)
+pub const MAX_OPEN_ORDERS: usize = 24;
+
+// OpenOrdersAccount layout (zero-copy, after the 8-byte discriminator):
+// - 32 bytes: owner
+// - 32 bytes: market
+// - 32 bytes: name
+// - 32 bytes: delegate (NonZeroPubkeyOption)
+// - 4 bytes: account_num (u32)
+// - 1 byte: bump, 1 byte: version, 2 bytes: padding
+// - 160 bytes: Position
+// - 24 * 40 bytes: OpenOrder
+const OWNER_OFFSET: usize = 8;
+const MARKET_OFFSET: usize = 40;
+const NAME_OFFSET: usize = 72;
+const DELEGATE_OFFSET: usize = 104;
+const ACCOUNT_NUM_OFFSET: usize = 136;
+const VERSION_OFFSET: usize = 141;
+const POSITION_OFFSET: usize = 144;
+const OPEN_ORDERS_OFFSET: usize = POSITION_OFFSET + 160;
+const OPEN_ORDER_SIZE: usize = 40;
+const OPEN_ORDERS_ACCOUNT_SIZE: usize = OPEN_ORDERS_OFFSET + MAX_OPEN_ORDERS * OPEN_ORDER_SIZE;
+
+/// `getMultipleAccounts` accepts at most 100 keys per request
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Free and locked balances held by an OpenOrdersAccount (`Position` in the IDL)
+#[derive(Debug, Clone)]
+pub struct Position {
+    pub bids_base_lots: i64,
+    pub asks_base_lots: i64,
+    pub base_free_native: u64,
+    pub quote_free_native: u64,
+    pub locked_maker_fees: u64,
+    pub referrer_rebates_available: u64,
+    pub penalty_heap_count: u64,
+    pub maker_volume: u128,
+    pub taker_volume: u128,
+    pub bids_quote_lots: i64,
+}
+
+/// One order slot (`OpenOrder` in the IDL)
+#[derive(Debug, Clone)]
+pub struct OpenOrder {
+    pub id: u128,
+    pub client_id: u64,
+    pub locked_price: i64,
+    pub is_free: bool,
+    pub side_and_tree: u8,
+}
+
+impl OpenOrder {
+    /// "bid" or "ask" (SideAndOrderTree: BidFixed, AskFixed, BidOraclePegged, AskOraclePegged)
+    pub fn side(&self) -> &'static str {
+        if self.side_and_tree.is_multiple_of(2) {
+            "bid"
+        } else {
+            "ask"
+        }
+    }
+
+    /// "fixed" or "oracle_pegged"
+    pub fn order_tree(&self) -> &'static str {
+        if self.side_and_tree < 2 {
+            "fixed"
+        } else {
+            "oracle_pegged"
+        }
+    }
+
+    /// Price in lots, encoded in the upper 64 bits of the order id for fixed orders
+    pub fn price_lots(&self) -> Option<i64> {
+        if self.order_tree() == "fixed" {
+            Some((self.id >> 64) as i64)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenOrdersAccount {
+    pub address: String,
+    pub owner: String,
+    pub market: String,
+    pub name: String,
+    pub delegate: Option<String>,
+    pub account_num: u32,
+    pub version: u8,
+    pub position: Position,
+    pub open_orders: Vec<OpenOrder>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenOrdersIndexer {
+    pub created_counter: u32,
+    pub addresses: Vec<String>,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_u128(data: &[u8], offset: usize) -> u128 {
+    u128::from_le_bytes(data[offset..offset + 16].try_into().unwrap())
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    Pubkey::try_from(&data[offset..offset + 32]).unwrap_or_default()
+}
+
+/// Parse OpenOrdersAccount data
+pub fn parse_open_orders_account(
+    data: &[u8],
+    address: &str,
+) -> Result<OpenOrdersAccount, Box<dyn std::error::Error>> {
+    if data.len() < OPEN_ORDERS_ACCOUNT_SIZE {
+        return Err("Account data too small to be an open orders account".into());
+    }
+
+    if data[0..8] != OPEN_ORDERS_ACCOUNT_DISCRIMINATOR {
+        return Err("Invalid open orders account discriminator".into());
+    }
+
+    let name = String::from_utf8_lossy(&data[NAME_OFFSET..NAME_OFFSET + 32])
+        .trim_end_matches('\0')
+        .to_string();
+
+    // NonZeroPubkeyOption: the all-zero key means None
+    let delegate = read_pubkey(data, DELEGATE_OFFSET);
+    let delegate = if delegate == Pubkey::default() {
+        None
+    } else {
+        Some(delegate.to_string())
+    };
+
+    let p = POSITION_OFFSET;
+    let position = Position {
+        bids_base_lots: read_i64(data, p),
+        asks_base_lots: read_i64(data, p + 8),
+        base_free_native: read_u64(data, p + 16),
+        quote_free_native: read_u64(data, p + 24),
+        locked_maker_fees: read_u64(data, p + 32),
+        referrer_rebates_available: read_u64(data, p + 40),
+        penalty_heap_count: read_u64(data, p + 48),
+        maker_volume: read_u128(data, p + 56),
+        taker_volume: read_u128(data, p + 72),
+        bids_quote_lots: read_i64(data, p + 88),
+    };
+
+    let open_orders = (0..MAX_OPEN_ORDERS)
+        .map(|i| {
+            let o = OPEN_ORDERS_OFFSET + i * OPEN_ORDER_SIZE;
+            OpenOrder {
+                id: read_u128(data, o),
+                client_id: read_u64(data, o + 16),
+                locked_price: read_i64(data, o + 24),
+                is_free: data[o + 32] != 0,
+                side_and_tree: data[o + 33],
+            }
+        })
+        .collect();
+
+    Ok(OpenOrdersAccount {
+        address: address.to_string(),
+        owner: read_pubkey(data, OWNER_OFFSET).to_string(),
+        market: read_pubkey(data, MARKET_OFFSET).to_string(),
+        name,
+        delegate,
+        account_num: read_u32(data, ACCOUNT_NUM_OFFSET),
+        version: data[VERSION_OFFSET],
+        position,
+        open_orders,
+    })
+}
+
+/// Parse OpenOrdersIndexer data
+pub fn parse_open_orders_indexer(
+    data: &[u8],
+) -> Result<OpenOrdersIndexer, Box<dyn std::error::Error>> {
+    // Borsh layout: discriminator, bump: u8, created_counter: u32, addresses: Vec<Pubkey>
+    if data.len() < 8 + 1 + 4 + 4 {
+        return Err("Account data too small to be an open orders indexer".into());
+    }
+
+    if data[0..8] != OPEN_ORDERS_INDEXER_DISCRIMINATOR {
+        return Err("Invalid open orders indexer discriminator".into());
+    }
+
+    let created_counter = read_u32(data, 9);
+    let len = read_u32(data, 13) as usize;
+
+    if data.len() < 17 + len * 32 {
+        return Err(format!("Open orders indexer truncated ({} addresses)", len).into());
+    }
+
+    let addresses = (0..len)
+        .map(|i| read_pubkey(data, 17 + i * 32).to_string())
+        .collect();
+
+    Ok(OpenOrdersIndexer {
+        created_counter,
+        addresses,
+    })
+}
+
+/// OpenOrdersIndexer PDA for an owner (seeds: ["OpenOrdersIndexer", owner])
+pub fn open_orders_indexer_address(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"OpenOrdersIndexer", owner.as_ref()], program_id).0
+}
+
+/// Fetch every OpenOrdersAccount owned by `owner` via its OpenOrdersIndexer
+pub async fn fetch_open_orders_accounts(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Vec<OpenOrdersAccount>, Box<dyn std::error::Error>> {
+    let indexer_address = open_orders_indexer_address(program_id, owner);
+
+    let indexer_account = rpc_client
+        .get_multiple_accounts(&[indexer_address])
+        .await?
+        .into_iter()
+        .next()
+        .flatten();
+
+    // No indexer means the owner never created an open orders account
+    let Some(indexer_account) = indexer_account else {
+        return Ok(Vec::new());
+    };
+
+    let indexer = parse_open_orders_indexer(&indexer_account.data)?;
+
+    let keys: Vec<Pubkey> = indexer
+        .addresses
+        .iter()
+        .filter_map(|a| a.parse().ok())
+        .collect();
+
+    let mut accounts = Vec::new();
+
+    for chunk in keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let fetched = rpc_client.get_multiple_accounts(chunk).await?;
+        for (key, account) in chunk.iter().zip(fetched) {
+            let Some(account) = account else {
+                warn!("⚠️  Open orders account {} not found", key);
+                continue;
+            };
+            match parse_open_orders_account(&account.data, &key.to_string()) {
+                Ok(parsed) => accounts.push(parsed),
+                Err(e) => warn!("⚠️  Failed to parse open orders account {}: {}", key, e),
+            }
+        }
+    }
+
+    Ok(accounts)
+}
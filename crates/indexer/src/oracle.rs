@@ -0,0 +1,456 @@
+use solana_sdk::{account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::programs::{Deployment, MarketLayout};
+use crate::rpc_pool::RpcPool;
+use crate::types::{DeploymentFilter, OraclePrice};
+
+/// Pyth v2 price account magic number
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+
+/// OpenBook V2 StubOracle discriminator (sha256("account:StubOracle")[..8])
+const STUB_ORACLE_DISCRIMINATOR: [u8; 8] = [224, 251, 254, 99, 177, 174, 137, 4];
+
+/// Switchboard V2 AggregatorAccountData discriminator (sha256("account:AggregatorAccountData")[..8])
+const SWITCHBOARD_V2_DISCRIMINATOR: [u8; 8] = [217, 230, 65, 101, 201, 162, 27, 125];
+
+/// Switchboard V1 program - its result accounts have no discriminator, only an owner
+const SWITCHBOARD_V1_PROGRAM_ID: &str = "DtmE9D2CSB4L5D6A15mraeEjrGMm6auWVzgaD8hK2tZM";
+
+/// `getMultipleAccounts` accepts at most 100 keys per request
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OracleType {
+    Pyth,
+    Stub,
+    SwitchboardV1,
+    SwitchboardV2,
+}
+
+impl OracleType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OracleType::Pyth => "pyth",
+            OracleType::Stub => "stub",
+            OracleType::SwitchboardV1 => "switchboard_v1",
+            OracleType::SwitchboardV2 => "switchboard_v2",
+        }
+    }
+}
+
+/// Oracle reading in UI units, as the program's `OracleState`
+#[derive(Debug, Clone)]
+pub struct OracleState {
+    pub oracle_type: OracleType,
+    pub price: f64,
+    pub deviation: f64,
+    pub last_update_slot: u64,
+}
+
+impl OracleState {
+    /// Stale when the last update is older than `max_staleness_slots` (negative disables the check)
+    pub fn is_stale(&self, config: &MarketOracleConfig, now_slot: u64) -> bool {
+        if config.max_staleness_slots < 0 {
+            return false;
+        }
+        self.last_update_slot
+            .saturating_add(config.max_staleness_slots as u64)
+            < now_slot
+    }
+
+    /// Single-oracle confidence filter: deviation <= conf_filter * price
+    pub fn has_valid_confidence(&self, config: &MarketOracleConfig) -> bool {
+        self.deviation <= config.conf_filter * self.price
+    }
+
+    /// Two-oracle confidence filter: relative deviations add up when dividing a by b
+    pub fn has_valid_combined_confidence(
+        &self,
+        other: &OracleState,
+        config: &MarketOracleConfig,
+    ) -> bool {
+        let relative_deviation = self.deviation / self.price + other.deviation / other.price;
+        relative_deviation <= config.conf_filter
+    }
+}
+
+/// Oracle settings of a market, read from the market account
+#[derive(Debug, Clone)]
+pub struct MarketOracleConfig {
+    pub oracle_a: Option<Pubkey>,
+    pub oracle_b: Option<Pubkey>,
+    pub conf_filter: f64,
+    pub max_staleness_slots: i64,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub base_lot_size: i64,
+    pub quote_lot_size: i64,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_i32(data: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_f64(data: &[u8], offset: usize) -> f64 {
+    f64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_i128(data: &[u8], offset: usize) -> i128 {
+    i128::from_le_bytes(data[offset..offset + 16].try_into().unwrap())
+}
+
+/// NonZeroPubkeyOption: the all-zero key means None
+fn read_optional_pubkey(data: &[u8], offset: usize) -> Option<Pubkey> {
+    Pubkey::try_from(&data[offset..offset + 32])
+        .ok()
+        .filter(|k| *k != Pubkey::default())
+}
+
+/// SwitchboardDecimal { mantissa: i128, scale: u32 } as f64
+fn read_switchboard_decimal(data: &[u8], offset: usize) -> f64 {
+    let mantissa = read_i128(data, offset);
+    let scale = read_u32(data, offset + 16);
+    mantissa as f64 / 10f64.powi(scale as i32)
+}
+
+/// Parse the oracle settings out of market account data of the given layout
+pub fn parse_market_oracle_config(
+    data: &[u8],
+    layout: MarketLayout,
+) -> Result<MarketOracleConfig, Box<dyn std::error::Error>> {
+    let offsets = layout.offsets();
+    let (Some(state), Some((base_decimals, quote_decimals))) = (offsets.state, offsets.decimals)
+    else {
+        return Err(format!("Oracle fields of {} markets are unknown", layout.as_str()).into());
+    };
+
+    if data.len() < state.base_lot_size.max(state.quote_lot_size) + 8 {
+        return Err("Account data too small to be a market".into());
+    }
+
+    Ok(MarketOracleConfig {
+        oracle_a: read_optional_pubkey(data, state.oracle_a),
+        oracle_b: read_optional_pubkey(data, state.oracle_b),
+        conf_filter: read_f64(data, state.conf_filter),
+        max_staleness_slots: read_i64(data, state.max_staleness_slots),
+        base_decimals: data[base_decimals],
+        quote_decimals: data[quote_decimals],
+        base_lot_size: read_i64(data, state.base_lot_size),
+        quote_lot_size: read_i64(data, state.quote_lot_size),
+    })
+}
+
+/// Identify the oracle kind the same way the program does
+pub fn determine_oracle_type(account: &Account) -> Option<OracleType> {
+    let data = &account.data;
+
+    if data.len() >= 4 && read_u32(data, 0) == PYTH_MAGIC {
+        return Some(OracleType::Pyth);
+    }
+
+    if data.len() >= 8 && data[0..8] == STUB_ORACLE_DISCRIMINATOR {
+        return Some(OracleType::Stub);
+    }
+
+    if data.len() >= 8 && data[0..8] == SWITCHBOARD_V2_DISCRIMINATOR {
+        return Some(OracleType::SwitchboardV2);
+    }
+
+    if account.owner.to_string() == SWITCHBOARD_V1_PROGRAM_ID {
+        return Some(OracleType::SwitchboardV1);
+    }
+
+    None
+}
+
+/// Read an oracle account into an OracleState (no staleness/confidence filtering)
+pub fn oracle_state_unchecked(
+    account: &Account,
+) -> Result<OracleState, Box<dyn std::error::Error>> {
+    let data = &account.data;
+
+    match determine_oracle_type(account) {
+        Some(OracleType::Pyth) => {
+            // Pyth v2 price account: expo @20, aggregate PriceInfo { price @208, conf @216, pub_slot @232 }
+            if data.len() < 240 {
+                return Err("Pyth price account too small".into());
+            }
+            let expo = read_i32(data, 20);
+            let scale = 10f64.powi(expo);
+            Ok(OracleState {
+                oracle_type: OracleType::Pyth,
+                price: read_i64(data, 208) as f64 * scale,
+                deviation: read_u64(data, 216) as f64 * scale,
+                last_update_slot: read_u64(data, 232),
+            })
+        }
+        Some(OracleType::Stub) => {
+            // StubOracle: owner, mint, price: f64 @72, last_update_ts @80, last_update_slot @88, deviation @96
+            if data.len() < 104 {
+                return Err("Stub oracle account too small".into());
+            }
+            Ok(OracleState {
+                oracle_type: OracleType::Stub,
+                price: read_f64(data, 72),
+                deviation: read_f64(data, 96),
+                last_update_slot: read_u64(data, 88),
+            })
+        }
+        Some(OracleType::SwitchboardV2) => {
+            // AggregatorAccountData (packed): latest_confirmed_round @341 with
+            // round_open_slot @350, result @366, std_deviation @386
+            if data.len() < 406 {
+                return Err("Switchboard V2 aggregator account too small".into());
+            }
+            Ok(OracleState {
+                oracle_type: OracleType::SwitchboardV2,
+                price: read_switchboard_decimal(data, 366),
+                deviation: read_switchboard_decimal(data, 386),
+                last_update_slot: read_u64(data, 350),
+            })
+        }
+        Some(OracleType::SwitchboardV1) => {
+            // FastRoundResultAccountData: account_type u8, parent pubkey, then FastRoundResult
+            // { num_success: i32, num_error: i32, result: f64 @41, round_open_slot @49,
+            //   round_open_timestamp @57, min_response @65, max_response @73 }
+            if data.len() < 81 {
+                return Err("Switchboard V1 result account too small".into());
+            }
+            Ok(OracleState {
+                oracle_type: OracleType::SwitchboardV1,
+                price: read_f64(data, 41),
+                deviation: read_f64(data, 73) - read_f64(data, 65),
+                last_update_slot: read_u64(data, 49),
+            })
+        }
+        None => Err("Unknown oracle account type".into()),
+    }
+}
+
+/// Combined oracle price as computed by `Market::oracle_price`: a alone, or a / b,
+/// rejected when stale or outside the confidence filter
+pub fn combined_oracle_price(
+    config: &MarketOracleConfig,
+    oracle_a: &OracleState,
+    oracle_b: Option<&OracleState>,
+    now_slot: u64,
+) -> Option<f64> {
+    match oracle_b {
+        Some(b) => {
+            if oracle_a.is_stale(config, now_slot)
+                || b.is_stale(config, now_slot)
+                || !oracle_a.has_valid_combined_confidence(b, config)
+            {
+                None
+            } else {
+                Some(oracle_a.price / b.price)
+            }
+        }
+        None => {
+            if oracle_a.is_stale(config, now_slot) || !oracle_a.has_valid_confidence(config) {
+                None
+            } else {
+                Some(oracle_a.price)
+            }
+        }
+    }
+}
+
+/// Convert a UI price (quote per base) to price lots
+pub fn ui_price_to_lots(config: &MarketOracleConfig, price: f64) -> Option<i64> {
+    if config.quote_lot_size == 0 {
+        return None;
+    }
+    let decimals = config.quote_decimals as i32 - config.base_decimals as i32;
+    let native_price = price * 10f64.powi(decimals);
+    Some((native_price * config.base_lot_size as f64 / config.quote_lot_size as f64) as i64)
+}
+
+/// Fetch accounts in chunks, returning the context slot of the last request
+async fn fetch_accounts(
+    rpc_pool: &RpcPool,
+    keys: &[Pubkey],
+) -> Result<(Vec<Option<Account>>, u64), Box<dyn std::error::Error>> {
+    let mut accounts = Vec::with_capacity(keys.len());
+    let mut slot = 0;
+
+    for chunk in keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let response = rpc_pool
+            .call(|rpc| async move {
+                rpc.get_multiple_accounts_with_commitment(chunk, CommitmentConfig::confirmed())
+                    .await
+            })
+            .await?;
+        slot = slot.max(response.context.slot);
+        accounts.extend(response.value);
+    }
+
+    Ok((accounts, slot))
+}
+
+/// Read oracle_a/oracle_b of every market with oracles of `deployments` and store one
+/// price per market
+pub async fn record_oracle_prices(
+    rpc_pool: &RpcPool,
+    db: &crate::Database,
+    deployments: &[Deployment],
+) -> Result<Vec<OraclePrice>, Box<dyn std::error::Error>> {
+    let mut configs: Vec<(Pubkey, MarketOracleConfig)> = Vec::new();
+    for deployment in deployments {
+        let markets = db
+            .get_markets(&DeploymentFilter::deployment(deployment), 1000)
+            .await?;
+        let market_keys: Vec<Pubkey> = markets
+            .iter()
+            .filter_map(|m| Pubkey::from_str(&m.id).ok())
+            .collect();
+
+        let (market_accounts, _) = fetch_accounts(rpc_pool, &market_keys).await?;
+
+        configs.extend(
+            market_keys
+                .iter()
+                .zip(market_accounts)
+                .filter_map(|(key, account)| {
+                    let config =
+                        parse_market_oracle_config(&account?.data, deployment.layout).ok()?;
+                    config.oracle_a.map(|_| (*key, config))
+                }),
+        );
+    }
+
+    if configs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let oracle_keys: Vec<Pubkey> = configs
+        .iter()
+        .flat_map(|(_, c)| [c.oracle_a, c.oracle_b])
+        .flatten()
+        .collect();
+    let (oracle_accounts, slot) = fetch_accounts(rpc_pool, &oracle_keys).await?;
+    let oracles: std::collections::HashMap<Pubkey, Account> = oracle_keys
+        .into_iter()
+        .zip(oracle_accounts)
+        .filter_map(|(key, account)| account.map(|a| (key, a)))
+        .collect();
+
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let mut prices = Vec::new();
+
+    for (market, config) in &configs {
+        let Some(oracle_a_key) = config.oracle_a else {
+            continue;
+        };
+
+        let read = |key: &Pubkey| -> Option<OracleState> {
+            let account = oracles.get(key)?;
+            match oracle_state_unchecked(account) {
+                Ok(state) => Some(state),
+                Err(e) => {
+                    warn!("⚠️  Failed to read oracle {}: {}", key, e);
+                    None
+                }
+            }
+        };
+
+        let Some(state_a) = read(&oracle_a_key) else {
+            continue;
+        };
+        let state_b = match config.oracle_b {
+            Some(key) => match read(&key) {
+                Some(state) => Some(state),
+                None => continue,
+            },
+            None => None,
+        };
+
+        let is_stale = state_a.is_stale(config, slot)
+            || state_b.as_ref().is_some_and(|b| b.is_stale(config, slot));
+        let price = combined_oracle_price(config, &state_a, state_b.as_ref(), slot);
+
+        let oracle_price = OraclePrice {
+            market_id: market.to_string(),
+            oracle_a: oracle_a_key.to_string(),
+            oracle_a_type: state_a.oracle_type.as_str().to_string(),
+            oracle_a_price: state_a.price,
+            oracle_a_deviation: state_a.deviation,
+            oracle_a_last_update_slot: state_a.last_update_slot as i64,
+            oracle_b: config.oracle_b.map(|k| k.to_string()),
+            oracle_b_type: state_b.as_ref().map(|b| b.oracle_type.as_str().to_string()),
+            oracle_b_price: state_b.as_ref().map(|b| b.price),
+            oracle_b_deviation: state_b.as_ref().map(|b| b.deviation),
+            oracle_b_last_update_slot: state_b.as_ref().map(|b| b.last_update_slot as i64),
+            price,
+            price_lots: price.and_then(|p| ui_price_to_lots(config, p)),
+            is_stale,
+            slot: slot as i64,
+            timestamp: now_ms,
+        };
+
+        db.insert_oracle_price(&oracle_price).await?;
+        prices.push(oracle_price);
+    }
+
+    Ok(prices)
+}
+
+/// Periodically record the combined oracle price of every market that has oracles configured
+pub async fn monitor_oracle_prices(
+    rpc_pool: &RpcPool,
+    db: &crate::Database,
+    deployments: &[Deployment],
+    interval: Duration,
+) {
+    info!(
+        "🔮 Oracle price monitor started (interval: {}s)",
+        interval.as_secs()
+    );
+
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+
+        let result = record_oracle_prices(rpc_pool, db, deployments)
+            .await
+            .map_err(|e| e.to_string());
+
+        match result {
+            Ok(prices) => {
+                for p in &prices {
+                    match p.price {
+                        Some(price) => info!(
+                            "  🔮 Oracle {}: {:.6}",
+                            &p.market_id[..12.min(p.market_id.len())],
+                            price
+                        ),
+                        None => warn!(
+                            "  🔮 Oracle {}: rejected (stale: {})",
+                            &p.market_id[..12.min(p.market_id.len())],
+                            p.is_stale
+                        ),
+                    }
+                }
+            }
+            Err(e) => {
+                warn!("⚠️  Oracle price snapshot failed: {}", e);
+            }
+        }
+    }
+}
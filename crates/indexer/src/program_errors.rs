@@ -0,0 +1,111 @@
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+
+/// Custom errors of the OpenBook v2 program (`errors` of its IDL): code, name, message
+pub const OPENBOOK_ERRORS: &[(u32, &str, &str)] = &[
+    (6000, "SomeError", ""),
+    (6001, "InvalidInputNameLength", "Name lenght above limit"),
+    (6002, "InvalidInputMarketExpired", "Market cannot be created as expired"),
+    (6003, "InvalidInputMarketFees", "Taker fees should be positive and if maker fees are negative, greater or equal to their abs value"),
+    (6004, "InvalidInputLots", "Lots cannot be negative"),
+    (6005, "InvalidInputLotsSize", "Lots size above market limits"),
+    (6006, "InvalidInputOrdersAmounts", "Input amounts above limits"),
+    (6007, "InvalidInputCancelSize", "Price lots should be greater than zero"),
+    (6008, "InvalidInputPriceLots", "Expected cancel size should be greater than zero"),
+    (6009, "InvalidInputPegLimit", "Peg limit should be greater than zero"),
+    (6010, "InvalidInputOrderType", "The order type is invalid. A taker order must be Market or ImmediateOrCancel"),
+    (6011, "InvalidInputOrderId", "Order id cannot be zero"),
+    (6012, "InvalidInputHeapSlots", "Slot above heap limit"),
+    (6013, "InvalidOracleTypes", "Cannot combine two oracles of different providers"),
+    (6014, "InvalidSecondOracle", "Cannot configure secondary oracle without primary"),
+    (6015, "NoCloseMarketAdmin", "This market does not have a `close_market_admin` and thus cannot be closed."),
+    (6016, "InvalidCloseMarketAdmin", "The signer of this transaction is not this market's `close_market_admin`."),
+    (6017, "InvalidOpenOrdersAdmin", "The `open_orders_admin` required by this market to sign all instructions that creates orders is missing or is not valid"),
+    (6018, "InvalidConsumeEventsAdmin", "The `consume_events_admin` required by this market to sign all instructions that consume events is missing or is not valid"),
+    (6019, "InvalidMarketVault", "Provided `market_vault` is invalid"),
+    (6020, "IndexerActiveOO", "Cannot be closed due to the existence of open orders accounts"),
+    (6021, "OraclePegInvalidOracleState", "Cannot place a peg order due to invalid oracle state"),
+    (6022, "UnknownOracleType", "oracle type cannot be determined"),
+    (6023, "OracleConfidence", "an oracle does not reach the confidence threshold"),
+    (6024, "OracleStale", "an oracle is stale"),
+    (6025, "OrderIdNotFound", "Order id not found on the orderbook"),
+    (6026, "EventHeapContainsElements", "Event heap contains elements and market can't be closed"),
+    (6027, "InvalidOrderPostIOC", "ImmediateOrCancel is not a PostOrderType"),
+    (6028, "InvalidOrderPostMarket", "Market is not a PostOrderType"),
+    (6029, "WouldSelfTrade", "would self trade"),
+    (6030, "MarketHasExpired", "The Market has already expired."),
+    (6031, "InvalidPriceLots", "Price lots should be greater than zero"),
+    (6032, "InvalidOraclePrice", "Oracle price above market limits"),
+    (6033, "MarketHasNotExpired", "The Market has not expired yet."),
+    (6034, "NoOwnerOrDelegate", "No correct owner or delegate."),
+    (6035, "NoOwner", "No correct owner"),
+    (6036, "OpenOrdersFull", "No free order index in open orders account"),
+    (6037, "BookContainsElements", "Book contains elements"),
+    (6038, "OpenOrdersOrderNotFound", "Could not find order in user account"),
+    (6039, "InvalidPostAmount", "Amount to post above book limits"),
+    (6040, "DisabledOraclePeg", "Oracle peg orders are not enabled for this market"),
+    (6041, "NonEmptyMarket", "Cannot close a non-empty market"),
+    (6042, "NonEmptyOpenOrdersPosition", "Cannot close a non-empty open orders account"),
+];
+
+/// Name of an OpenBook error code, as in the IDL
+pub fn error_name(code: u32) -> Option<&'static str> {
+    error_entry(code).map(|(_, name, _)| name)
+}
+
+/// Message of an OpenBook error code, as in the IDL (empty for some codes)
+pub fn error_message(code: u32) -> Option<&'static str> {
+    error_entry(code).map(|(_, _, message)| message)
+}
+
+fn error_entry(code: u32) -> Option<(u32, &'static str, &'static str)> {
+    OPENBOOK_ERRORS
+        .iter()
+        .find(|(entry_code, _, _)| *entry_code == code)
+        .copied()
+}
+
+/// Why a transaction failed on-chain, from its status meta `err`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionFailure {
+    /// Top-level instruction that failed, when the error is an instruction error
+    pub instruction_index: Option<i32>,
+    /// Custom program error code
+    pub error_code: Option<u32>,
+    /// OpenBook error name of `error_code`, when it is one of the IDL's
+    pub error_name: Option<&'static str>,
+    /// Readable description of the whole error
+    pub message: String,
+}
+
+impl TransactionFailure {
+    pub fn from_error(error: &TransactionError) -> Self {
+        let (instruction_index, error_code) = match error {
+            TransactionError::InstructionError(index, InstructionError::Custom(code)) => {
+                (Some(*index as i32), Some(*code))
+            }
+            TransactionError::InstructionError(index, _) => (Some(*index as i32), None),
+            _ => (None, None),
+        };
+        let error_name = error_code.and_then(error_name);
+
+        let message = match (error_code, error_name) {
+            (Some(code), Some(name)) => {
+                let description = error_message(code).unwrap_or_default();
+                if description.is_empty() {
+                    format!("OpenBook error {} {}", code, name)
+                } else {
+                    format!("OpenBook error {} {}: {}", code, name, description)
+                }
+            }
+            _ => error.to_string(),
+        };
+
+        Self {
+            instruction_index,
+            error_code,
+            error_name,
+            message,
+        }
+    }
+}
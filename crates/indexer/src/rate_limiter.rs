@@ -0,0 +1,152 @@
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Rate multiplier applied when the RPC answers 429 Too Many Requests
+const RATE_LIMITED_FACTOR: f64 = 0.5;
+
+/// Rate multiplier applied on any other RPC error
+const ERROR_FACTOR: f64 = 0.8;
+
+/// Requests per second added after each successful request
+const SUCCESS_INCREMENT: f64 = 0.1;
+
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// Latency of the requests made since the previous snapshot
+#[derive(Debug, Clone, Default)]
+pub struct LatencyStats {
+    pub requests: u64,
+    pub errors: u64,
+    pub rate_limited: u64,
+    pub avg_latency_ms: f64,
+    pub max_latency_ms: u64,
+}
+
+/// Token-bucket limiter for RPC requests that adapts its rate to how the endpoint behaves
+///
+/// The rate grows additively while requests succeed and shrinks multiplicatively on
+/// 429s and errors, staying within `[min_rate, max_rate]` requests per second.
+pub struct AdaptiveRateLimiter {
+    bucket: Mutex<Bucket>,
+    min_rate: f64,
+    max_rate: f64,
+    requests: AtomicU64,
+    errors: AtomicU64,
+    rate_limited: AtomicU64,
+    total_latency_ms: AtomicU64,
+    max_latency_ms: AtomicU64,
+}
+
+impl AdaptiveRateLimiter {
+    pub fn new(initial_rate: f64, min_rate: f64, max_rate: f64) -> Self {
+        let min_rate = min_rate.max(0.1);
+        let max_rate = max_rate.max(min_rate);
+        let rate = initial_rate.clamp(min_rate, max_rate);
+
+        Self {
+            bucket: Mutex::new(Bucket {
+                rate,
+                tokens: 1.0,
+                last_refill: Instant::now(),
+            }),
+            min_rate,
+            max_rate,
+            requests: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            rate_limited: AtomicU64::new(0),
+            total_latency_ms: AtomicU64::new(0),
+            max_latency_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// Current rate in requests per second
+    pub fn rate(&self) -> f64 {
+        self.bucket.lock().unwrap().rate
+    }
+
+    /// Wait until a request may be sent
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+                // Allow bursts of up to one second worth of requests
+                bucket.tokens = (bucket.tokens + elapsed * bucket.rate).min(bucket.rate.max(1.0));
+                bucket.last_refill = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+
+                Duration::from_secs_f64((1.0 - bucket.tokens) / bucket.rate)
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Record a successful request and speed up
+    pub fn record_success(&self, latency: Duration) {
+        self.record_latency(latency);
+        self.adjust_rate(|rate| rate + SUCCESS_INCREMENT);
+    }
+
+    /// Record a failed request and slow down (harder if the endpoint rate limited us)
+    pub fn record_error(&self, latency: Duration, error: &ClientError) {
+        self.record_latency(latency);
+        self.errors.fetch_add(1, Ordering::Relaxed);
+
+        if is_rate_limited(error) {
+            self.rate_limited.fetch_add(1, Ordering::Relaxed);
+            self.adjust_rate(|rate| rate * RATE_LIMITED_FACTOR);
+        } else {
+            self.adjust_rate(|rate| rate * ERROR_FACTOR);
+        }
+    }
+
+    /// Latency stats since the last call
+    pub fn take_stats(&self) -> LatencyStats {
+        let requests = self.requests.swap(0, Ordering::Relaxed);
+        let total_latency_ms = self.total_latency_ms.swap(0, Ordering::Relaxed);
+
+        LatencyStats {
+            requests,
+            errors: self.errors.swap(0, Ordering::Relaxed),
+            rate_limited: self.rate_limited.swap(0, Ordering::Relaxed),
+            avg_latency_ms: if requests > 0 {
+                total_latency_ms as f64 / requests as f64
+            } else {
+                0.0
+            },
+            max_latency_ms: self.max_latency_ms.swap(0, Ordering::Relaxed),
+        }
+    }
+
+    fn record_latency(&self, latency: Duration) {
+        let ms = latency.as_millis() as u64;
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.total_latency_ms.fetch_add(ms, Ordering::Relaxed);
+        self.max_latency_ms.fetch_max(ms, Ordering::Relaxed);
+    }
+
+    fn adjust_rate(&self, f: impl FnOnce(f64) -> f64) {
+        let mut bucket = self.bucket.lock().unwrap();
+        bucket.rate = f(bucket.rate).clamp(self.min_rate, self.max_rate);
+    }
+}
+
+/// Whether the RPC rejected the request with HTTP 429
+pub fn is_rate_limited(error: &ClientError) -> bool {
+    match error.kind() {
+        ClientErrorKind::Reqwest(e) => e.status().map(|s| s.as_u16()) == Some(429),
+        other => other.to_string().contains("429"),
+    }
+}
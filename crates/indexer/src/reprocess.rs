@@ -0,0 +1,277 @@
+//! Derives rows again from the raw transaction archive, falling back to RPC for
+//! transactions indexed before they were archived
+
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, warn};
+
+use crate::archive::decompress_transaction;
+use crate::event_processor::{derive_writes, index_transaction, PARSER_VERSION};
+use crate::ingest::block_time::{estimate_block_time, resolve_block_time, BlockTimeCache};
+use crate::ingest::logs::{fetch_transaction, BlockSignatureCache, PendingSignature};
+use crate::ingest::IngestedTransaction;
+use crate::programs::Deployment;
+use crate::rate_limiter::AdaptiveRateLimiter;
+use crate::rpc_pool::RpcPool;
+use crate::types::{ApplyMode, OutdatedTransaction};
+use crate::Database;
+
+/// Archived transactions read per database round trip
+const PAGE_SIZE: i64 = 500;
+
+/// A window of indexed history to derive again
+#[derive(Debug, Clone)]
+pub struct ReprocessOptions {
+    /// Program (and cluster) whose transactions are derived again
+    pub deployment: Deployment,
+    /// Only transactions touching this market
+    pub market: Option<String>,
+    /// Oldest slot to include
+    pub from_slot: Option<u64>,
+    /// Newest slot to include
+    pub to_slot: Option<u64>,
+    /// Only transactions whose rows an older `PARSER_VERSION` derived; progress is
+    /// reported in `indexer_status`
+    pub outdated: bool,
+    /// Report what would be written without touching the database
+    pub dry_run: bool,
+}
+
+/// Totals of a reprocess run
+#[derive(Debug, Clone, Default)]
+pub struct ReprocessReport {
+    pub transactions: u64,
+    pub events: u64,
+    pub orders: u64,
+    pub trades: u64,
+    /// Not in the archive and fetched over RPC
+    pub fetched: u64,
+    /// Not in the archive, and no RPC to fetch them from
+    pub missing: u64,
+    pub failed: u64,
+    /// Rows deleted before deriving again (or that would be, on a dry run)
+    pub replaced_events: i64,
+    pub replaced_orders: i64,
+    pub replaced_trades: i64,
+}
+
+/// Replace the rows derived from every transaction in the window with rows derived by
+/// the current parser
+///
+/// Without `outdated`, only archived transactions are read. With it, outdated
+/// transactions missing from the archive are fetched from `rpc` when given. Transactions
+/// are written in `Rebuild` mode, so the live checkpoint never moves.
+pub async fn run_reprocess(
+    db: &Database,
+    rpc: Option<(Arc<RpcPool>, Arc<AdaptiveRateLimiter>)>,
+    options: &ReprocessOptions,
+) -> Result<ReprocessReport, Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    let cluster = options.deployment.cluster.clone();
+    let program_id = options.deployment.program();
+    let from_slot = options.from_slot.map(|slot| slot as i64);
+    let to_slot = options.to_slot.map(|slot| slot as i64);
+    let track_progress = options.outdated && !options.dry_run;
+    let mut report = ReprocessReport::default();
+    let mut after: Option<(i64, String)> = None;
+    // Transactions dealt with so far, failed ones included
+    let mut handled: i64 = 0;
+    let block_signatures = Arc::new(BlockSignatureCache::default());
+    let block_times = Arc::new(BlockTimeCache::default());
+
+    if options.outdated {
+        let total = db
+            .count_outdated_transactions(
+                &cluster,
+                &program_id,
+                PARSER_VERSION,
+                options.market.as_deref(),
+                from_slot,
+                to_slot,
+            )
+            .await?;
+        info!(
+            "📜 {} transactions derived by a parser older than version {}",
+            total, PARSER_VERSION
+        );
+        if track_progress {
+            db.start_reindex(&cluster, &program_id, PARSER_VERSION, total)
+                .await?;
+        }
+    }
+
+    loop {
+        let after_key = after
+            .as_ref()
+            .map(|(slot, signature)| (*slot, signature.as_str()));
+        let page: Vec<OutdatedTransaction> = if options.outdated {
+            db.get_outdated_transactions(
+                &cluster,
+                &program_id,
+                PARSER_VERSION,
+                options.market.as_deref(),
+                from_slot,
+                to_slot,
+                after_key,
+                PAGE_SIZE,
+            )
+            .await?
+        } else {
+            db.get_raw_transactions(
+                &cluster,
+                &program_id,
+                options.market.as_deref(),
+                from_slot,
+                to_slot,
+                after_key,
+                PAGE_SIZE,
+            )
+            .await?
+            .into_iter()
+            .map(|raw| OutdatedTransaction {
+                signature: raw.signature.clone(),
+                slot: raw.slot,
+                raw: Some(raw),
+            })
+            .collect()
+        };
+        let Some(last) = page.last() else {
+            break;
+        };
+        let last_slot = last.slot;
+        after = Some((last.slot, last.signature.clone()));
+
+        let signatures: Vec<String> = page.iter().map(|entry| entry.signature.clone()).collect();
+        let (events, orders, trades) = db.count_derived_rows(&signatures).await?;
+        report.replaced_events += events;
+        report.replaced_orders += orders;
+        report.replaced_trades += trades;
+
+        for entry in page {
+            handled += 1;
+            let Some(tx) = load_transaction(
+                entry,
+                rpc.as_ref(),
+                &block_signatures,
+                &block_times,
+                options.deployment.program_id,
+                &mut report,
+            )
+            .await
+            else {
+                continue;
+            };
+
+            report.transactions += 1;
+
+            let derived = if options.dry_run {
+                let derived = derive_writes(&tx, &options.deployment, db).await;
+                if !derived.events.is_empty() {
+                    info!(
+                        "  🔍 Slot {} {}: {} → {} orders, {} trades",
+                        tx.slot,
+                        tx.signature,
+                        derived.events.join(", "),
+                        derived.writes.orders.len(),
+                        derived.writes.trades.len()
+                    );
+                }
+                derived
+            } else {
+                // Decode and persist failures land in the dead-letter queue
+                match index_transaction(&tx, &options.deployment, db, ApplyMode::Rebuild).await {
+                    Ok((derived, _)) => derived,
+                    Err(_) => {
+                        report.failed += 1;
+                        continue;
+                    }
+                }
+            };
+
+            report.events += derived.events.len() as u64;
+            report.orders += derived.writes.orders.len() as u64;
+            report.trades += derived.writes.trades.len() as u64;
+        }
+
+        if track_progress {
+            db.update_reindex_progress(
+                &cluster,
+                &program_id,
+                handled,
+                (report.failed + report.missing) as i64,
+            )
+            .await?;
+        }
+
+        info!(
+            "⏱️  PROGRESS: {} transactions up to slot {} in {:.1}s",
+            report.transactions,
+            last_slot,
+            start.elapsed().as_secs_f64()
+        );
+    }
+
+    Ok(report)
+}
+
+/// The transaction to derive from: its archived copy, or a fresh one from `rpc`
+async fn load_transaction(
+    entry: OutdatedTransaction,
+    rpc: Option<&(Arc<RpcPool>, Arc<AdaptiveRateLimiter>)>,
+    block_signatures: &Arc<BlockSignatureCache>,
+    block_times: &Arc<BlockTimeCache>,
+    program_id: Pubkey,
+    report: &mut ReprocessReport,
+) -> Option<IngestedTransaction> {
+    if let Some(raw) = &entry.raw {
+        return match decompress_transaction(raw) {
+            // Archived before its block time was known: resolve it now if possible
+            Ok(mut tx) => {
+                match rpc {
+                    Some((rpc_pool, rate_limiter)) => {
+                        resolve_block_time(rpc_pool, rate_limiter, block_times, &mut tx).await
+                    }
+                    None => estimate_block_time(block_times, &mut tx),
+                }
+                Some(tx)
+            }
+            Err(e) => {
+                warn!("⚠️  Unreadable archive entry {}: {}", entry.signature, e);
+                report.failed += 1;
+                None
+            }
+        };
+    }
+
+    let Some((rpc_pool, rate_limiter)) = rpc else {
+        warn!("⚠️  {} is not archived, skipping", entry.signature);
+        report.missing += 1;
+        return None;
+    };
+    let pending = PendingSignature {
+        signature: entry.signature,
+        slot: entry.slot as u64,
+        live: false,
+    };
+    match fetch_transaction(
+        rpc_pool.clone(),
+        rate_limiter.clone(),
+        block_signatures.clone(),
+        block_times.clone(),
+        program_id,
+        pending,
+    )
+    .await
+    {
+        Ok(tx) => {
+            report.fetched += 1;
+            Some(tx)
+        }
+        Err(e) => {
+            warn!("⚠️  {}", e);
+            report.failed += 1;
+            None
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use solana_client::client_error::ClientError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::config::RpcConfig;
+use crate::metrics::metrics;
+
+/// Weight of the newest outcome in the error rate moving average
+const ERROR_RATE_ALPHA: f64 = 0.2;
+
+/// Error rate above which an endpoint is only used when nothing better is left
+const MAX_HEALTHY_ERROR_RATE: f64 = 0.5;
+
+/// Slots an endpoint may trail the freshest one before it counts as lagging
+const MAX_HEALTHY_SLOT_LAG: u64 = 50;
+
+/// Score penalty for an error rate of 1.0, in slots
+const ERROR_RATE_WEIGHT: f64 = 100.0;
+
+/// Health-check request timeout
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Observed health of one endpoint
+#[derive(Debug, Clone, Default)]
+pub struct EndpointHealth {
+    /// Latest slot reported by the endpoint (0 until the first health check)
+    pub slot: u64,
+    /// Exponential moving average of request failures, 0.0 - 1.0
+    pub error_rate: f64,
+    pub consecutive_errors: u64,
+    pub last_latency_ms: u64,
+    pub last_checked: Option<Instant>,
+}
+
+/// One RPC provider: an HTTP endpoint and, optionally, its WebSocket counterpart
+pub struct RpcEndpoint {
+    pub http_url: String,
+    pub ws_url: Option<String>,
+    pub client: Arc<RpcClient>,
+    health: Mutex<EndpointHealth>,
+}
+
+impl RpcEndpoint {
+    fn new(http_url: String, ws_url: Option<String>) -> Self {
+        Self {
+            client: Arc::new(RpcClient::new(http_url.clone())),
+            http_url,
+            ws_url,
+            health: Mutex::new(EndpointHealth::default()),
+        }
+    }
+
+    pub fn health(&self) -> EndpointHealth {
+        self.health.lock().unwrap().clone()
+    }
+
+    fn record_success(&self, latency: Duration) {
+        let mut health = self.health.lock().unwrap();
+        health.error_rate *= 1.0 - ERROR_RATE_ALPHA;
+        health.consecutive_errors = 0;
+        health.last_latency_ms = latency.as_millis() as u64;
+    }
+
+    fn record_error(&self) {
+        let mut health = self.health.lock().unwrap();
+        health.error_rate = health.error_rate * (1.0 - ERROR_RATE_ALPHA) + ERROR_RATE_ALPHA;
+        health.consecutive_errors += 1;
+    }
+}
+
+/// Pool of RPC endpoints that routes each request to the healthiest one and fails over
+/// to the next on error
+///
+/// Health is scored from slot freshness (distance to the freshest endpoint) and the
+/// moving error rate of real requests and periodic health checks.
+pub struct RpcPool {
+    endpoints: Vec<RpcEndpoint>,
+    commitment: CommitmentConfig,
+}
+
+impl RpcPool {
+    /// Build a pool from HTTP URLs and their WebSocket URLs (matched by position)
+    pub fn new(http_urls: Vec<String>, ws_urls: Vec<String>) -> Result<Self, String> {
+        if http_urls.is_empty() {
+            return Err("RPC pool needs at least one endpoint".to_string());
+        }
+
+        let mut ws_urls = ws_urls.into_iter();
+        let endpoints = http_urls
+            .into_iter()
+            .map(|http| RpcEndpoint::new(http, ws_urls.next()))
+            .collect();
+
+        Ok(Self {
+            endpoints,
+            commitment: CommitmentConfig::confirmed(),
+        })
+    }
+
+    /// Build a pool from the `[rpc]` settings
+    pub fn from_config(config: &RpcConfig) -> Result<Self, String> {
+        let commitment = config.commitment_config()?;
+        Ok(Self::new(config.urls.clone(), config.ws_urls.clone())?.with_commitment(commitment))
+    }
+
+    /// Ingest at `commitment` instead of `confirmed`
+    pub fn with_commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.commitment = commitment;
+        self
+    }
+
+    pub fn endpoints(&self) -> &[RpcEndpoint] {
+        &self.endpoints
+    }
+
+    /// Commitment transactions and slots are read at
+    pub fn commitment(&self) -> CommitmentConfig {
+        self.commitment
+    }
+
+    /// Endpoint indices, best first
+    fn ranked(&self) -> Vec<usize> {
+        let health: Vec<EndpointHealth> = self.endpoints.iter().map(|e| e.health()).collect();
+        let freshest = health.iter().map(|h| h.slot).max().unwrap_or(0);
+
+        let rank = |h: &EndpointHealth| {
+            let slot_lag = freshest.saturating_sub(h.slot);
+            let unhealthy =
+                slot_lag > MAX_HEALTHY_SLOT_LAG || h.error_rate > MAX_HEALTHY_ERROR_RATE;
+            (
+                unhealthy,
+                slot_lag as f64 + h.error_rate * ERROR_RATE_WEIGHT,
+            )
+        };
+
+        let mut order: Vec<usize> = (0..self.endpoints.len()).collect();
+        // Stable sort: configuration order breaks ties
+        order.sort_by(|&a, &b| {
+            let (a_unhealthy, a_score) = rank(&health[a]);
+            let (b_unhealthy, b_score) = rank(&health[b]);
+            a_unhealthy
+                .cmp(&b_unhealthy)
+                .then(a_score.total_cmp(&b_score))
+        });
+        order
+    }
+
+    /// The endpoint requests currently go to
+    pub fn best(&self) -> &RpcEndpoint {
+        &self.endpoints[self.ranked()[0]]
+    }
+
+    /// WebSocket URL of the best endpoint that has one
+    pub fn ws_url(&self) -> Option<String> {
+        self.ranked()
+            .into_iter()
+            .find_map(|i| self.endpoints[i].ws_url.clone())
+    }
+
+    /// Count a failure against the endpoint serving `ws_url` (e.g. a dropped subscription)
+    pub fn record_ws_error(&self, ws_url: &str) {
+        if let Some(endpoint) = self
+            .endpoints
+            .iter()
+            .find(|e| e.ws_url.as_deref() == Some(ws_url))
+        {
+            endpoint.record_error();
+        }
+    }
+
+    /// Run a request against the best endpoint, failing over to the others in order
+    ///
+    /// Returns the last error if every endpoint fails.
+    pub async fn call<T, F, Fut>(&self, request: F) -> Result<T, ClientError>
+    where
+        F: Fn(Arc<RpcClient>) -> Fut,
+        Fut: Future<Output = Result<T, ClientError>>,
+    {
+        let mut last_error = None;
+
+        for index in self.ranked() {
+            let endpoint = &self.endpoints[index];
+            let start = Instant::now();
+
+            match request(endpoint.client.clone()).await {
+                Ok(value) => {
+                    endpoint.record_success(start.elapsed());
+                    metrics()
+                        .rpc_request_duration
+                        .observe(&[endpoint.http_url.as_str(), "ok"], start.elapsed());
+                    return Ok(value);
+                }
+                Err(e) => {
+                    endpoint.record_error();
+                    metrics()
+                        .rpc_request_duration
+                        .observe(&[endpoint.http_url.as_str(), "error"], start.elapsed());
+                    metrics().rpc_errors.inc(&[endpoint.http_url.as_str()]);
+                    if self.endpoints.len() > 1 {
+                        warn!("⚠️  RPC {} failed, failing over: {}", endpoint.http_url, e);
+                    }
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.expect("pool has at least one endpoint"))
+    }
+
+    /// Poll every endpoint's slot once and update its health
+    pub async fn check_health(&self) {
+        let checks = self.endpoints.iter().map(|endpoint| async move {
+            let start = Instant::now();
+            let result = tokio::time::timeout(
+                HEALTH_CHECK_TIMEOUT,
+                endpoint
+                    .client
+                    .get_slot_with_commitment(CommitmentConfig::confirmed()),
+            )
+            .await;
+
+            match result {
+                Ok(Ok(slot)) => {
+                    endpoint.record_success(start.elapsed());
+                    let mut health = endpoint.health.lock().unwrap();
+                    health.slot = slot;
+                    health.last_checked = Some(Instant::now());
+                }
+                Ok(Err(e)) => {
+                    endpoint.record_error();
+                    warn!("⚠️  Health check of {} failed: {}", endpoint.http_url, e);
+                }
+                Err(_) => {
+                    endpoint.record_error();
+                    warn!("⚠️  Health check of {} timed out", endpoint.http_url);
+                }
+            }
+        });
+
+        futures::future::join_all(checks).await;
+    }
+
+    /// Periodically health-check every endpoint
+    pub async fn monitor_health(&self, interval: Duration) {
+        info!(
+            "🩺 RPC pool health monitor started ({} endpoints, interval: {}s)",
+            self.endpoints.len(),
+            interval.as_secs()
+        );
+
+        let mut ticker = tokio::time::interval(interval);
+        let mut best = None;
+
+        loop {
+            ticker.tick().await;
+            self.check_health().await;
+
+            let current = self.best().http_url.clone();
+            if best.as_ref() != Some(&current) {
+                info!("🩺 Best RPC endpoint: {}", current);
+                best = Some(current);
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Graceful shutdown on SIGINT/SIGTERM
+//!
+//! Tasks watch a [`Shutdown`] to stop taking new work once a signal arrives and finish
+//! what is in flight; a watchdog exits the process if that takes longer than the deadline.
+
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{error, info};
+
+/// Shared view of whether shutdown was requested; clone it into every task that drains
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    requested: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    /// Request shutdown on the first SIGINT or SIGTERM
+    pub fn on_signals() -> Self {
+        let (sender, shutdown) = Self::channel();
+        tokio::spawn(async move {
+            let signal = wait_for_signal().await;
+            info!("🛑 {} received: shutting down...", signal);
+            let _ = sender.send(true);
+        });
+        shutdown
+    }
+
+    /// Shutdown requested by sending `true` on the returned sender
+    pub fn channel() -> (watch::Sender<bool>, Self) {
+        let (sender, requested) = watch::channel(false);
+        (sender, Self { requested })
+    }
+
+    pub fn is_requested(&self) -> bool {
+        *self.requested.borrow()
+    }
+
+    /// Completes once shutdown is requested
+    pub async fn requested(&self) {
+        let mut requested = self.requested.clone();
+        if requested.wait_for(|requested| *requested).await.is_err() {
+            // Nobody can request it anymore
+            std::future::pending::<()>().await;
+        }
+    }
+
+    /// Wait for the next piece of work, unless shutdown is requested first (`None`)
+    ///
+    /// Only the wait is interrupted: work taken before the request is left to finish.
+    pub async fn until_requested<T>(&self, next: impl Future<Output = T>) -> Option<T> {
+        tokio::select! {
+            biased;
+            _ = self.requested() => None,
+            next = next => Some(next),
+        }
+    }
+
+    /// Exit the process if draining is still going on `timeout` after shutdown was requested
+    pub fn exit_after(&self, timeout: Duration) {
+        let shutdown = self.clone();
+        tokio::spawn(async move {
+            shutdown.requested().await;
+            tokio::time::sleep(timeout).await;
+            error!(
+                "❌ Shutdown did not complete within {}s, exiting",
+                timeout.as_secs()
+            );
+            std::process::exit(1);
+        });
+    }
+}
+
+/// Name of the first termination signal received
+async fn wait_for_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => tokio::select! {
+                _ = tokio::signal::ctrl_c() => "SIGINT",
+                _ = terminate.recv() => "SIGTERM",
+            },
+            Err(e) => {
+                error!("❌ Cannot listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl-C"
+    }
+}
@@ -7,6 +7,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Market {
     pub id: String,
+    pub cluster: String,
+    pub program_id: String,
     pub base_mint: String,
     pub quote_mint: String,
     pub symbol: String,
@@ -28,16 +30,276 @@ pub struct Order {
     pub filled: i64,
     pub status: String,
     pub timestamp: i64,
+    /// `confirmed` until the finalizer settles the slot, then `finalized`
+    pub commitment: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trade {
     pub id: String,
+    /// Monotonic id from the trade's chain position (see `event_processor::trade_id`),
+    /// None until the trade is placed in its block
+    pub trade_id: Option<i64>,
     pub market_id: String,
     pub price: i64,
     pub quantity: i64,
     pub side: String,
     pub timestamp: i64,
+    /// `timestamp` was estimated from neighbouring slots
+    pub time_estimated: bool,
+    /// `confirmed` until the finalizer settles the slot, then `finalized`
+    pub commitment: String,
+}
+
+/// Point-in-time crank lag measurement for one market's event heap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventHeapSnapshot {
+    pub market_id: String,
+    pub event_heap: String,
+    pub pending_events: i32,
+    pub fill_events: i32,
+    pub out_events: i32,
+    pub oldest_event_timestamp: Option<i64>,
+    pub oldest_event_age_secs: Option<i64>,
+    pub fullness: f64,
+    pub seq_num: i64,
+    pub slot: i64,
+    pub timestamp: i64,
+}
+
+/// Oracle reading for a market with oracle_a (and optionally oracle_b) configured
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OraclePrice {
+    pub market_id: String,
+    pub oracle_a: String,
+    pub oracle_a_type: String,
+    pub oracle_a_price: f64,
+    pub oracle_a_deviation: f64,
+    pub oracle_a_last_update_slot: i64,
+    pub oracle_b: Option<String>,
+    pub oracle_b_type: Option<String>,
+    pub oracle_b_price: Option<f64>,
+    pub oracle_b_deviation: Option<f64>,
+    pub oracle_b_last_update_slot: Option<i64>,
+    /// Combined price in UI units; None when rejected by the staleness/confidence filters
+    pub price: Option<f64>,
+    pub price_lots: Option<i64>,
+    pub is_stale: bool,
+    pub slot: i64,
+    pub timestamp: i64,
+}
+
+/// Token balances held by a market's base and quote vaults
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultBalance {
+    pub market_id: String,
+    pub base_vault: String,
+    pub quote_vault: String,
+    pub base_amount: i64,
+    pub quote_amount: i64,
+    pub base_deposit_total: i64,
+    pub quote_deposit_total: i64,
+    /// Price (in lots) used to value the base vault, if the book had one
+    pub price_lots: Option<i64>,
+    /// quote_amount + base_amount valued at price_lots, in quote native units
+    pub tvl_in_quote: Option<i64>,
+    pub slot: i64,
+    pub timestamp: i64,
+}
+
+/// Restricts queries to one cluster and/or program; `None` matches everything
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeploymentFilter {
+    pub cluster: Option<String>,
+    pub program_id: Option<String>,
+}
+
+impl DeploymentFilter {
+    /// Everything indexed from one cluster
+    pub fn cluster(cluster: &str) -> Self {
+        Self {
+            cluster: Some(cluster.to_string()),
+            program_id: None,
+        }
+    }
+
+    /// Everything indexed from one program on one cluster
+    pub fn deployment(deployment: &crate::programs::Deployment) -> Self {
+        Self {
+            cluster: Some(deployment.cluster.clone()),
+            program_id: Some(deployment.program()),
+        }
+    }
+}
+
+/// Last fully processed transaction of a program, persisted in `indexer_status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub cluster: String,
+    pub program_id: String,
+    pub slot: i64,
+    pub signature: String,
+    pub timestamp: i64,
+    pub total_events_processed: i64,
+    pub updated_at: i64,
+    /// Slots up to here are settled: finalized or rolled back
+    pub finalized_slot: Option<i64>,
+    /// Latest re-derivation of rows from outdated parser versions
+    pub reindex: Option<ReindexProgress>,
+}
+
+/// Progress of a `reprocess --outdated` run, persisted in `indexer_status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReindexProgress {
+    /// Parser version the rows are re-derived with
+    pub parser_version: i32,
+    /// Outdated transactions when the run started
+    pub total: i64,
+    /// Transactions dealt with so far, failed ones included
+    pub done: i64,
+    /// Not re-derived: unreadable, missing from the archive or failed to index
+    pub failed: i64,
+    pub started_at: i64,
+    pub updated_at: i64,
+}
+
+/// Dead-letter entry for a transaction that failed to fetch, decode or persist
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedTransaction {
+    pub signature: String,
+    pub cluster: String,
+    pub program_id: String,
+    pub slot: i64,
+    pub stage: String,
+    pub error: String,
+    pub attempts: i32,
+    pub first_failed_at: i64,
+    pub last_failed_at: i64,
+    /// None once automatic retries are exhausted
+    pub next_retry_at: Option<i64>,
+}
+
+/// Archived transaction, as stored in `raw_transactions`
+#[derive(Debug, Clone)]
+pub struct RawTransaction {
+    pub signature: String,
+    pub slot: i64,
+    pub tx_index: Option<i32>,
+    pub encoding: String,
+    pub data: Vec<u8>,
+}
+
+/// Transaction whose rows were derived by an older parser version, with its archived
+/// copy when there is one
+#[derive(Debug, Clone)]
+pub struct OutdatedTransaction {
+    pub signature: String,
+    pub slot: i64,
+    pub raw: Option<RawTransaction>,
+}
+
+/// How `Database::apply_transaction` treats a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyMode {
+    /// Indexing in order: advances the checkpoint
+    Live,
+    /// Filling in a past window: leaves the checkpoint alone
+    Backfill,
+    /// Like `Backfill`, replacing the rows previously derived from the transaction
+    Rebuild,
+}
+
+/// Where an event sits inside its transaction; with the slot and transaction index it is
+/// the ordering key of the rows derived from the event
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EventPosition {
+    /// Top-level instruction that emitted the event
+    pub instruction_index: i32,
+    /// 0 for the top-level instruction itself, n for the n-th invocation (CPI) under it
+    pub inner_index: i32,
+    /// Position of the event among all OpenBook events of the transaction
+    pub event_seq: i32,
+}
+
+/// Everything derived from one transaction, written atomically by `Database::apply_transaction`
+#[derive(Debug, Clone, Default)]
+pub struct TransactionWrites {
+    pub signature: String,
+    /// Deployment the orders and trades are tagged with
+    pub cluster: String,
+    pub program_id: String,
+    pub slot: i64,
+    /// Position of the transaction inside its block, when the source knows it
+    pub tx_index: Option<i32>,
+    pub timestamp: i64,
+    /// `timestamp` was estimated from neighbouring slots, the block time being unknown
+    pub time_estimated: bool,
+    /// `PARSER_VERSION` of the logic that derived the rows
+    pub parser_version: i32,
+    pub events: Vec<EventWrite>,
+    pub orders: Vec<OrderWrite>,
+    pub trades: Vec<TradeWrite>,
+    /// Set when the transaction failed on-chain: it is recorded instead of its events,
+    /// orders and trades
+    pub failed_attempt: Option<FailedAttemptWrite>,
+    /// Compressed copy of the transaction for the archive
+    pub raw_transaction: Option<RawTransactionWrite>,
+}
+
+/// New `raw_transactions` row
+#[derive(Debug, Clone)]
+pub struct RawTransactionWrite {
+    pub tx_index: Option<i32>,
+    pub account_keys: Vec<String>,
+    pub data: Vec<u8>,
+}
+
+/// Raw `events` row
+#[derive(Debug, Clone)]
+pub struct EventWrite {
+    pub position: EventPosition,
+    pub event_type: String,
+    pub market_id: Option<String>,
+    pub user_address: Option<String>,
+}
+
+/// New `orders` row
+#[derive(Debug, Clone)]
+pub struct OrderWrite {
+    pub position: EventPosition,
+    pub market_id: String,
+    pub order_id: i64,
+    pub user_address: String,
+    pub side: String,
+    pub order_type: String,
+    pub price: i64,
+    pub quantity: i64,
+}
+
+/// New `trades` row
+#[derive(Debug, Clone)]
+pub struct TradeWrite {
+    pub position: EventPosition,
+    /// See `event_processor::trade_id`
+    pub trade_id: Option<i64>,
+    pub market_id: String,
+    pub maker_address: String,
+    pub taker_address: String,
+    pub side: String,
+    pub price: i64,
+    pub quantity: i64,
+}
+
+/// New `failed_attempts` row
+#[derive(Debug, Clone)]
+pub struct FailedAttemptWrite {
+    pub instruction_index: Option<i32>,
+    pub event_type: Option<String>,
+    pub market_id: Option<String>,
+    pub user_address: Option<String>,
+    pub error_code: Option<i64>,
+    pub error_name: Option<String>,
+    pub error: String,
 }
 
 // ============================================================================
@@ -61,6 +323,8 @@ pub struct TradingPair {
 #[serde(rename_all = "camelCase")]
 pub struct MarketResponse {
     pub symbol: String,
+    pub cluster: String,
+    pub program_id: String,
     pub base_asset: String,
     pub quote_asset: String,
     pub pool_id: String,
@@ -73,6 +337,8 @@ pub struct MarketResponse {
     pub bid_liquidity: String,
     pub ask_liquidity: String,
     pub total_liquidity_in_quote: String,
+    pub base_tvl: String,
+    pub quote_tvl: String,
     pub created_at: i64,
 }
 
@@ -99,6 +365,8 @@ pub struct OrderResponse {
     pub update_time: i64,
     pub is_working: bool,
     pub orig_quote_order_qty: String,
+    /// From a confirmed slot that has not finalized yet
+    pub provisional: bool,
 }
 
 /// Balance information
@@ -155,12 +423,151 @@ pub struct Ticker24hr {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TradeResponse {
-    pub id: String,
+    /// Null for live trades until the finalizer places them in their block
+    pub id: Option<i64>,
     pub price: String,
     pub qty: String,
     pub time: i64,
     pub is_buyer_maker: bool,
     pub is_best_match: bool,
+    /// From a confirmed slot that has not finalized yet
+    pub provisional: bool,
+    /// `time` is an estimate: the chain reported no block time for the slot
+    pub time_estimated: bool,
+}
+
+/// Event heap (crank backlog) state for a market
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventHeapResponse {
+    pub pool_id: String,
+    pub event_heap: String,
+    pub pending_events: i32,
+    pub fill_events: i32,
+    pub out_events: i32,
+    pub capacity: i32,
+    pub fullness: String,
+    pub oldest_event_time: Option<i64>,
+    pub oldest_event_age_secs: Option<i64>,
+    pub seq_num: String,
+    pub slot: i64,
+    pub time: i64,
+}
+
+/// On-chain balances of an OpenOrdersAccount (amounts as strings, u64/u128 safe)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnChainPosition {
+    pub bids_base_lots: String,
+    pub asks_base_lots: String,
+    pub bids_quote_lots: String,
+    pub base_free_native: String,
+    pub quote_free_native: String,
+    pub locked_maker_fees: String,
+    pub referrer_rebates_available: String,
+    pub penalty_heap_count: String,
+    pub maker_volume: String,
+    pub taker_volume: String,
+}
+
+/// Occupied order slot of an OpenOrdersAccount
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnChainOpenOrder {
+    pub slot: usize,
+    pub order_id: String,
+    pub client_order_id: String,
+    pub side: String,
+    pub order_tree: String,
+    pub price_lots: Option<String>,
+    pub locked_price: String,
+}
+
+/// One OpenOrdersAccount with its on-chain state and the indexer's view of the same market
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenOrdersAccountResponse {
+    pub address: String,
+    pub pool_id: String,
+    pub symbol: String,
+    pub name: String,
+    pub account_num: u32,
+    pub delegate: Option<String>,
+    pub position: OnChainPosition,
+    pub open_orders: Vec<OnChainOpenOrder>,
+    pub indexed_open_orders: Vec<OrderResponse>,
+}
+
+/// All positions for an owner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionsResponse {
+    pub owner: String,
+    pub open_orders_accounts: Vec<OpenOrdersAccountResponse>,
+    /// Indexed open orders in markets where the owner has no OpenOrdersAccount on-chain
+    pub orphaned_indexed_orders: Vec<OrderResponse>,
+    pub time: i64,
+}
+
+/// Oracle price point for a market
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OraclePriceResponse {
+    pub pool_id: String,
+    pub price: Option<String>,
+    pub price_lots: Option<String>,
+    pub is_valid: bool,
+    pub is_stale: bool,
+    pub oracle_a: String,
+    pub oracle_a_type: String,
+    pub oracle_a_price: String,
+    pub oracle_a_confidence: String,
+    pub oracle_b: Option<String>,
+    pub oracle_b_type: Option<String>,
+    pub oracle_b_price: Option<String>,
+    pub oracle_b_confidence: Option<String>,
+    pub slot: i64,
+    pub time: i64,
+}
+
+/// Vault-backed TVL point for a market
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TvlResponse {
+    pub pool_id: String,
+    pub base_vault: String,
+    pub quote_vault: String,
+    pub base_tvl: String,
+    pub quote_tvl: String,
+    pub base_deposit_total: String,
+    pub quote_deposit_total: String,
+    pub tvl_in_quote: Option<String>,
+    pub slot: i64,
+    pub time: i64,
+}
+
+/// On-chain failures of one market's transactions, next to its successful ones
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketFailureRate {
+    pub market_id: String,
+    pub symbol: Option<String>,
+    pub failed: i64,
+    /// Transactions that wrote orders or trades for the market
+    pub succeeded: i64,
+    pub failure_rate: f64,
+}
+
+/// How often one error made transactions fail
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorFailureCount {
+    pub error_code: Option<i64>,
+    /// OpenBook error name, or the raw error when it is not an OpenBook one
+    pub error_name: String,
+    pub failures: i64,
+    /// Share of all failed attempts
+    pub share: f64,
 }
 
 // ============================================================================
@@ -206,18 +613,26 @@ impl Market {
         latest_price: String,
         bid_liquidity: String,
         ask_liquidity: String,
+        vault_balance: Option<&VaultBalance>,
     ) -> MarketResponse {
         let now = chrono::Utc::now().timestamp_millis();
         let age = now - self.created_at;
 
-        // Calculate total liquidity in quote
-        let bid_liq = bid_liquidity.parse::<f64>().unwrap_or(0.0);
-        let ask_liq = ask_liquidity.parse::<f64>().unwrap_or(0.0);
-        let price = latest_price.parse::<f64>().unwrap_or(0.0);
-        let total_liquidity = ((bid_liq + ask_liq) * price).to_string();
+        // Prefer real TVL from the vaults; fall back to open order sums times latest price
+        let total_liquidity = match vault_balance.and_then(|v| v.tvl_in_quote) {
+            Some(tvl) => tvl.to_string(),
+            None => {
+                let bid_liq = bid_liquidity.parse::<f64>().unwrap_or(0.0);
+                let ask_liq = ask_liquidity.parse::<f64>().unwrap_or(0.0);
+                let price = latest_price.parse::<f64>().unwrap_or(0.0);
+                ((bid_liq + ask_liq) * price).to_string()
+            }
+        };
 
         MarketResponse {
             symbol: self.symbol.clone(),
+            cluster: self.cluster.clone(),
+            program_id: self.program_id.clone(),
             base_asset: self.base_asset(),
             quote_asset: self.quote_asset(),
             pool_id: self.id.clone(),
@@ -230,6 +645,12 @@ impl Market {
             bid_liquidity,
             ask_liquidity,
             total_liquidity_in_quote: total_liquidity,
+            base_tvl: vault_balance
+                .map(|v| v.base_amount.to_string())
+                .unwrap_or_else(|| "0".to_string()),
+            quote_tvl: vault_balance
+                .map(|v| v.quote_amount.to_string())
+                .unwrap_or_else(|| "0".to_string()),
             created_at: self.created_at,
         }
     }
@@ -274,6 +695,7 @@ impl Order {
             update_time: self.timestamp,
             is_working: self.status == "open",
             orig_quote_order_qty,
+            provisional: self.commitment != "finalized",
         }
     }
 }
@@ -287,12 +709,126 @@ impl Trade {
         let is_buyer_maker = self.side == "sell";
 
         TradeResponse {
-            id: self.id.clone(),
+            id: self.trade_id,
             price: self.price.to_string(),
             qty: self.quantity.to_string(),
             time: self.timestamp,
             is_buyer_maker,
             is_best_match: true, // All trades are best match by default
+            provisional: self.commitment != "finalized",
+            time_estimated: self.time_estimated,
+        }
+    }
+}
+
+impl EventHeapSnapshot {
+    /// Convert to frontend-compatible EventHeapResponse
+    pub fn to_event_heap_response(&self) -> EventHeapResponse {
+        EventHeapResponse {
+            pool_id: self.market_id.clone(),
+            event_heap: self.event_heap.clone(),
+            pending_events: self.pending_events,
+            fill_events: self.fill_events,
+            out_events: self.out_events,
+            capacity: crate::event_heap::MAX_NUM_EVENTS as i32,
+            fullness: format!("{:.4}", self.fullness),
+            // Event timestamps are unix seconds on-chain; API times are milliseconds
+            oldest_event_time: self.oldest_event_timestamp.map(|ts| ts * 1000),
+            oldest_event_age_secs: self.oldest_event_age_secs,
+            seq_num: self.seq_num.to_string(),
+            slot: self.slot,
+            time: self.timestamp,
+        }
+    }
+}
+
+impl crate::open_orders::OpenOrdersAccount {
+    /// Convert to frontend-compatible OpenOrdersAccountResponse
+    pub fn to_open_orders_account_response(
+        &self,
+        symbol: &str,
+        indexed_open_orders: Vec<OrderResponse>,
+    ) -> OpenOrdersAccountResponse {
+        let p = &self.position;
+
+        let open_orders = self
+            .open_orders
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| !o.is_free)
+            .map(|(slot, o)| OnChainOpenOrder {
+                slot,
+                order_id: o.id.to_string(),
+                client_order_id: o.client_id.to_string(),
+                side: o.side().to_string(),
+                order_tree: o.order_tree().to_string(),
+                price_lots: o.price_lots().map(|p| p.to_string()),
+                locked_price: o.locked_price.to_string(),
+            })
+            .collect();
+
+        OpenOrdersAccountResponse {
+            address: self.address.clone(),
+            pool_id: self.market.clone(),
+            symbol: symbol.to_string(),
+            name: self.name.clone(),
+            account_num: self.account_num,
+            delegate: self.delegate.clone(),
+            position: OnChainPosition {
+                bids_base_lots: p.bids_base_lots.to_string(),
+                asks_base_lots: p.asks_base_lots.to_string(),
+                bids_quote_lots: p.bids_quote_lots.to_string(),
+                base_free_native: p.base_free_native.to_string(),
+                quote_free_native: p.quote_free_native.to_string(),
+                locked_maker_fees: p.locked_maker_fees.to_string(),
+                referrer_rebates_available: p.referrer_rebates_available.to_string(),
+                penalty_heap_count: p.penalty_heap_count.to_string(),
+                maker_volume: p.maker_volume.to_string(),
+                taker_volume: p.taker_volume.to_string(),
+            },
+            open_orders,
+            indexed_open_orders,
+        }
+    }
+}
+
+impl OraclePrice {
+    /// Convert to frontend-compatible OraclePriceResponse
+    pub fn to_oracle_price_response(&self) -> OraclePriceResponse {
+        OraclePriceResponse {
+            pool_id: self.market_id.clone(),
+            price: self.price.map(|p| p.to_string()),
+            price_lots: self.price_lots.map(|p| p.to_string()),
+            is_valid: self.price.is_some(),
+            is_stale: self.is_stale,
+            oracle_a: self.oracle_a.clone(),
+            oracle_a_type: self.oracle_a_type.clone(),
+            oracle_a_price: self.oracle_a_price.to_string(),
+            oracle_a_confidence: self.oracle_a_deviation.to_string(),
+            oracle_b: self.oracle_b.clone(),
+            oracle_b_type: self.oracle_b_type.clone(),
+            oracle_b_price: self.oracle_b_price.map(|p| p.to_string()),
+            oracle_b_confidence: self.oracle_b_deviation.map(|d| d.to_string()),
+            slot: self.slot,
+            time: self.timestamp,
+        }
+    }
+}
+
+impl VaultBalance {
+    /// Convert to frontend-compatible TvlResponse
+    pub fn to_tvl_response(&self) -> TvlResponse {
+        TvlResponse {
+            pool_id: self.market_id.clone(),
+            base_vault: self.base_vault.clone(),
+            quote_vault: self.quote_vault.clone(),
+            base_tvl: self.base_amount.to_string(),
+            quote_tvl: self.quote_amount.to_string(),
+            base_deposit_total: self.base_deposit_total.to_string(),
+            quote_deposit_total: self.quote_deposit_total.to_string(),
+            tvl_in_quote: self.tvl_in_quote.map(|t| t.to_string()),
+            slot: self.slot,
+            time: self.timestamp,
         }
     }
 }
@@ -0,0 +1,223 @@
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::programs::{Deployment, MarketLayout};
+use crate::rpc_pool::RpcPool;
+use crate::types::{DeploymentFilter, VaultBalance};
+
+/// SPL token account: mint (32), owner (32), amount (u64)
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+
+/// `getMultipleAccounts` accepts at most 100 keys per request
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Vault addresses and deposit counters of a market
+#[derive(Debug, Clone)]
+pub struct MarketVaults {
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub base_deposit_total: u64,
+    pub quote_deposit_total: u64,
+    pub base_lot_size: i64,
+    pub quote_lot_size: i64,
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    Pubkey::try_from(&data[offset..offset + 32]).unwrap_or_default()
+}
+
+/// Parse the vault fields out of market account data of the given layout
+pub fn parse_market_vaults(
+    data: &[u8],
+    layout: MarketLayout,
+) -> Result<MarketVaults, Box<dyn std::error::Error>> {
+    let Some(state) = layout.offsets().state else {
+        return Err(format!("Vault fields of {} markets are unknown", layout.as_str()).into());
+    };
+
+    if data.len() < state.quote_deposit_total + 8 {
+        return Err("Account data too small to be a market".into());
+    }
+
+    Ok(MarketVaults {
+        base_vault: read_pubkey(data, state.base_vault),
+        quote_vault: read_pubkey(data, state.quote_vault),
+        base_deposit_total: read_u64(data, state.base_deposit_total),
+        quote_deposit_total: read_u64(data, state.quote_deposit_total),
+        base_lot_size: read_i64(data, state.base_lot_size),
+        quote_lot_size: read_i64(data, state.quote_lot_size),
+    })
+}
+
+/// Token amount held by an SPL token account
+pub fn parse_token_amount(data: &[u8]) -> Option<u64> {
+    if data.len() < TOKEN_ACCOUNT_AMOUNT_OFFSET + 8 {
+        return None;
+    }
+    Some(read_u64(data, TOKEN_ACCOUNT_AMOUNT_OFFSET))
+}
+
+/// Value base native units in quote native units at a price expressed in lots; None
+/// when the value does not fit an i64
+pub fn base_native_in_quote(
+    base_native: u64,
+    price_lots: i64,
+    vaults: &MarketVaults,
+) -> Option<i64> {
+    if vaults.base_lot_size == 0 {
+        return Some(0);
+    }
+    let quote = (base_native as i128 * price_lots as i128)
+        .checked_mul(vaults.quote_lot_size as i128)?
+        / vaults.base_lot_size as i128;
+    i64::try_from(quote.max(0)).ok()
+}
+
+/// Value of both vaults in quote native units, the base side at `price_lots`; None when
+/// it does not fit an i64
+pub fn tvl_in_quote(
+    base_native: u64,
+    quote_native: u64,
+    price_lots: i64,
+    vaults: &MarketVaults,
+) -> Option<i64> {
+    base_native_in_quote(base_native, price_lots, vaults)?
+        .checked_add(i64::try_from(quote_native).ok()?)
+}
+
+/// Read both vaults of every market of `deployments` and store one balance row per market
+pub async fn record_vault_balances(
+    rpc_pool: &RpcPool,
+    db: &crate::Database,
+    deployments: &[Deployment],
+) -> Result<Vec<VaultBalance>, Box<dyn std::error::Error>> {
+    let mut market_vaults = Vec::new();
+    for deployment in deployments {
+        let markets = db
+            .get_markets(&DeploymentFilter::deployment(deployment), 1000)
+            .await?;
+        let market_keys: Vec<Pubkey> = markets
+            .iter()
+            .filter_map(|m| Pubkey::from_str(&m.id).ok())
+            .collect();
+
+        for chunk in market_keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let accounts = rpc_pool
+                .call(|rpc| async move { rpc.get_multiple_accounts(chunk).await })
+                .await?;
+            for (key, account) in chunk.iter().zip(accounts) {
+                match account.map(|a| parse_market_vaults(&a.data, deployment.layout)) {
+                    Some(Ok(vaults)) => market_vaults.push((*key, vaults)),
+                    Some(Err(e)) => warn!("⚠️  Failed to parse vaults of market {}: {}", key, e),
+                    None => warn!("⚠️  Market account {} not found", key),
+                }
+            }
+        }
+    }
+
+    let mut balances = Vec::new();
+
+    // Two vaults per market, so half as many markets per request
+    for chunk in market_vaults.chunks(MAX_MULTIPLE_ACCOUNTS / 2) {
+        let keys: Vec<Pubkey> = chunk
+            .iter()
+            .flat_map(|(_, v)| [v.base_vault, v.quote_vault])
+            .collect();
+        let keys = &keys;
+        let response = rpc_pool
+            .call(|rpc| async move {
+                rpc.get_multiple_accounts_with_commitment(keys, CommitmentConfig::confirmed())
+                    .await
+            })
+            .await?;
+        let slot = response.context.slot as i64;
+        let now_ms = chrono::Utc::now().timestamp_millis();
+
+        for ((market, vaults), pair) in chunk.iter().zip(response.value.chunks(2)) {
+            let base_amount = pair[0].as_ref().and_then(|a| parse_token_amount(&a.data));
+            let quote_amount = pair[1].as_ref().and_then(|a| parse_token_amount(&a.data));
+
+            let (Some(base_amount), Some(quote_amount)) = (base_amount, quote_amount) else {
+                warn!("⚠️  Vault accounts of market {} not readable", market);
+                continue;
+            };
+
+            let market_id = market.to_string();
+
+            // Value the base side at the mid of the indexed book (or whichever side exists)
+            let best_bid = db.get_best_bid(&market_id).await?;
+            let best_ask = db.get_best_ask(&market_id).await?;
+            let price_lots = match (best_bid, best_ask) {
+                (Some(bid), Some(ask)) => Some((bid + ask) / 2),
+                (bid, ask) => ask.or(bid),
+            };
+            // Stored as NULL when it overflows, like when there is no price
+            let tvl_in_quote =
+                price_lots.and_then(|p| tvl_in_quote(base_amount, quote_amount, p, vaults));
+
+            let balance = VaultBalance {
+                market_id,
+                base_vault: vaults.base_vault.to_string(),
+                quote_vault: vaults.quote_vault.to_string(),
+                base_amount: base_amount as i64,
+                quote_amount: quote_amount as i64,
+                base_deposit_total: vaults.base_deposit_total as i64,
+                quote_deposit_total: vaults.quote_deposit_total as i64,
+                price_lots,
+                tvl_in_quote,
+                slot,
+                timestamp: now_ms,
+            };
+
+            db.insert_vault_balance(&balance).await?;
+            balances.push(balance);
+        }
+    }
+
+    Ok(balances)
+}
+
+/// Periodically record base/quote vault balances (real TVL) of every market
+pub async fn monitor_vault_balances(
+    rpc_pool: &RpcPool,
+    db: &crate::Database,
+    deployments: &[Deployment],
+    interval: Duration,
+) {
+    info!(
+        "🏦 Vault balance monitor started (interval: {}s)",
+        interval.as_secs()
+    );
+
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+
+        let result = record_vault_balances(rpc_pool, db, deployments)
+            .await
+            .map_err(|e| e.to_string());
+
+        match result {
+            Ok(balances) => {
+                info!(
+                    "  🏦 Recorded vault balances for {} markets",
+                    balances.len()
+                );
+            }
+            Err(e) => {
+                warn!("⚠️  Vault balance snapshot failed: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use solana_openbook_indexer::archive::{
+    compress_transaction, decompress_transaction, ARCHIVE_ENCODING,
+};
+use solana_openbook_indexer::ingest::replay::parse_line;
+use solana_openbook_indexer::ingest::IngestedTransaction;
+use solana_openbook_indexer::RawTransaction;
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/replay.jsonl");
+
+fn fixture_lines() -> Vec<String> {
+    std::fs::read_to_string(FIXTURE)
+        .unwrap()
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+fn archived(line: &str) -> (IngestedTransaction, RawTransaction) {
+    let tx = parse_line(line).unwrap();
+    let raw = RawTransaction {
+        signature: tx.signature.clone(),
+        slot: tx.slot as i64,
+        tx_index: tx.tx_index.map(|index| index as i32),
+        encoding: ARCHIVE_ENCODING.to_string(),
+        data: compress_transaction(&tx).unwrap(),
+    };
+    (tx, raw)
+}
+
+#[test]
+fn archived_transactions_decompress_identically() {
+    for line in fixture_lines() {
+        let (tx, raw) = archived(&line);
+        assert!(raw.data.len() < line.len());
+
+        let restored = decompress_transaction(&raw).unwrap();
+        assert_eq!(restored.signature, tx.signature);
+        assert_eq!(restored.slot, tx.slot);
+        assert_eq!(restored.tx_index, tx.tx_index);
+        assert_eq!(restored.transaction, tx.transaction);
+    }
+}
+
+#[test]
+fn rejects_unknown_encodings_and_corrupt_data() {
+    let (_, raw) = archived(&fixture_lines()[0]);
+
+    let other = RawTransaction {
+        encoding: "borsh".to_string(),
+        ..raw.clone()
+    };
+    assert!(decompress_transaction(&other).is_err());
+
+    let corrupt = RawTransaction {
+        data: raw.data[..raw.data.len() / 2].to_vec(),
+        ..raw
+    };
+    assert!(decompress_transaction(&corrupt).is_err());
+}
@@ -0,0 +1,95 @@
+use solana_openbook_indexer::backfill::BackfillOptions;
+use solana_openbook_indexer::programs::{Deployment, MarketLayout, OPENBOOK_V2_PROGRAM_ID};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+fn options() -> BackfillOptions {
+    let program_id = Pubkey::from_str(OPENBOOK_V2_PROGRAM_ID).unwrap();
+    BackfillOptions {
+        deployment: Deployment {
+            cluster: "mainnet-beta".to_string(),
+            program_id,
+            layout: MarketLayout::OpenBookV2,
+        },
+        address: program_id,
+        from_slot: None,
+        to_slot: None,
+        before: None,
+        until: None,
+        dry_run: false,
+        rebuild: false,
+        concurrency: 4,
+    }
+}
+
+#[test]
+fn a_backfill_needs_a_range_or_a_market() {
+    let unbounded = options();
+    assert!(unbounded.validate().unwrap_err().contains("--reindex"));
+
+    // An end alone still walks back through the full history
+    let to_slot_only = BackfillOptions {
+        to_slot: Some(200),
+        ..options()
+    };
+    assert!(to_slot_only.validate().is_err());
+
+    let market = BackfillOptions {
+        address: Pubkey::new_unique(),
+        ..options()
+    };
+    assert_eq!(market.validate(), Ok(()));
+
+    for bounded in [
+        BackfillOptions {
+            from_slot: Some(100),
+            ..options()
+        },
+        BackfillOptions {
+            before: Some("5h6xBEau".to_string()),
+            ..options()
+        },
+        BackfillOptions {
+            until: Some("5h6xBEau".to_string()),
+            ..options()
+        },
+    ] {
+        assert_eq!(bounded.validate(), Ok(()));
+    }
+}
+
+#[test]
+fn inverted_slot_ranges_are_refused() {
+    let inverted = BackfillOptions {
+        from_slot: Some(300),
+        to_slot: Some(200),
+        ..options()
+    };
+    assert_eq!(
+        inverted.validate(),
+        Err("--from-slot 300 is after --to-slot 200".to_string())
+    );
+
+    let single_slot = BackfillOptions {
+        from_slot: Some(200),
+        to_slot: Some(200),
+        ..options()
+    };
+    assert_eq!(single_slot.validate(), Ok(()));
+}
+
+#[test]
+fn slot_ranges_are_inclusive() {
+    let range = BackfillOptions {
+        from_slot: Some(100),
+        to_slot: Some(200),
+        ..options()
+    };
+    assert!(!range.contains_slot(99));
+    assert!(range.contains_slot(100));
+    assert!(range.contains_slot(200));
+    assert!(!range.contains_slot(201));
+
+    let open = options();
+    assert!(open.contains_slot(0) && open.contains_slot(u64::MAX));
+}
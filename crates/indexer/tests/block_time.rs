@@ -0,0 +1,77 @@
+use solana_openbook_indexer::ingest::block_time::{
+    estimate_block_time, interpolate_block_time, BlockTimeCache, SLOT_DURATION_MS,
+};
+use solana_openbook_indexer::ingest::replay::parse_line;
+use solana_openbook_indexer::ingest::IngestedTransaction;
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/replay.jsonl");
+
+/// A fixture transaction moved to `slot`, with the given block time
+fn transaction_at(slot: u64, block_time: Option<i64>) -> IngestedTransaction {
+    let line = std::fs::read_to_string(FIXTURE)
+        .unwrap()
+        .lines()
+        .find(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .unwrap()
+        .to_string();
+    let mut tx = parse_line(&line).unwrap();
+    tx.slot = slot;
+    tx.transaction.slot = slot;
+    tx.transaction.block_time = block_time;
+    tx
+}
+
+#[test]
+fn interpolates_between_and_extrapolates_from_neighbours() {
+    let before = Some((1_000, 1_700_000_000));
+    let after = Some((1_100, 1_700_000_050));
+
+    assert_eq!(
+        interpolate_block_time(1_040, before, after),
+        Some(1_700_000_020)
+    );
+    assert_eq!(
+        interpolate_block_time(1_010, before, None),
+        Some(1_700_000_000 + 10 * SLOT_DURATION_MS / 1000)
+    );
+    assert_eq!(
+        interpolate_block_time(1_090, None, after),
+        Some(1_700_000_050 - 10 * SLOT_DURATION_MS / 1000)
+    );
+    assert_eq!(interpolate_block_time(1_040, None, None), None);
+}
+
+#[test]
+fn missing_block_times_are_estimated_and_flagged() {
+    let block_times = BlockTimeCache::default();
+    let mut first = transaction_at(900_000_000, Some(1_700_000_000));
+    estimate_block_time(&block_times, &mut first);
+    assert!(!first.time_estimated());
+    assert_eq!(first.timestamp_ms(), 1_700_000_000_000);
+
+    let mut last = transaction_at(900_000_100, Some(1_700_000_040));
+    estimate_block_time(&block_times, &mut last);
+
+    let mut missing = transaction_at(900_000_050, None);
+    estimate_block_time(&block_times, &mut missing);
+    assert!(missing.time_estimated());
+    assert_eq!(missing.timestamp_ms(), 1_700_000_020_000);
+
+    // Another transaction of a slot with a known time gets that time
+    let mut same_slot = transaction_at(900_000_100, None);
+    estimate_block_time(&block_times, &mut same_slot);
+    assert!(!same_slot.time_estimated());
+    assert_eq!(same_slot.block_time(), Some(1_700_000_040));
+}
+
+#[test]
+fn caches_do_not_share_block_times() {
+    let known = BlockTimeCache::default();
+    let mut dated = transaction_at(900_000_000, Some(1_700_000_000));
+    estimate_block_time(&known, &mut dated);
+
+    let mut undated = transaction_at(900_000_000, None);
+    estimate_block_time(&BlockTimeCache::default(), &mut undated);
+    assert_eq!(undated.block_time(), None);
+    assert_eq!(undated.estimated_block_time, None);
+}
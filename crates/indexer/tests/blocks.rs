@@ -0,0 +1,89 @@
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::rpc_custom_error::JSON_RPC_SERVER_ERROR_SLOT_SKIPPED;
+use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+use solana_openbook_indexer::ingest::blocks::{block_transactions, is_skipped_slot, round_slots};
+use solana_openbook_indexer::ingest::replay::parse_line;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{EncodedTransactionWithStatusMeta, UiConfirmedBlock};
+use std::str::FromStr;
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/replay.jsonl");
+const PROGRAM: &str = "opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb";
+
+/// The fixture transactions as block entries; those at `other` mention another program
+fn entries(other: &[usize]) -> Vec<EncodedTransactionWithStatusMeta> {
+    std::fs::read_to_string(FIXTURE)
+        .unwrap()
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .enumerate()
+        .map(|(index, line)| {
+            let line = if other.contains(&index) {
+                line.replace(PROGRAM, &Pubkey::new_unique().to_string())
+            } else {
+                line.to_string()
+            };
+            parse_line(&line).unwrap().transaction.transaction
+        })
+        .collect()
+}
+
+fn block(transactions: Vec<EncodedTransactionWithStatusMeta>) -> UiConfirmedBlock {
+    UiConfirmedBlock {
+        previous_blockhash: String::new(),
+        blockhash: String::new(),
+        parent_slot: 99,
+        transactions: Some(transactions),
+        signatures: None,
+        rewards: None,
+        block_time: Some(1_700_000_000),
+        block_height: None,
+    }
+}
+
+#[test]
+fn rounds_walk_up_to_the_tip() {
+    assert_eq!(round_slots(100, 1_000, 50), Some((100, 149)));
+    assert_eq!(round_slots(990, 1_000, 50), Some((990, 1_000)));
+    assert_eq!(round_slots(1_000, 1_000, 50), Some((1_000, 1_000)));
+    assert_eq!(round_slots(1_001, 1_000, 50), None);
+    // A zero batch still makes progress
+    assert_eq!(round_slots(100, 1_000, 0), Some((100, 100)));
+}
+
+#[test]
+fn only_program_transactions_are_taken_in_block_order() {
+    let program = Pubkey::from_str(PROGRAM).unwrap();
+    let mixed = entries(&[1]);
+    let count = mixed.len();
+
+    let transactions = block_transactions(100, block(mixed), &[program]);
+
+    assert_eq!(transactions.len(), count - 1);
+    let indexes: Vec<Option<u32>> = transactions.iter().map(|tx| tx.tx_index).collect();
+    let expected: Vec<Option<u32>> = (0..count as u32)
+        .filter(|index| *index != 1)
+        .map(Some)
+        .collect();
+    assert_eq!(indexes, expected);
+    assert!(transactions
+        .iter()
+        .all(|tx| tx.slot == 100 && tx.block_time() == Some(1_700_000_000)));
+
+    assert!(block_transactions(100, block(Vec::new()), &[program]).is_empty());
+    assert!(block_transactions(100, block(entries(&[])), &[Pubkey::new_unique()]).is_empty());
+}
+
+#[test]
+fn skipped_slots_are_recognised() {
+    let skipped: ClientError = ClientErrorKind::RpcError(RpcError::RpcResponseError {
+        code: JSON_RPC_SERVER_ERROR_SLOT_SKIPPED,
+        message: "Slot 100 was skipped".to_string(),
+        data: RpcResponseErrorData::Empty,
+    })
+    .into();
+    let other: ClientError = ClientErrorKind::Custom("timed out".to_string()).into();
+
+    assert!(is_skipped_slot(&skipped));
+    assert!(!is_skipped_slot(&other));
+}
@@ -0,0 +1,105 @@
+mod database;
+
+use database::TestDatabase;
+use solana_openbook_indexer::{ApplyMode, TransactionWrites};
+
+const CLUSTER: &str = "localnet";
+const PROGRAM: &str = "opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb";
+
+fn transaction(signature: &str, slot: i64) -> TransactionWrites {
+    TransactionWrites {
+        signature: signature.to_string(),
+        cluster: CLUSTER.to_string(),
+        program_id: PROGRAM.to_string(),
+        slot,
+        timestamp: slot * 400,
+        ..Default::default()
+    }
+}
+
+async fn checkpoint(test: &TestDatabase) -> Option<(i64, String)> {
+    test.db
+        .get_checkpoint(CLUSTER, PROGRAM)
+        .await
+        .unwrap()
+        .map(|cp| (cp.slot, cp.signature))
+}
+
+#[tokio::test]
+async fn live_transactions_advance_the_checkpoint() {
+    let Some(test) = TestDatabase::create().await else {
+        return;
+    };
+    assert_eq!(checkpoint(&test).await, None);
+
+    let db = &test.db;
+    db.apply_transaction(&transaction("a", 10), ApplyMode::Live)
+        .await
+        .unwrap();
+    db.apply_transaction(&transaction("b", 12), ApplyMode::Live)
+        .await
+        .unwrap();
+    assert_eq!(checkpoint(&test).await, Some((12, "b".to_string())));
+
+    // Backfilled history is behind the live position and leaves it alone
+    db.apply_transaction(&transaction("c", 5), ApplyMode::Backfill)
+        .await
+        .unwrap();
+    db.apply_transaction(&transaction("d", 20), ApplyMode::Backfill)
+        .await
+        .unwrap();
+    assert_eq!(checkpoint(&test).await, Some((12, "b".to_string())));
+    let processed: Vec<String> = test
+        .column("SELECT signature FROM processed_transactions ORDER BY slot")
+        .await;
+    assert_eq!(processed, vec!["c", "a", "b", "d"]);
+
+    test.drop().await;
+}
+
+#[tokio::test]
+async fn the_checkpoint_never_moves_back() {
+    let Some(test) = TestDatabase::create().await else {
+        return;
+    };
+    let db = &test.db;
+
+    db.save_checkpoint(CLUSTER, PROGRAM, 12, "b", 4800, 3)
+        .await
+        .unwrap();
+    db.save_checkpoint(CLUSTER, PROGRAM, 10, "a", 4000, 2)
+        .await
+        .unwrap();
+    assert_eq!(checkpoint(&test).await, Some((12, "b".to_string())));
+
+    db.save_checkpoint(CLUSTER, PROGRAM, 12, "c", 4800, 1)
+        .await
+        .unwrap();
+    let saved = db.get_checkpoint(CLUSTER, PROGRAM).await.unwrap().unwrap();
+    assert_eq!((saved.slot, saved.signature.as_str()), (12, "c"));
+    assert_eq!(saved.total_events_processed, 4);
+
+    test.drop().await;
+}
+
+#[tokio::test]
+async fn a_reset_checkpoint_resumes_from_the_full_history() {
+    let Some(test) = TestDatabase::create().await else {
+        return;
+    };
+    let db = &test.db;
+    db.apply_transaction(&transaction("a", 10), ApplyMode::Live)
+        .await
+        .unwrap();
+
+    db.reset_checkpoint(CLUSTER, PROGRAM).await.unwrap();
+    assert_eq!(checkpoint(&test).await, None);
+
+    // Re-indexing starts over: an older transaction becomes the checkpoint again
+    db.save_checkpoint(CLUSTER, PROGRAM, 5, "z", 2000, 0)
+        .await
+        .unwrap();
+    assert_eq!(checkpoint(&test).await, Some((5, "z".to_string())));
+
+    test.drop().await;
+}
@@ -0,0 +1,6 @@
+//! Helpers shared by the integration tests
+
+/// Write `bytes` into an account fixture at `offset`
+pub fn put(data: &mut [u8], offset: usize, bytes: &[u8]) {
+    data[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
@@ -0,0 +1,134 @@
+use solana_openbook_indexer::config::{Config, ListenerArgs};
+use solana_openbook_indexer::ingest::SourceKind;
+use solana_openbook_indexer::programs::{MarketLayout, OPENBOOK_V2_PROGRAM_ID};
+
+#[test]
+fn example_config_holds_the_defaults() {
+    let example = Config::from_toml(include_str!("../indexer.example.toml")).unwrap();
+    example.validate().unwrap();
+
+    let defaults = Config::default();
+    assert_eq!(
+        format!("{:?}", example),
+        format!("{:?}", defaults),
+        "indexer.example.toml is out of date"
+    );
+    assert_eq!(defaults.programs[0].id, OPENBOOK_V2_PROGRAM_ID);
+}
+
+#[test]
+fn partial_files_keep_the_other_defaults() {
+    let config = Config::from_toml(
+        r#"
+        cluster = "devnet"
+
+        [[programs]]
+        id = "opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb"
+
+        [[programs]]
+        id = "11111111111111111111111111111111"
+        layout = "v2"
+
+        [ingest]
+        source = "blocks"
+        "#,
+    )
+    .unwrap();
+    config.validate().unwrap();
+
+    assert_eq!(config.ingest.source_kind(), Ok(SourceKind::Blocks));
+    assert_eq!(config.ingest.block_batch_slots, 64);
+    assert_eq!(config.api.port, 42070);
+
+    let deployments = config.deployments().unwrap();
+    assert_eq!(deployments.len(), 2);
+    assert!(deployments.iter().all(|d| d.cluster == "devnet"));
+    assert!(deployments
+        .iter()
+        .all(|d| d.layout == MarketLayout::OpenBookV2));
+}
+
+#[test]
+fn unknown_keys_are_rejected() {
+    assert!(Config::from_toml("[rpc]\nurl = \"http://localhost:8899\"\n").is_err());
+    assert!(Config::from_toml("[api]\nport = \"not a port\"\n").is_err());
+}
+
+#[test]
+fn validation_reports_every_problem() {
+    let config = Config::from_toml(
+        r#"
+        programs = []
+
+        [rpc]
+        urls = ["localhost:8899"]
+        commitment = "processed"
+
+        [ingest]
+        source = "enhanced_ws"
+
+        [backfill]
+        rps = 100.0
+        max_rps = 10.0
+        "#,
+    )
+    .unwrap();
+
+    let error = config.validate().unwrap_err();
+    for problem in [
+        "no OpenBook program configured",
+        "rpc url 'localhost:8899' is not http(s)",
+        "unsupported commitment 'processed'",
+        "the enhanced_ws source needs ingest.enhanced_ws_url",
+        "backfill needs 0 < rps <= max_rps",
+    ] {
+        assert!(error.contains(problem), "{} misses: {}", error, problem);
+    }
+}
+
+#[test]
+fn the_geyser_source_needs_an_endpoint() {
+    let mut config = Config::from_toml("[ingest]\nsource = \"geyser\"\n").unwrap();
+    assert_eq!(config.ingest.source_kind(), Ok(SourceKind::Geyser));
+    let error = config.validate().unwrap_err();
+    assert!(error.contains("the geyser source needs ingest.geyser_url"));
+
+    config.ingest.geyser_url = Some("https://grpc.example.com".to_string());
+    config.validate().unwrap();
+}
+
+#[test]
+fn listener_arguments_are_parsed_strictly() {
+    let args = |line: &str| ListenerArgs::parse(line.split_whitespace().map(str::to_string));
+
+    assert_eq!(args(""), Ok(ListenerArgs::default()));
+    assert_eq!(
+        args("--reindex --record capture.jsonl --config indexer.toml"),
+        Ok(ListenerArgs {
+            config: Some("indexer.toml".to_string()),
+            reindex: true,
+            record: Some("capture.jsonl".to_string()),
+        })
+    );
+    assert_eq!(args("--record"), Err("--record needs a value".to_string()));
+    assert_eq!(
+        args("--reindx"),
+        Err("unexpected argument: --reindx".to_string())
+    );
+}
+
+#[test]
+fn a_replay_is_not_recorded_over_itself() {
+    let config = Config::from_toml(
+        r#"
+        [ingest]
+        source = "replay"
+        replay_file = "capture.jsonl"
+        record_file = "capture.jsonl"
+        "#,
+    )
+    .unwrap();
+
+    let error = config.validate().unwrap_err();
+    assert!(error.contains("ingest.record_file cannot be the replay file"));
+}
@@ -0,0 +1,69 @@
+//! Throwaway databases for the tests of the SQL layer
+//!
+//! Tests that need one are skipped unless `TEST_DATABASE_URL` points at a PostgreSQL
+//! database whose user may create databases, e.g.
+//! `postgresql://postgres@localhost:15432/postgres`.
+
+use solana_openbook_indexer::Database;
+use std::sync::atomic::{AtomicU32, Ordering};
+use tokio_postgres::NoTls;
+
+/// A database created from `schema.sql` for one test, dropped by `drop`
+pub struct TestDatabase {
+    pub db: Database,
+    admin_url: String,
+    name: String,
+}
+
+impl TestDatabase {
+    /// None (and the test skipped) without `TEST_DATABASE_URL`
+    pub async fn create() -> Option<Self> {
+        static NEXT: AtomicU32 = AtomicU32::new(0);
+
+        let Ok(admin_url) = std::env::var("TEST_DATABASE_URL") else {
+            eprintln!("TEST_DATABASE_URL is not set, skipping");
+            return None;
+        };
+        let name = format!(
+            "openbook_indexer_test_{}_{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        );
+        execute(&admin_url, &format!("CREATE DATABASE {}", name)).await;
+
+        let (server, _) = admin_url.rsplit_once('/').expect("database URL");
+        let url = format!("{}/{}", server, name);
+        execute(&url, include_str!("../../schema.sql")).await;
+
+        Some(Self {
+            db: Database::new(&url, 2).await.unwrap(),
+            admin_url,
+            name,
+        })
+    }
+
+    /// First column of the rows `sql` returns
+    pub async fn column<T>(&self, sql: &str) -> Vec<T>
+    where
+        T: for<'a> tokio_postgres::types::FromSql<'a>,
+    {
+        let client = self.db.pool().get().await.unwrap();
+        let rows = client.query(sql, &[]).await.unwrap();
+        rows.iter().map(|row| row.get(0)).collect()
+    }
+
+    pub async fn drop(self) {
+        self.db.pool().close();
+        execute(
+            &self.admin_url,
+            &format!("DROP DATABASE {} WITH (FORCE)", self.name),
+        )
+        .await;
+    }
+}
+
+async fn execute(url: &str, sql: &str) {
+    let (client, connection) = tokio_postgres::connect(url, NoTls).await.unwrap();
+    tokio::spawn(connection);
+    client.batch_execute(sql).await.unwrap();
+}
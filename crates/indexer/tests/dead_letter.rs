@@ -0,0 +1,11 @@
+use solana_openbook_indexer::dead_letter::retry_delay;
+use std::time::Duration;
+
+#[test]
+fn retries_back_off_exponentially_then_stop() {
+    assert_eq!(retry_delay(1), Some(Duration::from_secs(30)));
+    assert_eq!(retry_delay(2), Some(Duration::from_secs(60)));
+    assert_eq!(retry_delay(4), Some(Duration::from_secs(240)));
+    assert_eq!(retry_delay(9), Some(Duration::from_secs(3600)));
+    assert_eq!(retry_delay(10), None);
+}
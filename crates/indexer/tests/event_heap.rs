@@ -0,0 +1,158 @@
+mod common;
+
+use solana_openbook_indexer::event_heap::{
+    parse_event_heap, snapshot_event_heap, AnyEvent, MAX_NUM_EVENTS,
+};
+use solana_sdk::pubkey::Pubkey;
+
+use common::put;
+
+const DISCRIMINATOR: [u8; 8] = [119, 59, 61, 19, 165, 84, 57, 175];
+const NODES_OFFSET: usize = 8 + 16;
+const NODE_SIZE: usize = 152;
+const NO_NODE: u16 = u16::MAX;
+
+/// An empty EventHeap account with the given header
+fn heap_account(used_head: u16, count: u16, seq_num: u64) -> Vec<u8> {
+    let mut data = vec![0u8; NODES_OFFSET + MAX_NUM_EVENTS * NODE_SIZE + 64];
+    put(&mut data, 0, &DISCRIMINATOR);
+    put(&mut data, 10, &used_head.to_le_bytes());
+    put(&mut data, 12, &count.to_le_bytes());
+    put(&mut data, 16, &seq_num.to_le_bytes());
+    data
+}
+
+/// Write node `index` with its `next` link and a 144-byte event
+fn put_node(data: &mut [u8], index: usize, next: u16, event: &[u8; 144]) {
+    let offset = NODES_OFFSET + index * NODE_SIZE;
+    put(data, offset, &next.to_le_bytes());
+    put(data, offset + 8, event);
+}
+
+fn fill_event(
+    maker: &Pubkey,
+    taker: &Pubkey,
+    timestamp: u64,
+    price: i64,
+    quantity: i64,
+) -> [u8; 144] {
+    let mut event = [0u8; 144];
+    event[0] = 0;
+    event[1] = 1; // taker sells
+    event[2] = 1; // maker out
+    event[3] = 7; // maker slot
+    put(&mut event, 8, &timestamp.to_le_bytes());
+    put(&mut event, 16, &41u64.to_le_bytes());
+    put(&mut event, 24, maker.as_ref());
+    put(&mut event, 64, taker.as_ref());
+    put(&mut event, 96, &99u64.to_le_bytes());
+    put(&mut event, 104, &price.to_le_bytes());
+    put(&mut event, 120, &quantity.to_le_bytes());
+    event
+}
+
+fn out_event(owner: &Pubkey, timestamp: u64, quantity: i64) -> [u8; 144] {
+    let mut event = [0u8; 144];
+    event[0] = 1;
+    event[1] = 0;
+    event[2] = 3;
+    put(&mut event, 8, &timestamp.to_le_bytes());
+    put(&mut event, 24, owner.as_ref());
+    put(&mut event, 56, &quantity.to_le_bytes());
+    event
+}
+
+#[test]
+fn walks_the_used_list_in_consume_order() {
+    let maker = Pubkey::new_unique();
+    let taker = Pubkey::new_unique();
+
+    // Used list 5 -> 2 -> 9; node 0 holds a consumed fill that must not be reported
+    let mut data = heap_account(5, 3, 1234);
+    put_node(&mut data, 0, NO_NODE, &fill_event(&maker, &taker, 1, 1, 1));
+    put_node(
+        &mut data,
+        5,
+        2,
+        &fill_event(&maker, &taker, 1_700_000_000, 5_150, 20),
+    );
+    put_node(&mut data, 2, 9, &out_event(&maker, 1_700_000_010, 4));
+    put_node(
+        &mut data,
+        9,
+        NO_NODE,
+        &fill_event(&maker, &taker, 1_700_000_020, 5_160, 3),
+    );
+
+    let heap = parse_event_heap(&data).unwrap();
+    assert_eq!(heap.header.used_head, 5);
+    assert_eq!(heap.header.count, 3);
+    assert_eq!(heap.header.seq_num, 1234);
+
+    let pending = heap.pending_events();
+    assert_eq!(pending.len(), 3);
+    match pending[0] {
+        AnyEvent::Fill(fill) => {
+            assert_eq!(fill.taker_side, 1);
+            assert!(fill.maker_out);
+            assert_eq!(fill.maker_slot, 7);
+            assert_eq!(fill.timestamp, 1_700_000_000);
+            assert_eq!(fill.seq_num, 41);
+            assert_eq!(fill.maker, maker.to_string());
+            assert_eq!(fill.taker, taker.to_string());
+            assert_eq!(fill.taker_client_order_id, 99);
+            assert_eq!(fill.price, 5_150);
+            assert_eq!(fill.quantity, 20);
+        }
+        other => panic!("expected a fill, got {:?}", other),
+    }
+    match pending[1] {
+        AnyEvent::Out(out) => {
+            assert_eq!(out.owner_slot, 3);
+            assert_eq!(out.owner, maker.to_string());
+            assert_eq!(out.quantity, 4);
+        }
+        other => panic!("expected an out event, got {:?}", other),
+    }
+    assert!(matches!(pending[2], AnyEvent::Fill(fill) if fill.price == 5_160));
+
+    let snapshot = snapshot_event_heap("market", "heap", &heap, 250_000_000, 1_700_000_100_000);
+    assert_eq!(snapshot.pending_events, 3);
+    assert_eq!(snapshot.fill_events, 2);
+    assert_eq!(snapshot.out_events, 1);
+    assert_eq!(snapshot.oldest_event_timestamp, Some(1_700_000_000));
+    assert_eq!(snapshot.oldest_event_age_secs, Some(100));
+    assert_eq!(snapshot.fullness, 3.0 / MAX_NUM_EVENTS as f64);
+}
+
+#[test]
+fn a_corrupt_used_list_stops_at_count() {
+    let owner = Pubkey::new_unique();
+
+    // Node 4 links to itself; the walk must still end after `count` events
+    let mut data = heap_account(4, 2, 0);
+    put_node(&mut data, 4, 4, &out_event(&owner, 1_700_000_000, 1));
+
+    let heap = parse_event_heap(&data).unwrap();
+    assert_eq!(heap.pending_events().len(), 2);
+}
+
+#[test]
+fn an_empty_heap_has_no_oldest_event() {
+    let heap = parse_event_heap(&heap_account(NO_NODE, 0, 0)).unwrap();
+    assert!(heap.pending_events().is_empty());
+    assert!(heap.oldest_pending().is_none());
+
+    let snapshot = snapshot_event_heap("market", "heap", &heap, 1, 1_700_000_000_000);
+    assert_eq!(snapshot.oldest_event_age_secs, None);
+    assert_eq!(snapshot.fullness, 0.0);
+}
+
+#[test]
+fn rejects_short_or_foreign_accounts() {
+    let mut data = heap_account(NO_NODE, 0, 0);
+    assert!(parse_event_heap(&data[..data.len() - 1]).is_err());
+
+    data[0] ^= 0xff;
+    assert!(parse_event_heap(&data).is_err());
+}
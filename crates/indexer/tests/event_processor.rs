@@ -0,0 +1,128 @@
+use solana_openbook_indexer::event_processor::{
+    market_account_index, parse_event_positions, parse_instruction_events, trade_id,
+};
+use solana_openbook_indexer::EventPosition;
+
+const OPENBOOK: &str = "opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb";
+
+fn logs(lines: &[&str]) -> Vec<String> {
+    lines
+        .iter()
+        .map(|line| line.replace("{ob}", OPENBOOK))
+        .collect()
+}
+
+#[test]
+fn events_carry_their_top_level_instruction() {
+    let logs = logs(&[
+        "Program ComputeBudget111111111111111111111111111111 invoke [1]",
+        "Program ComputeBudget111111111111111111111111111111 success",
+        "Program {ob} invoke [1]",
+        "Program log: Instruction: PlaceOrder",
+        "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+        "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA success",
+        "Program {ob} success",
+        "Program {ob} invoke [1]",
+        "Program log: Instruction: PlaceOrder",
+        "Program {ob} success",
+        "Program {ob} invoke [1]",
+        "Program log: Instruction: ConsumeEvents",
+        "Program {ob} success",
+    ]);
+
+    assert_eq!(
+        parse_instruction_events(&logs),
+        vec![(1, "PlaceOrder"), (2, "PlaceOrder"), (3, "ConsumeEvents")]
+    );
+}
+
+#[test]
+fn events_invoked_through_cpi_belong_to_the_outer_instruction() {
+    let logs = logs(&[
+        "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 invoke [1]",
+        "Program {ob} invoke [2]",
+        "Program log: Instruction: PlaceOrder",
+        "Program {ob} success",
+        "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 success",
+    ]);
+
+    assert_eq!(parse_instruction_events(&logs), vec![(0, "PlaceOrder")]);
+}
+
+#[test]
+fn events_are_positioned_by_invocation_and_sequence() {
+    let logs = logs(&[
+        "Program {ob} invoke [1]",
+        "Program log: Instruction: PlaceOrder",
+        "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+        "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA success",
+        "Program {ob} success",
+        "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 invoke [1]",
+        "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+        "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA success",
+        "Program {ob} invoke [2]",
+        "Program log: Instruction: PlaceOrder",
+        "Program {ob} invoke [3]",
+        "Program log: Instruction: ConsumeEvents",
+        "Program {ob} success",
+        "Program log: Instruction: Fill",
+        "Program {ob} success",
+        "Program JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4 success",
+    ]);
+
+    let position = |instruction_index, inner_index, event_seq| EventPosition {
+        instruction_index,
+        inner_index,
+        event_seq,
+    };
+    assert_eq!(
+        parse_event_positions(&logs),
+        vec![
+            (position(0, 0, 0), "PlaceOrder"),
+            (position(1, 2, 1), "PlaceOrder"),
+            (position(1, 3, 2), "ConsumeEvents"),
+            (position(1, 2, 3), "Fill"),
+        ]
+    );
+}
+
+#[test]
+fn trade_ids_follow_chain_order() {
+    let ids = [
+        trade_id(250_000_000, Some(3), 0),
+        trade_id(250_000_000, Some(3), 1),
+        trade_id(250_000_000, Some(7), 0),
+        trade_id(250_000_000, Some(0xFFFF), 0xFFFF),
+        trade_id(250_000_001, Some(0), 0),
+    ]
+    .map(Option::unwrap);
+
+    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(ids[0], (250_000_000 << 32) | (3 << 16));
+}
+
+#[test]
+fn trades_without_a_unique_position_have_no_id() {
+    assert_eq!(trade_id(250_000_000, None, 0), None);
+    assert_eq!(trade_id(250_000_000, Some(0x10000), 0), None);
+    assert_eq!(trade_id(250_000_000, Some(3), 0x10000), None);
+    assert_eq!(trade_id(250_000_000, Some(-1), 0), None);
+}
+
+#[test]
+fn market_accounts_are_found_per_instruction() {
+    let place_order = [51, 194, 155, 175, 109, 130, 96, 106, 0, 1, 2];
+    let cancel_order = [95, 129, 237, 240, 8, 49, 223, 132];
+    let consume_events = [221, 145, 177, 52, 31, 47, 63, 201, 9];
+    assert_eq!(market_account_index(&place_order), Some(4));
+    assert_eq!(market_account_index(&cancel_order), Some(2));
+    assert_eq!(market_account_index(&consume_events), Some(1));
+
+    // stubOracleSet names no market; unknown and truncated data name none either
+    assert_eq!(
+        market_account_index(&[109, 198, 79, 121, 65, 202, 161, 142]),
+        None
+    );
+    assert_eq!(market_account_index(&[0; 16]), None);
+    assert_eq!(market_account_index(&place_order[..7]), None);
+}
@@ -0,0 +1,204 @@
+mod database;
+
+use database::TestDatabase;
+use solana_openbook_indexer::event_processor::trade_id;
+use solana_openbook_indexer::finalizer::{block_range, partition_slots};
+use solana_openbook_indexer::{ApplyMode, EventPosition, TradeWrite, TransactionWrites};
+use std::collections::HashSet;
+
+const CLUSTER: &str = "localnet";
+const PROGRAM: &str = "opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb";
+const MARKET: &str = "CFSMrBssNG8Ud1edW59jNLnq2cwrQ9uY5cM3wXmqRJj3";
+
+#[test]
+fn block_range_spans_the_unfinalized_slots() {
+    assert_eq!(block_range(&[]), None);
+    assert_eq!(block_range(&[7]), Some((7, 7)));
+    assert_eq!(block_range(&[10, 12, 15]), Some((10, 15)));
+    assert_eq!(block_range(&[10, 900_000]), Some((10, 500_009)));
+}
+
+#[test]
+fn slots_without_a_block_are_skipped() {
+    let blocks: HashSet<u64> = [10, 12].into_iter().collect();
+
+    let (finalized, skipped) = partition_slots(&[10, 11, 12, 13], 13, &blocks);
+
+    assert_eq!(finalized, vec![10, 12]);
+    assert_eq!(skipped, vec![11, 13]);
+}
+
+#[test]
+fn slots_past_the_block_range_are_left_for_the_next_batch() {
+    let blocks: HashSet<u64> = [10].into_iter().collect();
+
+    let (finalized, skipped) = partition_slots(&[10, 11, 600_000], 500_009, &blocks);
+
+    assert_eq!(finalized, vec![10]);
+    assert_eq!(skipped, vec![11]);
+}
+
+fn transaction(signature: &str, slot: i64, tx_index: Option<i32>) -> TransactionWrites {
+    TransactionWrites {
+        signature: signature.to_string(),
+        cluster: CLUSTER.to_string(),
+        program_id: PROGRAM.to_string(),
+        slot,
+        tx_index,
+        timestamp: slot * 400,
+        trades: vec![TradeWrite {
+            position: EventPosition::default(),
+            trade_id: trade_id(slot, tx_index, 0),
+            market_id: MARKET.to_string(),
+            maker_address: "maker".to_string(),
+            taker_address: "taker".to_string(),
+            side: "buy".to_string(),
+            price: 100,
+            quantity: 1,
+        }],
+        ..Default::default()
+    }
+}
+
+async fn indexed(test: &TestDatabase, transactions: &[TransactionWrites]) {
+    test.db
+        .upsert_market(
+            MARKET,
+            CLUSTER,
+            PROGRAM,
+            "base",
+            "quote",
+            "BASE/QUOTE",
+            9,
+            6,
+            0,
+        )
+        .await
+        .unwrap();
+    for writes in transactions {
+        assert!(test
+            .db
+            .apply_transaction(writes, ApplyMode::Live)
+            .await
+            .unwrap());
+    }
+}
+
+#[tokio::test]
+async fn finalized_slots_are_promoted() {
+    let Some(test) = TestDatabase::create().await else {
+        return;
+    };
+    indexed(
+        &test,
+        &[transaction("a", 10, Some(0)), transaction("b", 11, Some(0))],
+    )
+    .await;
+
+    assert_eq!(test.db.finalize_slots(CLUSTER, &[10]).await.unwrap(), 1);
+
+    let trades: Vec<String> = test
+        .column("SELECT commitment FROM trades ORDER BY slot")
+        .await;
+    assert_eq!(trades, vec!["finalized", "confirmed"]);
+    assert_eq!(
+        test.db
+            .get_unfinalized_slots(CLUSTER, 100, 10)
+            .await
+            .unwrap(),
+        vec![11]
+    );
+
+    test.drop().await;
+}
+
+#[tokio::test]
+async fn skipped_slots_are_rolled_back_with_the_checkpoint() {
+    let Some(test) = TestDatabase::create().await else {
+        return;
+    };
+    indexed(
+        &test,
+        &[
+            transaction("a", 10, Some(0)),
+            transaction("b", 12, Some(0)),
+            transaction("c", 13, Some(0)),
+        ],
+    )
+    .await;
+
+    let rolled_back = test.db.rollback_slots(CLUSTER, &[12, 13]).await.unwrap();
+
+    assert_eq!(
+        rolled_back.into_iter().collect::<HashSet<_>>(),
+        ["b", "c"].map(String::from).into_iter().collect()
+    );
+    let trades: Vec<String> = test.column("SELECT signature FROM trades").await;
+    assert_eq!(trades, vec!["a"]);
+
+    let checkpoint = test
+        .db
+        .get_checkpoint(CLUSTER, PROGRAM)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((checkpoint.slot, checkpoint.signature.as_str()), (10, "a"));
+
+    test.drop().await;
+}
+
+#[tokio::test]
+async fn a_checkpoint_outside_the_skipped_slots_stays() {
+    let Some(test) = TestDatabase::create().await else {
+        return;
+    };
+    indexed(
+        &test,
+        &[transaction("a", 10, Some(0)), transaction("b", 12, Some(0))],
+    )
+    .await;
+
+    test.db.rollback_slots(CLUSTER, &[10]).await.unwrap();
+
+    let checkpoint = test
+        .db
+        .get_checkpoint(CLUSTER, PROGRAM)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((checkpoint.slot, checkpoint.signature.as_str()), (12, "b"));
+
+    test.drop().await;
+}
+
+#[tokio::test]
+async fn live_trades_are_placed_in_their_block() {
+    let Some(test) = TestDatabase::create().await else {
+        return;
+    };
+    indexed(
+        &test,
+        &[transaction("a", 10, None), transaction("b", 10, None)],
+    )
+    .await;
+    assert_eq!(
+        test.db.get_unplaced_slots(CLUSTER, 100, 10).await.unwrap(),
+        vec![10]
+    );
+
+    let block = ["vote", "b", "a"].map(String::from);
+    assert_eq!(test.db.place_slot(CLUSTER, 10, &block).await.unwrap(), 2);
+
+    let placed: Vec<i32> = test
+        .column("SELECT tx_index FROM trades ORDER BY trade_id")
+        .await;
+    assert_eq!(placed, vec![1, 2]);
+    assert!(test
+        .db
+        .get_unplaced_slots(CLUSTER, 100, 10)
+        .await
+        .unwrap()
+        .is_empty());
+
+    test.drop().await;
+}
@@ -0,0 +1,208 @@
+use prost::Message as _;
+use solana_openbook_indexer::ingest::geyser::{subscribe_request, transaction_from_update};
+use solana_openbook_indexer::ingest::geyser_proto::{
+    subscribe_update::UpdateOneof, CommitmentLevel, CompiledInstruction, InnerInstruction,
+    InnerInstructions, Message, MessageAddressTableLookup, MessageHeader, SubscribeRequest,
+    SubscribeUpdate, SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo, TokenBalance,
+    Transaction, TransactionError, TransactionStatusMeta, UiTokenAmount,
+};
+use solana_openbook_indexer::ingest::touches_program;
+use solana_sdk::commitment_config::CommitmentLevel as RpcCommitmentLevel;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::TransactionError as SdkTransactionError;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{EncodedTransaction, UiMessage};
+
+/// A failed v0 transaction whose program is only reached through a lookup table
+fn update(program_id: &Pubkey, signer: &Pubkey) -> SubscribeUpdateTransaction {
+    let error = SdkTransactionError::InstructionError(0, InstructionError::Custom(6000));
+
+    SubscribeUpdateTransaction {
+        slot: 250_000_000,
+        transaction: Some(SubscribeUpdateTransactionInfo {
+            signature: vec![7; 64],
+            is_vote: false,
+            index: 42,
+            transaction: Some(Transaction {
+                signatures: vec![vec![7; 64]],
+                message: Some(Message {
+                    header: Some(MessageHeader {
+                        num_required_signatures: 1,
+                        num_readonly_signed_accounts: 0,
+                        num_readonly_unsigned_accounts: 0,
+                    }),
+                    account_keys: vec![signer.to_bytes().to_vec()],
+                    recent_blockhash: vec![1; 32],
+                    instructions: vec![CompiledInstruction {
+                        program_id_index: 1,
+                        accounts: vec![0],
+                        data: vec![1, 2, 3],
+                    }],
+                    versioned: true,
+                    address_table_lookups: vec![MessageAddressTableLookup {
+                        account_key: Pubkey::new_unique().to_bytes().to_vec(),
+                        writable_indexes: vec![],
+                        readonly_indexes: vec![3],
+                    }],
+                }),
+            }),
+            meta: Some(TransactionStatusMeta {
+                err: Some(TransactionError {
+                    err: bincode::serialize(&error).unwrap(),
+                }),
+                fee: 5000,
+                pre_balances: vec![1_000_000, 1],
+                post_balances: vec![995_000, 1],
+                inner_instructions: vec![InnerInstructions {
+                    index: 0,
+                    instructions: vec![InnerInstruction {
+                        program_id_index: 1,
+                        accounts: vec![0],
+                        data: vec![9],
+                        stack_height: Some(2),
+                    }],
+                }],
+                log_messages: vec![
+                    format!("Program {} invoke [1]", program_id),
+                    format!(
+                        "Program {} failed: custom program error: 0x1770",
+                        program_id
+                    ),
+                ],
+                pre_token_balances: vec![TokenBalance {
+                    account_index: 0,
+                    mint: Pubkey::new_unique().to_string(),
+                    ui_token_amount: Some(UiTokenAmount {
+                        ui_amount: 0.0,
+                        decimals: 6,
+                        amount: "0".to_string(),
+                        ui_amount_string: "0".to_string(),
+                    }),
+                    owner: signer.to_string(),
+                    program_id: "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA".to_string(),
+                }],
+                loaded_readonly_addresses: vec![program_id.to_bytes().to_vec()],
+                compute_units_consumed: Some(12_345),
+                ..Default::default()
+            }),
+        }),
+    }
+}
+
+#[test]
+fn transactions_of_the_program_are_subscribed_at_the_pool_commitment() {
+    let program_id = Pubkey::new_unique();
+
+    let request = subscribe_request(&program_id, RpcCommitmentLevel::Confirmed);
+
+    assert_eq!(request.commitment, Some(CommitmentLevel::Confirmed as i32));
+    let filter = &request.transactions["openbook"];
+    assert_eq!(filter.account_include, vec![program_id.to_string()]);
+    assert_eq!((filter.vote, filter.failed), (Some(false), Some(true)));
+    assert_eq!(request.slots.len(), 1);
+    assert_eq!(request.ping, None);
+
+    let finalized = subscribe_request(&program_id, RpcCommitmentLevel::Finalized);
+    assert_eq!(
+        finalized.commitment,
+        Some(CommitmentLevel::Finalized as i32)
+    );
+
+    // What goes on the wire decodes back to the same request
+    let decoded = SubscribeRequest::decode(request.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(decoded, request);
+}
+
+#[test]
+fn streamed_transactions_are_encoded_like_get_transaction() {
+    let program_id = Pubkey::new_unique();
+    let signer = Pubkey::new_unique();
+
+    let tx = transaction_from_update(update(&program_id, &signer)).unwrap();
+
+    assert_eq!(tx.signature, bs58::encode([7u8; 64]).into_string());
+    assert_eq!(tx.slot, 250_000_000);
+    assert_eq!(tx.tx_index, Some(42));
+    assert_eq!(tx.block_time(), None);
+    assert_eq!(tx.log_messages().len(), 2);
+    assert_eq!(
+        tx.error(),
+        Some(SdkTransactionError::InstructionError(
+            0,
+            InstructionError::Custom(6000)
+        ))
+    );
+    // Reached through the lookup table only
+    assert!(touches_program(&tx.transaction.transaction, &program_id));
+
+    let EncodedTransaction::Json(ui_tx) = &tx.transaction.transaction.transaction else {
+        panic!("not JSON encoded");
+    };
+    let UiMessage::Raw(message) = &ui_tx.message else {
+        panic!("not a raw message");
+    };
+    assert_eq!(message.account_keys, vec![signer.to_string()]);
+    assert_eq!(
+        message.instructions[0].data,
+        bs58::encode([1, 2, 3]).into_string()
+    );
+    assert_eq!(message.address_table_lookups.as_ref().unwrap().len(), 1);
+
+    let meta = tx.transaction.transaction.meta.as_ref().unwrap();
+    assert_eq!(meta.fee, 5000);
+    let OptionSerializer::Some(inner) = &meta.inner_instructions else {
+        panic!("inner instructions missing");
+    };
+    assert_eq!(inner[0].instructions.len(), 1);
+    let OptionSerializer::Some(balances) = &meta.pre_token_balances else {
+        panic!("token balances missing");
+    };
+    assert_eq!(balances[0].ui_token_amount.ui_amount, None);
+    assert_eq!(meta.compute_units_consumed, OptionSerializer::Some(12_345));
+}
+
+#[test]
+fn incomplete_updates_are_rejected() {
+    let program_id = Pubkey::new_unique();
+    let signer = Pubkey::new_unique();
+
+    let mut without_meta = update(&program_id, &signer);
+    without_meta.transaction.as_mut().unwrap().meta = None;
+    assert!(transaction_from_update(without_meta).is_err());
+
+    let mut bad_key = update(&program_id, &signer);
+    bad_key
+        .transaction
+        .as_mut()
+        .unwrap()
+        .transaction
+        .as_mut()
+        .unwrap()
+        .message
+        .as_mut()
+        .unwrap()
+        .account_keys = vec![vec![1; 31]];
+    assert!(transaction_from_update(bad_key).is_err());
+}
+
+#[test]
+fn updates_the_source_does_not_read_still_decode() {
+    let program_id = Pubkey::new_unique();
+    let signer = Pubkey::new_unique();
+    let transaction = SubscribeUpdate {
+        filters: vec!["openbook".to_string()],
+        update_oneof: Some(UpdateOneof::Transaction(Box::new(update(
+            &program_id,
+            &signer,
+        )))),
+    };
+    let decoded = SubscribeUpdate::decode(transaction.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(decoded, transaction);
+
+    // A block update (field 5) with its creation time (field 11), neither declared here
+    let block = [0x0a, 0x01, b'b', 0x2a, 0x00, 0x5a, 0x02, 0x08, 0x01];
+    let decoded = SubscribeUpdate::decode(block.as_slice()).unwrap();
+    assert_eq!(decoded.filters, vec!["b".to_string()]);
+    assert_eq!(decoded.update_oneof, None);
+}
@@ -0,0 +1,70 @@
+use solana_openbook_indexer::ingest::logs::{hand_off, PendingSignature};
+use solana_openbook_indexer::ingest::ReconnectBackoff;
+use std::collections::HashSet;
+use std::time::Duration;
+
+fn pending(signature: &str, slot: u64) -> PendingSignature {
+    PendingSignature {
+        signature: signature.to_string(),
+        slot,
+        live: false,
+    }
+}
+
+fn signatures(gap: &[PendingSignature]) -> Vec<&str> {
+    gap.iter().map(|sig| sig.signature.as_str()).collect()
+}
+
+#[test]
+fn the_gap_fill_stops_at_the_first_buffered_slot() {
+    let mut gap = vec![pending("a", 900), pending("b", 1000), pending("c", 1001)];
+
+    let handoff = hand_off(&mut gap, Some(1000), 1000);
+
+    assert_eq!(signatures(&gap), vec!["a", "b"]);
+    assert_eq!(
+        handoff,
+        ["a", "b"]
+            .map(String::from)
+            .into_iter()
+            .collect::<HashSet<_>>()
+    );
+}
+
+#[test]
+fn only_signatures_near_the_subscription_are_deduplicated() {
+    let mut gap = vec![
+        pending("old", 100),
+        pending("near", 900),
+        pending("new", 1010),
+    ];
+
+    let handoff = hand_off(&mut gap, None, 1000);
+
+    // Nothing was buffered: the whole gap is kept
+    assert_eq!(signatures(&gap), vec!["old", "near", "new"]);
+    assert_eq!(
+        handoff,
+        ["near", "new"]
+            .map(String::from)
+            .into_iter()
+            .collect::<HashSet<_>>()
+    );
+}
+
+#[test]
+fn reconnects_back_off_until_a_session_is_healthy() {
+    let mut backoff = ReconnectBackoff::default();
+    let dropped = Duration::from_secs(2);
+
+    let delays: Vec<u64> = (0..8)
+        .map(|_| backoff.after_session(dropped).as_secs())
+        .collect();
+    assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+
+    assert_eq!(
+        backoff.after_session(Duration::from_secs(60)),
+        Duration::from_secs(1)
+    );
+    assert_eq!(backoff.after_session(dropped), Duration::from_secs(2));
+}
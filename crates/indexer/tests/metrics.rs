@@ -0,0 +1,37 @@
+use solana_openbook_indexer::metrics::metrics;
+use std::time::Duration;
+
+#[test]
+fn metrics_render_in_prometheus_text_format() {
+    metrics().events.inc(&["prog", "OrderPlaced"]);
+    metrics().events.inc(&["prog", "OrderPlaced"]);
+    metrics().slot_lag.set(&[], 42.0);
+    metrics()
+        .http_request_duration
+        .observe(&["GET", "/api/\"depth\"", "200"], Duration::from_millis(30));
+    metrics()
+        .http_request_duration
+        .observe(&["GET", "/api/\"depth\"", "200"], Duration::from_secs(20));
+
+    let text = metrics().render();
+
+    assert!(text.contains("# TYPE openbook_events_processed_total counter\n"));
+    assert!(text.contains(
+        "openbook_events_processed_total{program=\"prog\",event_type=\"OrderPlaced\"} 2\n"
+    ));
+    assert!(text.contains("openbook_slot_lag 42\n"));
+
+    // Buckets are cumulative; label values are escaped
+    let labels = "method=\"GET\",route=\"/api/\\\"depth\\\"\",status=\"200\"";
+    for (le, count) in [("0.025", 0), ("0.05", 1), ("10", 1), ("+Inf", 2)] {
+        let line = format!(
+            "openbook_http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}\n",
+            labels, le, count
+        );
+        assert!(text.contains(&line), "missing {}", line);
+    }
+    assert!(text.contains(&format!(
+        "openbook_http_request_duration_seconds_count{{{}}} 2\n",
+        labels
+    )));
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use solana_openbook_indexer::open_orders::{
+    parse_open_orders_account, parse_open_orders_indexer, MAX_OPEN_ORDERS,
+};
+use solana_sdk::pubkey::Pubkey;
+
+use common::put;
+
+const ACCOUNT_DISCRIMINATOR: [u8; 8] = [255, 194, 78, 123, 16, 105, 208, 165];
+const INDEXER_DISCRIMINATOR: [u8; 8] = [195, 83, 128, 213, 204, 91, 19, 150];
+const POSITION_OFFSET: usize = 144;
+const OPEN_ORDERS_OFFSET: usize = POSITION_OFFSET + 160;
+const OPEN_ORDER_SIZE: usize = 40;
+
+/// An OpenOrdersAccount with every order slot free
+fn open_orders_account(owner: &Pubkey, market: &Pubkey) -> Vec<u8> {
+    let mut data = vec![0u8; OPEN_ORDERS_OFFSET + MAX_OPEN_ORDERS * OPEN_ORDER_SIZE];
+    put(&mut data, 0, &ACCOUNT_DISCRIMINATOR);
+    put(&mut data, 8, owner.as_ref());
+    put(&mut data, 40, market.as_ref());
+    put(&mut data, 72, b"trading");
+    put(&mut data, 136, &3u32.to_le_bytes());
+    data[141] = 1;
+    for slot in 0..MAX_OPEN_ORDERS {
+        data[OPEN_ORDERS_OFFSET + slot * OPEN_ORDER_SIZE + 32] = 1;
+    }
+    data
+}
+
+#[test]
+fn decodes_position_and_order_slots() {
+    let owner = Pubkey::new_unique();
+    let market = Pubkey::new_unique();
+    let mut data = open_orders_account(&owner, &market);
+
+    let p = POSITION_OFFSET;
+    put(&mut data, p, &12i64.to_le_bytes());
+    put(&mut data, p + 8, &5i64.to_le_bytes());
+    put(&mut data, p + 16, &1_000u64.to_le_bytes());
+    put(&mut data, p + 24, &2_500u64.to_le_bytes());
+    put(&mut data, p + 32, &7u64.to_le_bytes());
+    put(&mut data, p + 56, &900u128.to_le_bytes());
+    put(&mut data, p + 72, &400u128.to_le_bytes());
+    put(&mut data, p + 88, &61_800i64.to_le_bytes());
+
+    // Slot 2 holds a resting fixed ask at 5_150 lots
+    let o = OPEN_ORDERS_OFFSET + 2 * OPEN_ORDER_SIZE;
+    let id = (5_150u128 << 64) | 17;
+    put(&mut data, o, &id.to_le_bytes());
+    put(&mut data, o + 16, &42u64.to_le_bytes());
+    put(&mut data, o + 24, &5_150i64.to_le_bytes());
+    data[o + 32] = 0;
+    data[o + 33] = 1;
+
+    let account = parse_open_orders_account(&data, "address").unwrap();
+    assert_eq!(account.address, "address");
+    assert_eq!(account.owner, owner.to_string());
+    assert_eq!(account.market, market.to_string());
+    assert_eq!(account.name, "trading");
+    assert_eq!(account.delegate, None);
+    assert_eq!(account.account_num, 3);
+    assert_eq!(account.version, 1);
+
+    let position = &account.position;
+    assert_eq!(position.bids_base_lots, 12);
+    assert_eq!(position.asks_base_lots, 5);
+    assert_eq!(position.base_free_native, 1_000);
+    assert_eq!(position.quote_free_native, 2_500);
+    assert_eq!(position.locked_maker_fees, 7);
+    assert_eq!(position.maker_volume, 900);
+    assert_eq!(position.taker_volume, 400);
+    assert_eq!(position.bids_quote_lots, 61_800);
+
+    assert_eq!(account.open_orders.len(), MAX_OPEN_ORDERS);
+    let filled = &account.open_orders[2];
+    assert!(!filled.is_free);
+    assert_eq!(filled.id, id);
+    assert_eq!(filled.client_id, 42);
+    assert_eq!(filled.locked_price, 5_150);
+    assert_eq!(filled.side(), "ask");
+    assert_eq!(filled.order_tree(), "fixed");
+    assert_eq!(filled.price_lots(), Some(5_150));
+
+    let empty = &account.open_orders[0];
+    assert!(empty.is_free);
+    assert_eq!(empty.id, 0);
+    assert_eq!(account.open_orders.iter().filter(|o| !o.is_free).count(), 1);
+}
+
+#[test]
+fn oracle_pegged_orders_have_no_fixed_price() {
+    let mut data = open_orders_account(&Pubkey::new_unique(), &Pubkey::new_unique());
+    let delegate = Pubkey::new_unique();
+    put(&mut data, 104, delegate.as_ref());
+
+    let o = OPEN_ORDERS_OFFSET;
+    put(&mut data, o, &((9u128 << 64) | 1).to_le_bytes());
+    data[o + 32] = 0;
+    data[o + 33] = 2;
+
+    let account = parse_open_orders_account(&data, "address").unwrap();
+    assert_eq!(account.delegate, Some(delegate.to_string()));
+
+    let pegged = &account.open_orders[0];
+    assert_eq!(pegged.side(), "bid");
+    assert_eq!(pegged.order_tree(), "oracle_pegged");
+    assert_eq!(pegged.price_lots(), None);
+}
+
+#[test]
+fn rejects_short_or_foreign_accounts() {
+    let mut data = open_orders_account(&Pubkey::new_unique(), &Pubkey::new_unique());
+    assert!(parse_open_orders_account(&data[..data.len() - 1], "address").is_err());
+
+    data[0] ^= 0xff;
+    assert!(parse_open_orders_account(&data, "address").is_err());
+}
+
+#[test]
+fn decodes_the_indexer_address_list() {
+    let addresses = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let mut data = INDEXER_DISCRIMINATOR.to_vec();
+    data.push(254);
+    data.extend_from_slice(&4u32.to_le_bytes());
+    data.extend_from_slice(&(addresses.len() as u32).to_le_bytes());
+    for address in &addresses {
+        data.extend_from_slice(address.as_ref());
+    }
+
+    let indexer = parse_open_orders_indexer(&data).unwrap();
+    assert_eq!(indexer.created_counter, 4);
+    assert_eq!(
+        indexer.addresses,
+        addresses.iter().map(|a| a.to_string()).collect::<Vec<_>>()
+    );
+
+    // A list longer than the account is truncated data, not a short list
+    assert!(parse_open_orders_indexer(&data[..data.len() - 1]).is_err());
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use solana_openbook_indexer::oracle::{
+    combined_oracle_price, determine_oracle_type, oracle_state_unchecked, ui_price_to_lots,
+    MarketOracleConfig, OracleType,
+};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use common::put;
+
+const STUB_ORACLE_DISCRIMINATOR: [u8; 8] = [224, 251, 254, 99, 177, 174, 137, 4];
+const SWITCHBOARD_V2_DISCRIMINATOR: [u8; 8] = [217, 230, 65, 101, 201, 162, 27, 125];
+
+fn account(data: Vec<u8>) -> Account {
+    Account {
+        lamports: 1,
+        data,
+        owner: Pubkey::new_unique(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Pyth v2 price account: `price * 10^expo` with confidence `conf * 10^expo`
+fn pyth_account(price: i64, conf: u64, expo: i32, pub_slot: u64) -> Account {
+    let mut data = vec![0u8; 240];
+    put(&mut data, 0, &0xa1b2c3d4u32.to_le_bytes());
+    put(&mut data, 20, &expo.to_le_bytes());
+    put(&mut data, 208, &price.to_le_bytes());
+    put(&mut data, 216, &conf.to_le_bytes());
+    put(&mut data, 232, &pub_slot.to_le_bytes());
+    account(data)
+}
+
+fn stub_account(price: f64, deviation: f64, last_update_slot: u64) -> Account {
+    let mut data = vec![0u8; 104];
+    put(&mut data, 0, &STUB_ORACLE_DISCRIMINATOR);
+    put(&mut data, 72, &price.to_le_bytes());
+    put(&mut data, 88, &last_update_slot.to_le_bytes());
+    put(&mut data, 96, &deviation.to_le_bytes());
+    account(data)
+}
+
+/// Switchboard V2 aggregator with `result` and `std_deviation` as SwitchboardDecimals
+fn switchboard_account(mantissa: i128, std_mantissa: i128, scale: u32, slot: u64) -> Account {
+    let mut data = vec![0u8; 406];
+    put(&mut data, 0, &SWITCHBOARD_V2_DISCRIMINATOR);
+    put(&mut data, 350, &slot.to_le_bytes());
+    put(&mut data, 366, &mantissa.to_le_bytes());
+    put(&mut data, 382, &scale.to_le_bytes());
+    put(&mut data, 386, &std_mantissa.to_le_bytes());
+    put(&mut data, 402, &scale.to_le_bytes());
+    account(data)
+}
+
+fn config(conf_filter: f64, max_staleness_slots: i64) -> MarketOracleConfig {
+    MarketOracleConfig {
+        oracle_a: Some(Pubkey::new_unique()),
+        oracle_b: None,
+        conf_filter,
+        max_staleness_slots,
+        base_decimals: 9,
+        quote_decimals: 6,
+        base_lot_size: 1_000_000,
+        quote_lot_size: 1,
+    }
+}
+
+fn approx(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9 * b.abs().max(1.0)
+}
+
+#[test]
+fn decodes_each_oracle_kind() {
+    let pyth = oracle_state_unchecked(&pyth_account(2_000_000_000, 1_000_000, -8, 100)).unwrap();
+    assert_eq!(pyth.oracle_type, OracleType::Pyth);
+    assert!(approx(pyth.price, 20.0));
+    assert!(approx(pyth.deviation, 0.01));
+    assert_eq!(pyth.last_update_slot, 100);
+
+    let switchboard =
+        oracle_state_unchecked(&switchboard_account(1_250_000, 2_500, 6, 101)).unwrap();
+    assert_eq!(switchboard.oracle_type, OracleType::SwitchboardV2);
+    assert!(approx(switchboard.price, 1.25));
+    assert!(approx(switchboard.deviation, 0.0025));
+    assert_eq!(switchboard.last_update_slot, 101);
+
+    let stub = oracle_state_unchecked(&stub_account(0.5, 0.0, 102)).unwrap();
+    assert_eq!(stub.oracle_type, OracleType::Stub);
+    assert!(approx(stub.price, 0.5));
+    assert_eq!(stub.last_update_slot, 102);
+
+    let unknown = account(vec![0u8; 240]);
+    assert_eq!(determine_oracle_type(&unknown), None);
+    assert!(oracle_state_unchecked(&unknown).is_err());
+
+    // A recognised header on a truncated account is an error, not garbage
+    let mut short = pyth_account(1, 1, 0, 1);
+    short.data.truncate(200);
+    assert!(oracle_state_unchecked(&short).is_err());
+}
+
+#[test]
+fn combines_two_oracles_as_a_over_b() {
+    let config = config(0.1, 50);
+    let a = oracle_state_unchecked(&pyth_account(2_000_000_000, 1_000_000, -8, 100)).unwrap();
+    let b = oracle_state_unchecked(&switchboard_account(1_250_000, 2_500, 6, 100)).unwrap();
+
+    let price = combined_oracle_price(&config, &a, Some(&b), 120).unwrap();
+    assert!(approx(price, 16.0));
+
+    // Stub oracles report no deviation and pass any confidence filter
+    let stub = oracle_state_unchecked(&stub_account(0.5, 0.0, 100)).unwrap();
+    let price = combined_oracle_price(&config, &a, Some(&stub), 120).unwrap();
+    assert!(approx(price, 40.0));
+
+    assert_eq!(ui_price_to_lots(&config, 16.0), Some(16_000));
+}
+
+#[test]
+fn rejects_low_confidence_prices() {
+    // 0.05% + 0.2% relative deviation against a 0.2% filter
+    let config = config(0.002, -1);
+    let a = oracle_state_unchecked(&pyth_account(2_000_000_000, 1_000_000, -8, 100)).unwrap();
+    let b = oracle_state_unchecked(&switchboard_account(1_250_000, 2_500, 6, 100)).unwrap();
+
+    assert!(combined_oracle_price(&config, &a, None, 100).is_some());
+    assert!(combined_oracle_price(&config, &b, None, 100).is_some());
+    assert!(combined_oracle_price(&config, &a, Some(&b), 100).is_none());
+
+    let wide = oracle_state_unchecked(&pyth_account(2_000_000_000, 10_000_000, -8, 100)).unwrap();
+    assert!(combined_oracle_price(&config, &wide, None, 100).is_none());
+}
+
+#[test]
+fn rejects_stale_prices() {
+    let config = config(0.1, 50);
+    let a = oracle_state_unchecked(&pyth_account(2_000_000_000, 1_000_000, -8, 100)).unwrap();
+    let b = oracle_state_unchecked(&stub_account(0.5, 0.0, 10)).unwrap();
+
+    assert!(combined_oracle_price(&config, &a, None, 150).is_some());
+    assert!(combined_oracle_price(&config, &a, None, 151).is_none());
+    assert!(b.is_stale(&config, 100));
+    assert!(combined_oracle_price(&config, &a, Some(&b), 100).is_none());
+
+    // A negative max staleness disables the check
+    let unchecked = self::config(0.1, -1);
+    assert!(combined_oracle_price(&unchecked, &a, Some(&b), 10_000).is_some());
+}
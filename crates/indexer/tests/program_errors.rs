@@ -0,0 +1,39 @@
+use solana_openbook_indexer::program_errors::{error_name, TransactionFailure, OPENBOOK_ERRORS};
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+
+#[test]
+fn every_idl_error_code_is_named() {
+    let codes: Vec<u32> = OPENBOOK_ERRORS.iter().map(|(code, _, _)| *code).collect();
+    assert_eq!(codes, (6000..=6042).collect::<Vec<_>>());
+    assert_eq!(error_name(6029), Some("WouldSelfTrade"));
+    assert_eq!(error_name(6042), Some("NonEmptyOpenOrdersPosition"));
+    assert_eq!(error_name(6043), None);
+}
+
+#[test]
+fn custom_instruction_errors_are_decoded() {
+    let failure = TransactionFailure::from_error(&TransactionError::InstructionError(
+        2,
+        InstructionError::Custom(6029),
+    ));
+    assert_eq!(failure.instruction_index, Some(2));
+    assert_eq!(failure.error_code, Some(6029));
+    assert_eq!(failure.error_name, Some("WouldSelfTrade"));
+    assert_eq!(
+        failure.message,
+        "OpenBook error 6029 WouldSelfTrade: would self trade"
+    );
+
+    // Custom codes of other programs keep their raw description
+    let failure = TransactionFailure::from_error(&TransactionError::InstructionError(
+        0,
+        InstructionError::Custom(1),
+    ));
+    assert_eq!((failure.error_code, failure.error_name), (Some(1), None));
+    assert!(failure.message.contains("custom program error: 0x1"));
+
+    let failure = TransactionFailure::from_error(&TransactionError::InsufficientFundsForFee);
+    assert_eq!(failure.instruction_index, None);
+    assert_eq!(failure.error_code, None);
+}
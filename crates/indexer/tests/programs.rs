@@ -0,0 +1,95 @@
+use solana_openbook_indexer::event_heap::event_heap_address_from_market;
+use solana_openbook_indexer::market_scanner::parse_market_account;
+use solana_openbook_indexer::oracle::parse_market_oracle_config;
+use solana_openbook_indexer::programs::{cluster_of_url, MarketLayout, OPENBOOK_V2_PROGRAM_ID};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+const MARKET_DISCRIMINATOR: [u8; 8] = [219, 190, 213, 55, 0, 227, 198, 154];
+
+/// Market account with the name and mints written where `layout` keeps them
+fn market_account(layout: MarketLayout, base_mint: &Pubkey, quote_mint: &Pubkey) -> Vec<u8> {
+    let offsets = layout.offsets();
+    let mut data = vec![0u8; 840];
+    data[..8].copy_from_slice(&MARKET_DISCRIMINATOR);
+    data[offsets.name..offsets.name + 7].copy_from_slice(b"SOL-USD");
+    data[offsets.base_mint..offsets.base_mint + 32].copy_from_slice(base_mint.as_ref());
+    data[offsets.quote_mint..offsets.quote_mint + 32].copy_from_slice(quote_mint.as_ref());
+    if let Some((base, quote)) = offsets.decimals {
+        data[base] = 9;
+        data[quote] = 6;
+    }
+    data
+}
+
+#[test]
+fn layout_defaults_to_v2_only_for_the_canonical_program() {
+    let canonical = Pubkey::from_str(OPENBOOK_V2_PROGRAM_ID).unwrap();
+    assert_eq!(
+        MarketLayout::for_program(&canonical),
+        MarketLayout::OpenBookV2
+    );
+    assert_eq!(
+        MarketLayout::for_program(&Pubkey::new_unique()),
+        MarketLayout::CustomFork
+    );
+    assert_eq!("v2".parse(), Ok(MarketLayout::OpenBookV2));
+    assert_eq!(" Custom ".parse(), Ok(MarketLayout::CustomFork));
+    assert!("v1".parse::<MarketLayout>().is_err());
+}
+
+#[test]
+fn cluster_is_guessed_from_the_rpc_url() {
+    assert_eq!(cluster_of_url("https://api.devnet.solana.com"), "devnet");
+    assert_eq!(cluster_of_url("https://api.testnet.solana.com"), "testnet");
+    assert_eq!(cluster_of_url("http://127.0.0.1:8899"), "localnet");
+    assert_eq!(
+        cluster_of_url("https://mainnet.helius-rpc.com"),
+        "mainnet-beta"
+    );
+}
+
+#[test]
+fn markets_are_parsed_with_the_layout_of_their_program() {
+    let base_mint = Pubkey::new_unique();
+    let quote_mint = Pubkey::new_unique();
+    let address = Pubkey::new_unique().to_string();
+
+    for layout in [MarketLayout::OpenBookV2, MarketLayout::CustomFork] {
+        let data = market_account(layout, &base_mint, &quote_mint);
+        let market = parse_market_account(&data, &address, layout).unwrap();
+        assert_eq!(market.name, "SOL-USD");
+        assert_eq!(market.base_mint, base_mint.to_string());
+        assert_eq!(market.quote_mint, quote_mint.to_string());
+    }
+
+    let v2 = market_account(MarketLayout::OpenBookV2, &base_mint, &quote_mint);
+    let market = parse_market_account(&v2, &address, MarketLayout::OpenBookV2).unwrap();
+    assert_eq!((market.base_decimals, market.quote_decimals), (9, 6));
+
+    // Read with the wrong layout, the mints come from unrelated bytes
+    let market = parse_market_account(&v2, &address, MarketLayout::CustomFork).unwrap();
+    assert_ne!(market.base_mint, base_mint.to_string());
+}
+
+#[test]
+fn account_monitors_only_read_layouts_with_known_offsets() {
+    let state = MarketLayout::OpenBookV2.offsets().state.unwrap();
+    assert_eq!(state.event_heap, 264);
+    assert_eq!((state.quote_lot_size, state.base_lot_size), (448, 456));
+
+    let mut data = vec![0u8; 840];
+    let event_heap = Pubkey::new_unique();
+    data[264..296].copy_from_slice(event_heap.as_ref());
+    assert_eq!(
+        event_heap_address_from_market(&data, MarketLayout::OpenBookV2),
+        Some(event_heap)
+    );
+
+    assert!(MarketLayout::CustomFork.offsets().state.is_none());
+    assert_eq!(
+        event_heap_address_from_market(&data, MarketLayout::CustomFork),
+        None
+    );
+    assert!(parse_market_oracle_config(&data, MarketLayout::CustomFork).is_err());
+}
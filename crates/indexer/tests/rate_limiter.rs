@@ -0,0 +1,78 @@
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_openbook_indexer::rate_limiter::{is_rate_limited, AdaptiveRateLimiter};
+use std::time::{Duration, Instant};
+
+fn error(message: &str) -> ClientError {
+    ClientErrorKind::Custom(message.to_string()).into()
+}
+
+fn assert_rate(limiter: &AdaptiveRateLimiter, expected: f64) {
+    assert!(
+        (limiter.rate() - expected).abs() < 1e-9,
+        "rate {} != {}",
+        limiter.rate(),
+        expected
+    );
+}
+
+#[test]
+fn rate_limits_back_off_harder_than_errors() {
+    let limiter = AdaptiveRateLimiter::new(10.0, 1.0, 20.0);
+    let latency = Duration::from_millis(50);
+
+    limiter.record_error(
+        latency,
+        &error("HTTP status client error (429 Too Many Requests)"),
+    );
+    assert_rate(&limiter, 5.0);
+
+    limiter.record_error(latency, &error("connection reset"));
+    assert_rate(&limiter, 4.0);
+
+    limiter.record_success(latency);
+    assert_rate(&limiter, 4.1);
+
+    let stats = limiter.take_stats();
+    assert_eq!(
+        (stats.requests, stats.errors, stats.rate_limited),
+        (3, 2, 1)
+    );
+    assert_eq!(stats.max_latency_ms, 50);
+    assert_eq!(limiter.take_stats().requests, 0);
+}
+
+#[test]
+fn the_rate_stays_within_its_bounds() {
+    let limiter = AdaptiveRateLimiter::new(2.0, 1.0, 2.05);
+
+    for _ in 0..5 {
+        limiter.record_error(Duration::ZERO, &error("429"));
+    }
+    assert_rate(&limiter, 1.0);
+
+    for _ in 0..20 {
+        limiter.record_success(Duration::ZERO);
+    }
+    assert_rate(&limiter, 2.05);
+}
+
+#[test]
+fn only_429s_count_as_rate_limited() {
+    assert!(is_rate_limited(&error("429 Too Many Requests")));
+    assert!(!is_rate_limited(&error("503 Service Unavailable")));
+}
+
+#[tokio::test]
+async fn requests_are_paced_to_the_rate() {
+    let limiter = AdaptiveRateLimiter::new(10.0, 10.0, 10.0);
+    let start = Instant::now();
+
+    // The first token is available at once, the next two take 100ms each
+    for _ in 0..3 {
+        limiter.acquire().await;
+    }
+
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(180), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_secs(1), "{:?}", elapsed);
+}
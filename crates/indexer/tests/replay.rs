@@ -0,0 +1,81 @@
+use solana_openbook_indexer::event_processor::parse_events_from_logs;
+use solana_openbook_indexer::ingest::replay::{parse_line, to_line};
+use solana_openbook_indexer::ingest::{IngestSource, ReplaySource, TransactionRecorder};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/replay.jsonl");
+
+async fn replay_all(path: &str) -> Vec<solana_openbook_indexer::ingest::IngestedTransaction> {
+    let mut source = ReplaySource::open(path).await.unwrap();
+    let mut transactions = Vec::new();
+    while let Some(tx) = source.next_transaction().await.unwrap() {
+        transactions.push(tx);
+    }
+    transactions
+}
+
+#[tokio::test]
+async fn replays_fixture_in_file_order() {
+    let transactions = replay_all(FIXTURE).await;
+
+    let summary: Vec<_> = transactions
+        .iter()
+        .map(|tx| (tx.slot, tx.tx_index, tx.block_time()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (250_000_000, Some(3), Some(1_700_000_000)),
+            (250_000_000, Some(7), Some(1_700_000_000)),
+            (250_000_004, None, Some(1_700_000_002)),
+        ]
+    );
+    assert!(transactions[0].signature.starts_with("5h6xBEau"));
+
+    let events: Vec<_> = transactions
+        .iter()
+        .flat_map(|tx| parse_events_from_logs(&tx.log_messages()))
+        .collect();
+    assert_eq!(events, vec!["PlaceOrder", "CancelOrder", "ConsumeEvents"]);
+}
+
+#[tokio::test]
+async fn recorded_transactions_replay_identically() {
+    let original = replay_all(FIXTURE).await;
+
+    let path = std::env::temp_dir().join(format!("openbook-replay-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut recorder = TransactionRecorder::create(&path).await.unwrap();
+    for tx in &original {
+        recorder.record(tx).await.unwrap();
+    }
+    drop(recorder);
+
+    let replayed = replay_all(path.to_str().unwrap()).await;
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(replayed.len(), original.len());
+    for (a, b) in original.iter().zip(&replayed) {
+        assert_eq!(a.signature, b.signature);
+        assert_eq!(a.tx_index, b.tx_index);
+        assert_eq!(a.transaction, b.transaction);
+    }
+}
+
+#[test]
+fn reports_malformed_lines() {
+    assert!(parse_line("{\"slot\": 1}").is_err());
+    assert!(parse_line("not json").is_err());
+
+    let line = std::fs::read_to_string(FIXTURE)
+        .unwrap()
+        .lines()
+        .nth(1)
+        .unwrap()
+        .to_string();
+    let tx = parse_line(&line).unwrap();
+    assert_eq!(
+        parse_line(&to_line(&tx).unwrap()).unwrap().transaction,
+        tx.transaction
+    );
+}
@@ -0,0 +1,142 @@
+use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+use serde_json::{json, Value};
+use solana_openbook_indexer::rpc_pool::RpcPool;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// JSON-RPC stub that answers `getSlot` with a fixed slot, or HTTP 500 while failing
+struct StubRpc {
+    slot: AtomicU64,
+    failing: AtomicBool,
+    requests: AtomicU64,
+}
+
+async fn handle(
+    State(stub): State<Arc<StubRpc>>,
+    Json(request): Json<Value>,
+) -> Result<Json<Value>, StatusCode> {
+    stub.requests.fetch_add(1, Ordering::Relaxed);
+
+    if stub.failing.load(Ordering::Relaxed) {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    // The client asks for the node version before its first commitment-bearing request
+    let result = match request["method"].as_str() {
+        Some("getVersion") => json!({ "solana-core": "1.18.26", "feature-set": 0 }),
+        _ => json!(stub.slot.load(Ordering::Relaxed)),
+    };
+
+    Ok(Json(json!({
+        "jsonrpc": "2.0",
+        "id": request["id"],
+        "result": result,
+    })))
+}
+
+/// Start a stub on an ephemeral port, returning its state and URL
+async fn spawn_stub(slot: u64) -> (Arc<StubRpc>, String) {
+    let stub = Arc::new(StubRpc {
+        slot: AtomicU64::new(slot),
+        failing: AtomicBool::new(false),
+        requests: AtomicU64::new(0),
+    });
+
+    let app = Router::new()
+        .route("/", post(handle))
+        .with_state(stub.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    (stub, url)
+}
+
+async fn get_slot(pool: &RpcPool) -> u64 {
+    pool.call(|rpc| async move { rpc.get_slot().await })
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn routes_to_freshest_endpoint() {
+    let (_, stale_url) = spawn_stub(100).await;
+    let (_, fresh_url) = spawn_stub(1_000).await;
+
+    let pool = RpcPool::new(
+        vec![stale_url, fresh_url.clone()],
+        vec!["ws://stale".to_string(), "ws://fresh".to_string()],
+    )
+    .unwrap();
+    pool.check_health().await;
+
+    assert_eq!(pool.best().http_url, fresh_url);
+    assert_eq!(pool.ws_url().as_deref(), Some("ws://fresh"));
+    assert_eq!(get_slot(&pool).await, 1_000);
+}
+
+#[tokio::test]
+async fn fails_over_when_best_endpoint_errors() {
+    let (primary, primary_url) = spawn_stub(500).await;
+    let (backup, backup_url) = spawn_stub(500).await;
+
+    let pool = RpcPool::new(vec![primary_url.clone(), backup_url.clone()], vec![]).unwrap();
+    pool.check_health().await;
+    assert_eq!(pool.best().http_url, primary_url);
+
+    primary.failing.store(true, Ordering::Relaxed);
+    backup.slot.store(501, Ordering::Relaxed);
+
+    // The failing request is retried on the backup
+    let backup_requests = backup.requests.load(Ordering::Relaxed);
+    assert_eq!(get_slot(&pool).await, 501);
+    assert!(backup.requests.load(Ordering::Relaxed) > backup_requests);
+
+    // The error pushes the primary behind the backup for the next requests
+    assert_eq!(pool.best().http_url, backup_url);
+    assert_eq!(pool.endpoints()[0].health().consecutive_errors, 1);
+
+    let primary_requests = primary.requests.load(Ordering::Relaxed);
+    assert_eq!(get_slot(&pool).await, 501);
+    assert_eq!(primary.requests.load(Ordering::Relaxed), primary_requests);
+}
+
+#[tokio::test]
+async fn unreachable_endpoint_is_ranked_last() {
+    let (_, live_url) = spawn_stub(42).await;
+
+    // Nothing listens on the discard port
+    let pool = RpcPool::new(
+        vec!["http://127.0.0.1:9".to_string(), live_url.clone()],
+        vec![],
+    )
+    .unwrap();
+    pool.check_health().await;
+
+    assert_eq!(pool.best().http_url, live_url);
+    assert_eq!(pool.endpoints()[0].health().consecutive_errors, 1);
+    assert_eq!(get_slot(&pool).await, 42);
+}
+
+#[tokio::test]
+async fn returns_error_when_every_endpoint_fails() {
+    let (a, a_url) = spawn_stub(1).await;
+    let (b, b_url) = spawn_stub(1).await;
+    a.failing.store(true, Ordering::Relaxed);
+    b.failing.store(true, Ordering::Relaxed);
+
+    let pool = RpcPool::new(vec![a_url, b_url], vec![]).unwrap();
+
+    let result = pool.call(|rpc| async move { rpc.get_slot().await }).await;
+    assert!(result.is_err());
+    assert_eq!(a.requests.load(Ordering::Relaxed), 1);
+    assert_eq!(b.requests.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn rejects_empty_pool() {
+    assert!(RpcPool::new(vec![], vec![]).is_err());
+}
@@ -0,0 +1,65 @@
+use solana_openbook_indexer::shutdown::Shutdown;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, Notify};
+
+#[tokio::test]
+async fn waits_are_interrupted_once_shutdown_is_requested() {
+    let (sender, shutdown) = Shutdown::channel();
+    assert!(!shutdown.is_requested());
+    assert_eq!(shutdown.until_requested(async { 1 }).await, Some(1));
+
+    sender.send(true).unwrap();
+    assert!(shutdown.is_requested());
+    assert_eq!(
+        shutdown.until_requested(std::future::pending::<()>()).await,
+        None
+    );
+    // Even work that is ready is no longer taken
+    assert_eq!(shutdown.until_requested(async { 2 }).await, None);
+}
+
+#[tokio::test]
+async fn work_in_flight_is_drained_before_stopping() {
+    let (sender, shutdown) = Shutdown::channel();
+    let (work, mut queue) = mpsc::unbounded_channel();
+    for item in 1..=3 {
+        work.send(item).unwrap();
+    }
+
+    let started = Arc::new(Notify::new());
+    let done = Arc::new(Mutex::new(Vec::new()));
+    let worker = tokio::spawn({
+        let (started, done) = (started.clone(), done.clone());
+        async move {
+            while let Some(Some(item)) = shutdown.until_requested(queue.recv()).await {
+                started.notify_one();
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                done.lock().unwrap().push(item);
+            }
+        }
+    });
+
+    // Requested while the first item is being handled
+    started.notified().await;
+    sender.send(true).unwrap();
+    worker.await.unwrap();
+
+    assert_eq!(*done.lock().unwrap(), vec![1]);
+}
+
+#[tokio::test]
+async fn nothing_is_interrupted_without_anyone_to_request_shutdown() {
+    let (sender, shutdown) = Shutdown::channel();
+    drop(sender);
+
+    let next = tokio::time::timeout(
+        Duration::from_secs(1),
+        shutdown.until_requested(async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            3
+        }),
+    )
+    .await;
+    assert_eq!(next, Ok(Some(3)));
+}
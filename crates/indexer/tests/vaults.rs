@@ -0,0 +1,91 @@
+mod common;
+
+use solana_openbook_indexer::programs::MarketLayout;
+use solana_openbook_indexer::vaults::{
+    base_native_in_quote, parse_market_vaults, parse_token_amount, tvl_in_quote,
+};
+use solana_sdk::pubkey::Pubkey;
+
+use common::put;
+
+/// Market account with vaults, deposit totals and lot sizes at the v2 offsets
+fn market_account(base_vault: &Pubkey, quote_vault: &Pubkey) -> Vec<u8> {
+    let mut data = vec![0u8; 840];
+    put(&mut data, 448, &10i64.to_le_bytes());
+    put(&mut data, 456, &1_000_000i64.to_le_bytes());
+    put(&mut data, 640, base_vault.as_ref());
+    put(&mut data, 672, &7_500_000_000u64.to_le_bytes());
+    put(&mut data, 680, quote_vault.as_ref());
+    put(&mut data, 712, &125_000_000u64.to_le_bytes());
+    data
+}
+
+/// SPL token account holding `amount`
+fn token_account(amount: u64) -> Vec<u8> {
+    let mut data = vec![0u8; 165];
+    put(&mut data, 0, Pubkey::new_unique().as_ref());
+    put(&mut data, 32, Pubkey::new_unique().as_ref());
+    put(&mut data, 64, &amount.to_le_bytes());
+    data
+}
+
+#[test]
+fn decodes_vaults_and_deposit_totals() {
+    let base_vault = Pubkey::new_unique();
+    let quote_vault = Pubkey::new_unique();
+
+    let data = market_account(&base_vault, &quote_vault);
+    let vaults = parse_market_vaults(&data, MarketLayout::OpenBookV2).unwrap();
+    assert_eq!(vaults.base_vault, base_vault);
+    assert_eq!(vaults.quote_vault, quote_vault);
+    assert_eq!(vaults.base_deposit_total, 7_500_000_000);
+    assert_eq!(vaults.quote_deposit_total, 125_000_000);
+    assert_eq!(vaults.quote_lot_size, 10);
+    assert_eq!(vaults.base_lot_size, 1_000_000);
+
+    assert!(parse_market_vaults(&[0u8; 719], MarketLayout::OpenBookV2).is_err());
+
+    // Vault offsets of the fork layout are unknown: its markets are not read
+    assert!(parse_market_vaults(&data, MarketLayout::CustomFork).is_err());
+}
+
+#[test]
+fn reads_token_amounts() {
+    assert_eq!(parse_token_amount(&token_account(42_000)), Some(42_000));
+    assert_eq!(parse_token_amount(&token_account(0)), Some(0));
+    assert_eq!(parse_token_amount(&[0u8; 71]), None);
+}
+
+#[test]
+fn values_base_in_quote_at_a_lot_price() {
+    let data = market_account(&Pubkey::new_unique(), &Pubkey::new_unique());
+    let vaults = parse_market_vaults(&data, MarketLayout::OpenBookV2).unwrap();
+
+    // 7.5 base lots at 2_000 quote lots of 10 native each
+    assert_eq!(
+        base_native_in_quote(7_500_000, 2_000, &vaults),
+        Some(150_000)
+    );
+    assert_eq!(base_native_in_quote(7_500_000, -1, &vaults), Some(0));
+
+    let mut unset = vaults.clone();
+    unset.base_lot_size = 0;
+    assert_eq!(base_native_in_quote(7_500_000, 2_000, &unset), Some(0));
+}
+
+#[test]
+fn tvl_that_overflows_is_unknown() {
+    let data = market_account(&Pubkey::new_unique(), &Pubkey::new_unique());
+    let vaults = parse_market_vaults(&data, MarketLayout::OpenBookV2).unwrap();
+
+    assert_eq!(
+        tvl_in_quote(7_500_000, 50_000, 2_000, &vaults),
+        Some(200_000)
+    );
+    assert_eq!(tvl_in_quote(u64::MAX, 0, i64::MAX, &vaults), None);
+    assert_eq!(tvl_in_quote(0, u64::MAX, 2_000, &vaults), None);
+    assert_eq!(
+        tvl_in_quote(7_500_000, i64::MAX as u64, 2_000, &vaults),
+        None
+    );
+}